@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+pub static HELP: &str = "\
+.chain [CHAINS]           Run queries without ON on CHAINS, e.g. `.chain sui_testnet`, no CHAINS goes back to the chains the REPL started on
+.format table|json|csv    Print results as tables, JSON or CSV
+.output [FILE]            Write results to FILE, no FILE prints them again
+.timing on|off            Print how long every program takes
+.explain [QUERY]          Explain QUERY, or the last program without one
+.describe ENTITY          List the fields of ENTITY
+.history                  List the history
+.save FILE                Save the programs of the session to FILE
+.run FILE                 Run the programs in FILE
+.help                     List the commands";
+
+/// The names of the commands, for completion.
+pub static COMMANDS: [&str; 10] = [
+    "chain", "format", "output", "timing", "explain", "describe", "history", "save", "run", "help",
+];
+
+/// How the REPL prints results.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+/// A line of the REPL starting with `.` or `\`, which changes the session instead of being
+/// run by the interpreter.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Chain(Option<String>),
+    Format(OutputFormat),
+    Output(Option<PathBuf>),
+    Timing(bool),
+    Explain(Option<String>),
+    Describe(String),
+    History,
+    Save(PathBuf),
+    Run(PathBuf),
+    Help,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("Unknown command `.{0}`, `.help` lists the commands")]
+    UnknownCommand(String),
+    #[error("`.{command}` expects {expected}")]
+    InvalidArgument {
+        command: &'static str,
+        expected: &'static str,
+    },
+}
+
+impl Command {
+    pub fn is_command(input: &str) -> bool {
+        input.trim_start().starts_with(['.', '\\'])
+    }
+}
+
+impl TryFrom<&str> for Command {
+    type Error = CommandError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        // A `;` out of habit doesn't belong to the command.
+        let input = input
+            .trim()
+            .trim_start_matches(['.', '\\'])
+            .trim_end_matches(';')
+            .trim_end();
+        let (name, argument) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let argument = argument.trim();
+        let argument = (!argument.is_empty()).then(|| argument.to_string());
+
+        let expect = |command, expected| CommandError::InvalidArgument { command, expected };
+        match name.to_lowercase().as_str() {
+            "chain" => Ok(Command::Chain(argument)),
+            "format" => match argument.as_deref() {
+                Some("table") => Ok(Command::Format(OutputFormat::Table)),
+                Some("json") => Ok(Command::Format(OutputFormat::Json)),
+                Some("csv") => Ok(Command::Format(OutputFormat::Csv)),
+                _ => Err(expect("format", "`table`, `json` or `csv`")),
+            },
+            "output" => Ok(Command::Output(argument.map(PathBuf::from))),
+            "timing" => match argument.as_deref() {
+                Some("on") => Ok(Command::Timing(true)),
+                Some("off") => Ok(Command::Timing(false)),
+                _ => Err(expect("timing", "`on` or `off`")),
+            },
+            "explain" => Ok(Command::Explain(argument)),
+            "describe" => argument
+                .map(Command::Describe)
+                .ok_or(expect("describe", "an entity")),
+            "history" => Ok(Command::History),
+            "save" => argument
+                .map(|file| Command::Save(file.into()))
+                .ok_or(expect("save", "a file")),
+            "run" => argument
+                .map(|file| Command::Run(file.into()))
+                .ok_or(expect("run", "a file")),
+            "help" => Ok(Command::Help),
+            _ => Err(CommandError::UnknownCommand(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let parse = |input: &str| Command::try_from(input);
+
+        assert_eq!(parse(".help"), Ok(Command::Help));
+        assert_eq!(parse("\\help"), Ok(Command::Help));
+        assert_eq!(parse("  .HELP  "), Ok(Command::Help));
+        assert_eq!(
+            parse(".FORMAT json"),
+            Ok(Command::Format(OutputFormat::Json))
+        );
+        assert_eq!(parse(".help;"), Ok(Command::Help));
+        assert_eq!(
+            parse(".format csv;"),
+            Ok(Command::Format(OutputFormat::Csv))
+        );
+        assert_eq!(parse(".timing on ;"), Ok(Command::Timing(true)));
+        assert_eq!(parse("\\timing off"), Ok(Command::Timing(false)));
+        assert_eq!(
+            parse(".chain sui_testnet, sui_devnet;"),
+            Ok(Command::Chain(Some("sui_testnet, sui_devnet".to_string())))
+        );
+        assert_eq!(parse(".chain;"), Ok(Command::Chain(None)));
+        assert_eq!(parse(".output"), Ok(Command::Output(None)));
+        assert_eq!(
+            parse(".run queries.sql;"),
+            Ok(Command::Run(PathBuf::from("queries.sql")))
+        );
+        assert_eq!(
+            parse(".explain SELECT * FROM tx WHERE checkpoint = 1;"),
+            Ok(Command::Explain(Some(
+                "SELECT * FROM tx WHERE checkpoint = 1".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_invalid_commands() {
+        let parse = |input: &str| Command::try_from(input);
+
+        assert_eq!(
+            parse(".format yaml"),
+            Err(CommandError::InvalidArgument {
+                command: "format",
+                expected: "`table`, `json` or `csv`",
+            })
+        );
+        assert_eq!(
+            parse(".timing"),
+            Err(CommandError::InvalidArgument {
+                command: "timing",
+                expected: "`on` or `off`",
+            })
+        );
+        assert_eq!(
+            parse(".timing yes"),
+            Err(CommandError::InvalidArgument {
+                command: "timing",
+                expected: "`on` or `off`",
+            })
+        );
+        assert_eq!(
+            parse(".save ;"),
+            Err(CommandError::InvalidArgument {
+                command: "save",
+                expected: "a file",
+            })
+        );
+        assert_eq!(
+            parse("\\run"),
+            Err(CommandError::InvalidArgument {
+                command: "run",
+                expected: "a file",
+            })
+        );
+        assert_eq!(
+            parse(".describe"),
+            Err(CommandError::InvalidArgument {
+                command: "describe",
+                expected: "an entity",
+            })
+        );
+        assert_eq!(
+            parse(".quit"),
+            Err(CommandError::UnknownCommand("quit".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_is_command() {
+        assert!(Command::is_command("  .help"));
+        assert!(Command::is_command("\\timing on"));
+        assert!(!Command::is_command(
+            "SELECT * FROM tx WHERE checkpoint = 1"
+        ));
+    }
+}
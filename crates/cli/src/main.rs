@@ -1,3 +1,4 @@
+mod command;
 mod repl;
 
 use crate::repl::Repl;
@@ -6,8 +7,13 @@ use csv::ReaderBuilder;
 use serde::Serialize;
 use std::error::Error;
 use sui_ql_core::{
-    common::query_result::{ExpressionResult, QueryResult},
-    interpreter::Interpreter,
+    common::{
+        chain::ChainOrRpc,
+        config::Config,
+        params::{parse_param, Params},
+        query_result::{ExpressionResult, QueryResult, RowQueryRes},
+    },
+    interpreter::{frontend::formatter::format_program, Interpreter},
 };
 use tabled::{builder::Builder, settings::Style, Table};
 
@@ -20,6 +26,10 @@ use tabled::{builder::Builder, settings::Style, Table};
 struct Arguments {
     #[clap(subcommand)]
     subcmd: SubCommand,
+
+    /// Runs the queries without `ON` on these chains, e.g. `--chain sui_testnet`
+    #[clap(long, global = true)]
+    chain: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -29,11 +39,28 @@ enum SubCommand {
 
     #[clap(name = "repl", about = "Start an interactive REPL")]
     Repl,
+
+    #[clap(name = "fmt", about = "Format .suiql files in place")]
+    Fmt(FmtArguments),
 }
 
 #[derive(Debug, Parser)]
 struct RunArguments {
     file: String,
+
+    /// Binds a `$1` or `:name` placeholder, e.g. `--param addr=0x..`
+    #[clap(long = "param", value_parser = parse_param)]
+    params: Vec<(String, String)>,
+}
+
+#[derive(Debug, Parser)]
+struct FmtArguments {
+    #[clap(required = true)]
+    files: Vec<String>,
+
+    /// Lists the files that aren't formatted instead of formatting them
+    #[clap(long)]
+    check: bool,
 }
 
 struct ResultHandler;
@@ -61,6 +88,12 @@ impl ResultHandler {
                 ExpressionResult::Object(objects_res) => {
                     println!("{}", to_table(objects_res)?);
                 }
+                ExpressionResult::Name(names_res) => {
+                    println!("{}", to_table(names_res)?);
+                }
+                ExpressionResult::Join(rows) | ExpressionResult::Rows(rows) => {
+                    println!("{}", rows_table(rows));
+                }
             }
         }
 
@@ -96,16 +129,43 @@ pub fn to_table<S: Serialize + core::fmt::Debug>(data: Vec<S>) -> Result<Table,
     Ok(table)
 }
 
+/// Join and computed rows have dynamic columns, so their table is built from the rows instead of through CSV.
+pub fn rows_table(rows: Vec<RowQueryRes>) -> Table {
+    let mut builder = Builder::default();
+
+    if let Some(first_row) = rows.first() {
+        builder.push_record(first_row.header().into_iter().map(String::from));
+    }
+    for row in &rows {
+        builder.push_record(row.values());
+    }
+
+    let mut table = builder.build();
+    table.with(Style::rounded());
+    table
+}
+
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
     let args = Arguments::parse();
+    let chains = match args.chain.as_deref() {
+        Some(selector) => Some(ChainOrRpc::from_selector(
+            selector,
+            &Config::load().get_networks()?,
+        )?),
+        None => None,
+    };
 
     match args.subcmd {
         SubCommand::Run(run_args) => {
             let source = std::fs::read_to_string(run_args.file)?;
+            let params: Params = run_args.params.into_iter().collect();
             let result_handler = ResultHandler::new();
-            let result = Interpreter::run_program(&source).await;
+            let result = match Interpreter::parse_program(&source, &params, chains) {
+                Ok(expressions) => Interpreter::run_expressions(expressions).await,
+                Err(e) => Err(e),
+            };
             match result {
                 Ok(query_results) => {
                     result_handler.handle_result(query_results)?;
@@ -116,7 +176,29 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
             }
         }
         SubCommand::Repl => {
-            Repl::new().run().await?;
+            Repl::new(chains)?.run().await?;
+        }
+        SubCommand::Fmt(fmt_args) => {
+            let networks = Config::load().get_networks().unwrap_or_default();
+            let mut failed = false;
+            for file in fmt_args.files {
+                let source = std::fs::read_to_string(&file)?;
+                match format_program(&source, &networks) {
+                    Ok(formatted) if formatted == source => {}
+                    Ok(_) if fmt_args.check => {
+                        println!("{}", file);
+                        failed = true;
+                    }
+                    Ok(formatted) => std::fs::write(&file, formatted)?,
+                    Err(e) => {
+                        eprintln!("{}: {}", file, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
         }
     }
 
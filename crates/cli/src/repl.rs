@@ -1,269 +1,402 @@
-use crate::to_table;
-use crossterm::{
-    cursor::{MoveLeft, MoveRight, MoveTo, MoveToColumn, MoveToNextLine},
-    event::{read, Event, KeyCode, KeyModifiers},
-    execute, queue,
-    style::{Print, Stylize},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+use crate::{
+    command::{Command, OutputFormat, COMMANDS, HELP},
+    rows_table, to_table,
+};
+use crossterm::style::{Color, Stylize};
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::FileHistory,
+    validate::{ValidationContext, ValidationResult, Validator},
+    Context, Editor, Helper,
+};
+use std::{
+    borrow::Cow,
+    env,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::Instant,
 };
-use std::io::{stdout, Stdout, Write};
 use sui_ql_core::{
-    common::query_result::{ExpressionResult, QueryResult},
-    interpreter::Interpreter,
+    common::{
+        chain::ChainOrRpc,
+        config::Config,
+        dump::DumpFormat,
+        literal::strip_comments,
+        params::Params,
+        query_result::{ExpressionResult, QueryResult},
+        serializer::serialize_result,
+        types::Expression,
+    },
+    interpreter::{
+        frontend::{
+            completion::{complete, word_start},
+            formatter::{highlights, Highlight},
+        },
+        Interpreter,
+    },
 };
+use tabled::Table;
 
 static REPL_LABEL: &str = "SUI_QL >";
+/// Kept in the home directory, so the history is shared between sessions.
+static HISTORY_FILE: &str = ".sui_ql_history";
 
 pub struct Repl {
-    history: Vec<String>,
-    history_offset: usize,
-    stdout: Stdout,
-    cursor_pos: usize,
-    expression: String,
+    editor: Editor<ReplHelper, FileHistory>,
+    history_path: Option<PathBuf>,
+    /// The chains the REPL was started with, which a bare `.chain` goes back to.
+    startup_chains: Option<Vec<ChainOrRpc>>,
+    session: Session,
+}
+
+/// What the commands of the REPL set for the rest of the session.
+#[derive(Default)]
+struct Session {
+    /// The chains of the queries without `ON`.
+    chains: Option<Vec<ChainOrRpc>>,
+    format: OutputFormat,
+    /// The file results are written to instead of the terminal.
+    output: Option<PathBuf>,
+    timing: bool,
+    /// The programs run so far, which `.save` writes and `.explain` explains the last of.
+    programs: Vec<String>,
 }
 
 impl Repl {
-    pub fn new() -> Self {
-        Repl {
-            history: vec![],
-            stdout: stdout(),
-            cursor_pos: 1,
-            expression: String::new(),
-            history_offset: 0,
+    /// Queries without `ON` run on `chains` until `.chain` changes them.
+    pub fn new(chains: Option<Vec<ChainOrRpc>>) -> Result<Self, Box<dyn Error>> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(ReplHelper));
+
+        let history_path = env::var("HOME")
+            .ok()
+            .map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(history_path) = &history_path {
+            // There is no history file before the first session.
+            let _ = editor.load_history(history_path);
         }
+
+        Ok(Repl {
+            editor,
+            history_path,
+            startup_chains: chains.clone(),
+            session: Session {
+                chains,
+                ..Session::default()
+            },
+        })
     }
 
     // The main loop for the REPL session
-    pub async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Clear the screen and enable raw mode for the terminal
-        self.clear_screen()?;
-        enable_raw_mode()?;
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        println!("SUI_QL REPL - End statements with `;`, `.help` lists the commands, press Ctrl-D to exit");
 
         loop {
-            // Read an event from the terminal
-            let event = read()?;
-
-            match event {
-                Event::Key(key_event) => match key_event.code {
-                    // Handle character input
-                    KeyCode::Char(ch) => {
-                        // Exit on Ctrl+C
-                        if key_event.modifiers == KeyModifiers::CONTROL && ch == 'c' {
-                            break;
-                        // Clear screen on Ctrl+L
-                        } else if key_event.modifiers == KeyModifiers::CONTROL && ch == 'l' {
-                            self.cursor_pos = 1;
-                            self.clear_screen()?;
-                            continue;
-                        // Clear current expression on Ctrl+U
-                        } else if key_event.modifiers == KeyModifiers::CONTROL && ch == 'u' {
-                            self.cursor_pos = 1;
-                            self.expression.clear();
-                            self.redraw_line()?;
-                        // Insert character into the current expression
-                        } else {
-                            self.expression.insert(self.cursor_pos - 1, ch);
-                            self.cursor_pos += 1;
-                            self.redraw_line()?;
-                        }
-                    }
-                    // Handle backspace
-                    KeyCode::Backspace => {
-                        // Delete word on Alt+Backspace
-                        if key_event.modifiers == KeyModifiers::ALT {
-                            let mut temp = self.expression.split(" ").collect::<Vec<&str>>();
-
-                            if let Some(removed) = temp.pop() {
-                                if temp.len() == 0 {
-                                    continue;
-                                } else {
-                                    let removed_len = removed.len() + 1; // +1 for the space
-                                    self.cursor_pos -= removed_len;
-                                    self.expression = temp.join(" ");
-                                }
-                            }
-                        // Delete character on Backspace
-                        } else {
-                            if self.cursor_pos > 1 {
-                                self.expression.remove(self.cursor_pos - 2); // -2 because it's the previous index and cursor start at 1 while string indexes at 0.
-                            }
-
-                            if self.cursor_pos > 1 {
-                                self.cursor_pos -= 1;
-                            }
-                        }
-
-                        self.redraw_line()?;
-                    }
-                    // Delete character on Delete
-                    KeyCode::Delete => {
-                        if self.cursor_pos - 1 < self.expression.len() {
-                            self.expression.remove(self.cursor_pos - 1); // -1 because cursor start at 1 while string indexes at 0.
-                            self.redraw_line()?;
-                        }
-                    }
-                    // Handle Enter key to execute the current expression
-                    KeyCode::Enter => {
-                        // Run the current expression and handle any errors
-                        let _ = self.run_expression().await.map_err(|e| {
-                            for line in e.to_string().split("\n") {
-                                queue!(self.stdout, MoveToNextLine(1), Print(line.red()),).unwrap();
-                            }
-                        });
-
-                        // Add the current expression to history and reset the state
-                        self.history.push(self.expression.trim().to_string());
-                        self.expression.clear();
-                        self.cursor_pos = 1;
-                        self.history_offset = 0;
-
-                        // Display the REPL prompt
-                        queue!(
-                            self.stdout,
-                            MoveToNextLine(1),
-                            Print(REPL_LABEL.italic().dark_grey().on_dark_yellow()),
-                            Print(" "),
-                            Print(&self.expression),
-                        )?;
-                    }
-                    // Handle Up arrow key to navigate history
-                    KeyCode::Up => {
-                        if self.history.len() == 0 || self.history_offset >= self.history.len() {
-                            continue;
-                        }
-
-                        self.history_offset += 1;
-                        self.expression =
-                            self.history[self.history.len() - self.history_offset].clone();
-                        self.cursor_pos = self.expression.len() + 1;
-                        self.redraw_line()?;
+            match self.editor.readline(&format!("{REPL_LABEL} ")) {
+                Ok(input) => {
+                    let input = input.trim();
+                    if input.is_empty() {
+                        continue;
                     }
-                    // Handle Down arrow key to navigate history
-                    KeyCode::Down => {
-                        if self.history_offset == 0 {
-                            continue;
-                        }
 
-                        self.history_offset -= 1;
-                        self.expression = if self.history_offset == 0 {
-                            self.cursor_pos = 1;
-                            "".to_string()
-                        } else {
-                            let temp =
-                                self.history[self.history.len() - self.history_offset].clone();
-                            self.cursor_pos = temp.len() + 1;
-                            temp
-                        };
-
-                        self.redraw_line()?;
-                    }
-                    // Handle Left arrow key to move cursor left
-                    KeyCode::Left => {
-                        if self.cursor_pos > 0 {
-                            self.cursor_pos -= 1;
-                        }
-                        queue!(self.stdout, MoveLeft(1))?;
-                    }
-                    // Handle Right arrow key to move cursor right
-                    KeyCode::Right => {
-                        if self.cursor_pos - 1 < self.expression.len() {
-                            self.cursor_pos += 1;
-                        }
-                        queue!(self.stdout, MoveRight(1))?;
+                    self.editor.add_history_entry(input)?;
+                    let result = match Command::is_command(input) {
+                        true => match Command::try_from(input) {
+                            Ok(command) => self.run_command(command).await,
+                            Err(e) => Err(e.into()),
+                        },
+                        false if strip_comments(input).trim().is_empty() => continue,
+                        false => self.run_program(input).await,
+                    };
+                    if let Err(e) = result {
+                        println!("{}", e.to_string().red());
                     }
-                    // Exit on Esc key
-                    KeyCode::Esc => break,
-                    _ => {}
-                },
-                _ => {}
+                }
+                // Ctrl-C drops the statement being written
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
             }
-
-            // Flush the stdout buffer to apply changes
-            self.stdout.flush()?;
         }
 
-        // Disable raw mode before exiting
-        disable_raw_mode()?;
+        if let Some(history_path) = &self.history_path {
+            self.editor.save_history(history_path)?;
+        }
 
         Ok(())
     }
 
-    fn redraw_line(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let expression = self.expression.clone();
-        let cursor_pos = self.cursor_pos;
+    async fn run_command(&mut self, command: Command) -> Result<(), Box<dyn Error>> {
+        match command {
+            Command::Chain(Some(selector)) => {
+                let chains = ChainOrRpc::from_selector(&selector, &Config::load().get_networks()?)?;
+                print_chains(&chains);
+                self.session.chains = Some(chains);
+            }
+            Command::Chain(None) => {
+                match &self.startup_chains {
+                    Some(chains) => print_chains(chains),
+                    None => print_chains(&ChainOrRpc::defaults()?),
+                }
+                self.session.chains = self.startup_chains.clone();
+            }
+            Command::Format(format) => self.session.format = format,
+            Command::Output(Some(path)) => {
+                // The file starts empty, results are appended to it until the next `.output`.
+                File::create(&path)?;
+                match path.extension().and_then(|extension| extension.to_str()) {
+                    Some("json") => self.session.format = OutputFormat::Json,
+                    Some("csv") => self.session.format = OutputFormat::Csv,
+                    _ => {}
+                }
+                self.session.output = Some(path);
+            }
+            Command::Output(None) => self.session.output = None,
+            Command::Timing(timing) => self.session.timing = timing,
+            Command::Explain(query) => {
+                let source = match query {
+                    Some(query) => query,
+                    None => self
+                        .session
+                        .programs
+                        .last()
+                        .cloned()
+                        .ok_or("There is no query to explain yet")?,
+                };
+                let expressions = self
+                    .parse_program(&source)?
+                    .into_iter()
+                    .filter_map(|expression| match expression {
+                        Expression::Get(get) | Expression::Explain(get) => {
+                            Some(Expression::Explain(get))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                self.run_expressions(expressions).await?;
+            }
+            Command::Describe(entity) => {
+                let expressions = self.parse_program(&format!("DESCRIBE {entity}"))?;
+                self.run_expressions(expressions).await?;
+            }
+            Command::History => {
+                for (i, entry) in self.editor.history().iter().enumerate() {
+                    println!("{:>5}  {entry}", i + 1);
+                }
+            }
+            Command::Save(path) => {
+                let mut content = self.session.programs.join("\n");
+                content.push('\n');
+                fs::write(&path, content)?;
+                print_note(&format!(
+                    "Saved {} programs to {}",
+                    self.session.programs.len(),
+                    path.display()
+                ));
+            }
+            Command::Run(path) => {
+                let source = fs::read_to_string(path)?;
+                self.run_program(&source).await?;
+            }
+            Command::Help => println!("{HELP}"),
+        }
 
-        queue!(
-            self.stdout,
-            MoveToColumn(0),
-            Clear(ClearType::CurrentLine),
-            Print(REPL_LABEL.italic().dark_grey().on_dark_yellow()),
-            Print(" "),
-            Print(expression),
-            MoveToColumn(cursor_pos as u16 + 5),
-        )?;
+        Ok(())
+    }
 
+    async fn run_program(&mut self, source: &str) -> Result<(), Box<dyn Error>> {
+        let expressions = self.parse_program(source)?;
+        self.run_expressions(expressions).await?;
+        self.session.programs.push(source.trim().to_string());
         Ok(())
     }
 
-    async fn run_expression(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let result = Interpreter::run_program(&self.expression).await?;
+    fn parse_program(&self, source: &str) -> Result<Vec<Expression>, Box<dyn Error>> {
+        let expressions =
+            Interpreter::parse_program(source, &Params::default(), self.session.chains.clone())?;
+        Ok(expressions)
+    }
+
+    async fn run_expressions(&self, expressions: Vec<Expression>) -> Result<(), Box<dyn Error>> {
+        let start = Instant::now();
+        let result = Interpreter::run_expressions(expressions).await?;
+        let elapsed = start.elapsed();
+
         self.display_result(result)?;
+        if self.session.timing {
+            print_note(&format!("Time: {:.3}s", elapsed.as_secs_f64()));
+        }
         Ok(())
     }
 
-    fn display_result(
-        &self,
-        query_results: Vec<QueryResult>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    fn display_result(&self, query_results: Vec<QueryResult>) -> Result<(), Box<dyn Error>> {
         for query_result in query_results {
-            match query_result.result {
-                ExpressionResult::Account(query_res) => {
-                    let table = to_table(query_res)?;
-                    table.to_string().split("\n").for_each(|line| {
-                        queue!(stdout(), MoveToNextLine(1), Print(line.green())).unwrap();
-                    });
-                }
-                ExpressionResult::Checkpoint(query_res) => {
-                    let table = to_table(query_res)?;
-                    table.to_string().split("\n").for_each(|line| {
-                        queue!(stdout(), MoveToNextLine(1), Print(line.cyan())).unwrap();
-                    });
-                }
-                ExpressionResult::Transaction(query_res) => {
-                    let table = to_table(query_res)?;
-                    table.to_string().split("\n").for_each(|line| {
-                        queue!(stdout(), MoveToNextLine(1), Print(line.yellow())).unwrap();
-                    });
-                }
-                ExpressionResult::Coin(items) => {
-                    let table = to_table(items)?;
-                    table.to_string().split("\n").for_each(|line| {
-                        queue!(stdout(), MoveToNextLine(1), Print(line.magenta())).unwrap();
-                    })
+            let result = query_result.result;
+            let content = match self.session.format {
+                OutputFormat::Table => {
+                    let (table, color) = result_table(result)?;
+                    match &self.session.output {
+                        Some(_) => format!("{table}\n").into_bytes(),
+                        None => {
+                            print_table(table, color);
+                            continue;
+                        }
+                    }
                 }
-                ExpressionResult::Object(items) => {
-                    let table = to_table(items)?;
-                    table.to_string().split("\n").for_each(|line| {
-                        queue!(stdout(), MoveToNextLine(1), Print(line.magenta())).unwrap();
-                    })
+                OutputFormat::Json => {
+                    let mut content = serialize_result(&result, &DumpFormat::Json)?;
+                    content.push(b'\n');
+                    content
                 }
+                OutputFormat::Csv => serialize_result(&result, &DumpFormat::Csv)?,
+            };
+
+            match &self.session.output {
+                Some(path) => OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path)?
+                    .write_all(&content)?,
+                None => io::stdout().write_all(&content)?,
             }
         }
 
         Ok(())
     }
+}
+
+fn result_table(result: ExpressionResult) -> Result<(Table, Color), Box<dyn Error>> {
+    Ok(match result {
+        ExpressionResult::Account(query_res) => (to_table(query_res)?, Color::Green),
+        ExpressionResult::Checkpoint(query_res) => (to_table(query_res)?, Color::Cyan),
+        ExpressionResult::Transaction(query_res) => (to_table(query_res)?, Color::Yellow),
+        ExpressionResult::Coin(items) => (to_table(items)?, Color::Magenta),
+        ExpressionResult::Object(items) => (to_table(items)?, Color::Magenta),
+        ExpressionResult::Name(items) => (to_table(items)?, Color::Blue),
+        ExpressionResult::Join(rows) | ExpressionResult::Rows(rows) => {
+            (rows_table(rows), Color::White)
+        }
+    })
+}
 
-    fn clear_screen(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        execute!(
-            self.stdout,
-            Clear(ClearType::All),
-            MoveTo(0, 0),
-            Print("SUI_QL REPL - Press ESC to exit"),
-            MoveToNextLine(1),
-            Print("SUI_QL >".italic().dark_grey().on_dark_yellow()),
-            Print(" "),
-        )?;
+fn print_chains(chains: &[ChainOrRpc]) {
+    let chains = chains.iter().map(ToString::to_string).collect::<Vec<_>>();
+    print_note(&format!("Queries without ON run on {}", chains.join(", ")));
+}
 
-        Ok(())
+fn print_note(note: &str) {
+    println!("{}", note.dark_grey());
+}
+
+fn print_table(table: Table, color: Color) {
+    for line in table.to_string().lines() {
+        println!("{}", line.with(color));
+    }
+}
+
+/// Completes, highlights and validates the input of the REPL with the frontend of the
+/// interpreter.
+struct ReplHelper;
+
+impl Helper for ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        // The name of a command, the arguments of commands aren't completed.
+        if Command::is_command(line) && !line[..pos].contains(char::is_whitespace) {
+            let prefix = &line[1..pos];
+            let candidates = COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(prefix))
+                .map(|command| Pair {
+                    display: command.to_string(),
+                    replacement: command.to_string(),
+                })
+                .collect();
+            return Ok((1, candidates));
+        }
+
+        let candidates = complete(line, pos)
+            .into_iter()
+            .map(|completion| Pair {
+                display: completion.label.clone(),
+                replacement: completion.label,
+            })
+            .collect();
+        Ok((word_start(line, pos), candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if Command::is_command(line) {
+            return Cow::Borrowed(line);
+        }
+
+        let mut highlighted = String::with_capacity(line.len());
+        let mut end = 0;
+
+        for (range, highlight) in highlights(line) {
+            highlighted.push_str(&line[end..range.start]);
+            let text = &line[range.clone()];
+            let styled = match highlight {
+                Highlight::Keyword => text.blue().bold(),
+                Highlight::String => text.green(),
+                Highlight::Number => text.magenta(),
+                Highlight::Operator => text.yellow(),
+                Highlight::Comment => text.dark_grey().italic(),
+            };
+            highlighted.push_str(&styled.to_string());
+            end = range.end;
+        }
+        highlighted.push_str(&line[end..]);
+
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_prompt<'b, 's: 'b, 'p: 'b>(
+        &'s self,
+        prompt: &'p str,
+        _default: bool,
+    ) -> Cow<'b, str> {
+        Cow::Owned(format!(
+            "{} ",
+            prompt.trim_end().italic().dark_grey().on_dark_yellow()
+        ))
+    }
+
+    // Keywords are only known once their word is complete, so the line is highlighted again
+    // on every change.
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+impl Validator for ReplHelper {
+    // Statements run once they end with `;`, so they can span several lines. Commands are a
+    // single line.
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = strip_comments(ctx.input());
+        let input = input.trim();
+        match input.is_empty() || input.ends_with(';') || Command::is_command(input) {
+            true => Ok(ValidationResult::Valid(None)),
+            false => Ok(ValidationResult::Incomplete),
+        }
     }
 }
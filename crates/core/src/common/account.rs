@@ -1,4 +1,15 @@
-use super::name_services::{NSError, NameOrAddress};
+use super::{
+    entity::QueryText,
+    entity_id::unique_ids,
+    expression::add_fields,
+    join::JoinEntity,
+    literal::unquote,
+    name_services::{NSError, NameOrAddress},
+    subquery::{
+        apply_in_ids, parse_in_values, take_subquery_filters, InFilter, InFilterError,
+        InFilterTarget, Subquery,
+    },
+};
 use crate::interpreter::frontend::parser::Rule;
 use eql_macros::EnumVariants;
 use pest::iterators::{Pair, Pairs};
@@ -18,9 +29,12 @@ pub enum AccountError {
 
     #[error(transparent)]
     NSError(#[from] NSError),
+
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Account {
     id: Option<Vec<NameOrAddress>>,
     filter: Option<Vec<AccountFilter>>,
@@ -47,6 +61,33 @@ impl Account {
     pub fn fields(&self) -> Vec<AccountField> {
         self.fields.clone()
     }
+
+    /// The accounts to fetch, from the id list and the `address = ...` filters.
+    pub fn addresses(&self) -> Vec<NameOrAddress> {
+        let filter_addresses = self.filter.iter().flatten().filter_map(|f| match f {
+            AccountFilter::Address(address) => Some(address.clone()),
+            _ => None,
+        });
+        unique_ids(self.id.iter().flatten().cloned().chain(filter_addresses))
+    }
+}
+
+impl Account {
+    pub(crate) fn query_text(&self) -> QueryText {
+        QueryText::new(
+            JoinEntity::Account,
+            &self.fields,
+            AccountField::all_variants(),
+            self.id.iter().flatten(),
+            self.filter.iter().flatten(),
+        )
+    }
+}
+
+impl Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.query_text().fmt(f)
+    }
 }
 
 impl TryFrom<Pairs<'_, Rule>> for Account {
@@ -56,11 +97,13 @@ impl TryFrom<Pairs<'_, Rule>> for Account {
         let mut fields: Vec<AccountField> = vec![];
         let mut id: Option<Vec<NameOrAddress>> = None;
         let mut filter: Option<Vec<AccountFilter>> = None;
+        let mut in_filters: Vec<InFilter> = vec![];
 
         for pair in pairs {
             match pair.as_rule() {
+                Rule::distinct => {}
                 Rule::account_fields => {
-                    let inner_pairs = pair.into_inner();
+                    let inner_pairs = pair.clone().into_inner();
 
                     if let Some(pair) = inner_pairs.peek() {
                         if pair.as_rule() == Rule::wildcard {
@@ -69,23 +112,31 @@ impl TryFrom<Pairs<'_, Rule>> for Account {
                         }
                     }
 
-                    fields = inner_pairs
-                        .map(|pair| AccountField::try_from(pair))
-                        .collect::<Result<Vec<AccountField>, AccountFieldError>>()?;
+                    add_fields(&mut fields, pair, |field| AccountField::try_from(field))?;
                 }
                 Rule::account_id => {
                     if let Some(id) = id.as_mut() {
-                        id.push(NameOrAddress::from_str(pair.as_str())?);
+                        id.push(NameOrAddress::from_str(&unquote(pair.as_str()))?);
                     } else {
-                        id = Some(vec![NameOrAddress::from_str(pair.as_str())?]);
+                        id = Some(vec![NameOrAddress::from_str(&unquote(pair.as_str()))?]);
                     }
                 }
-                Rule::account_filter_list => {
-                    filter = Some(
-                        pair.into_inner()
-                            .map(|pair| AccountFilter::try_from(pair))
-                            .collect::<Result<Vec<AccountFilter>, AccountFilterError>>()?,
-                    );
+                Rule::account_filter => {
+                    let inner = pair.into_inner().next().unwrap();
+                    if inner.as_rule() == Rule::expr_filter {
+                        add_fields(&mut fields, inner, |field| AccountField::try_from(field))?;
+                        continue;
+                    }
+                    if inner.as_rule() == Rule::in_filter {
+                        in_filters.push(InFilter::try_from(inner)?);
+                        continue;
+                    }
+                    filter
+                        .get_or_insert_with(Vec::new)
+                        .push(AccountFilter::try_from(inner)?);
+                }
+                Rule::variable => {
+                    in_filters.push(InFilter::variable(Account::IN_COLUMNS[0], pair));
                 }
                 _ => {
                     return Err(AccountError::UnexpectedToken(pair.as_str().to_string()));
@@ -93,7 +144,35 @@ impl TryFrom<Pairs<'_, Rule>> for Account {
             }
         }
 
-        Ok(Account { id, filter, fields })
+        let mut account = Account { id, filter, fields };
+        for in_filter in in_filters {
+            account.add_in_filter(in_filter)?;
+        }
+
+        Ok(account)
+    }
+}
+
+impl InFilterTarget for Account {
+    const IN_COLUMNS: &'static [&'static str] = &["address"];
+
+    fn apply_in(&mut self, column: &str, values: &[String]) -> Result<(), InFilterError> {
+        let addresses = parse_in_values(column, values, NameOrAddress::from_str)?;
+        apply_in_ids(&mut self.id, addresses);
+        Ok(())
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        self.filter
+            .get_or_insert_with(Vec::new)
+            .push(AccountFilter::Subquery(subquery));
+    }
+
+    fn take_subqueries(&mut self) -> Vec<Subquery> {
+        take_subquery_filters(&mut self.filter, |f| match f {
+            AccountFilter::Subquery(subquery) => Some(subquery),
+            _ => None,
+        })
     }
 }
 
@@ -106,9 +185,19 @@ pub enum AccountFilterError {
     NSError(#[from] NSError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum AccountFilter {
     Address(NameOrAddress),
+    Subquery(Subquery),
+}
+
+impl Display for AccountFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountFilter::Address(address) => write!(f, "address = {address}"),
+            AccountFilter::Subquery(subquery) => write!(f, "{subquery}"),
+        }
+    }
 }
 
 impl TryFrom<Pair<'_, Rule>> for AccountFilter {
@@ -116,8 +205,9 @@ impl TryFrom<Pair<'_, Rule>> for AccountFilter {
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
-            Rule::address_filter => {
-                let address = NameOrAddress::from_str(pair.as_str())?;
+            Rule::address_filter_type => {
+                let address = pair.into_inner().last().map_or("", |p| p.as_str());
+                let address = NameOrAddress::from_str(address.trim())?;
                 Ok(AccountFilter::Address(address))
             }
             _ => {
@@ -0,0 +1,777 @@
+use super::{
+    account::{Account, AccountField, AccountFilter},
+    chain::ChainOrRpc,
+    checkpoint::{
+        Checkpoint, CheckpointField, CheckpointFilter, CheckpointId, CheckpointNumberOrTag,
+        CheckpointRange,
+    },
+    coin::{Coin, CoinField, CoinFilter},
+    dump::{Dump, DumpFormat},
+    entity::Entity,
+    entity_id::unique_ids,
+    expression::{Distinct, Expr, Projection, SelectItem},
+    filters::{ComparisonFilter, EqualityFilter, FilterType},
+    join::JoinEntity,
+    name::{Name, NameField, NameFilter},
+    name_services::NameOrAddress,
+    object::{Object, ObjectField, ObjectFilter},
+    transaction::{Transaction, TransactionField, TransactionFilter},
+    types::GetExpression,
+};
+use std::fmt::Display;
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    digests::TransactionDigest,
+};
+
+/// Builds queries from Rust, without writing query text:
+///
+/// ```ignore
+/// let query = Query::tx()
+///     .fields([TransactionField::Digest, TransactionField::GasUsed])
+///     .where_(tx::checkpoint().between(10, 20))
+///     .where_(tx::sender().eq(address))
+///     .on(Chain::Mainnet);
+/// let results = ExecutionEngine::new().run_query(query).await?;
+/// ```
+///
+/// The built [`GetExpression`] is the one the parser returns for the same query, and its
+/// `Display` is the query text. The entity fetches the fields the filters compare, even when
+/// they aren't selected, and [`ExecutionEngine::run_query`] checks the query like a parsed
+/// program before running it.
+///
+/// [`ExecutionEngine::run_query`]: crate::interpreter::backend::execution_engine::ExecutionEngine::run_query
+pub struct Query;
+
+impl Query {
+    pub fn account() -> QueryBuilder<Account> {
+        QueryBuilder::new()
+    }
+
+    pub fn checkpoint() -> QueryBuilder<Checkpoint> {
+        QueryBuilder::new()
+    }
+
+    pub fn tx() -> QueryBuilder<Transaction> {
+        QueryBuilder::new()
+    }
+
+    pub fn coin() -> QueryBuilder<Coin> {
+        QueryBuilder::new()
+    }
+
+    pub fn object() -> QueryBuilder<Object> {
+        QueryBuilder::new()
+    }
+
+    pub fn name() -> QueryBuilder<Name> {
+        QueryBuilder::new()
+    }
+}
+
+/// The entities a [`QueryBuilder`] can query.
+pub trait BuildEntity {
+    type Id;
+    type Field: Copy + PartialEq + Display;
+    type Filter;
+
+    const ENTITY: JoinEntity;
+
+    /// The fields `*` selects.
+    fn wildcard() -> Vec<Self::Field>;
+
+    fn field(column: &str) -> Option<Self::Field>;
+
+    fn build(
+        ids: Option<Vec<Self::Id>>,
+        filters: Option<Vec<Self::Filter>>,
+        fields: Vec<Self::Field>,
+    ) -> Entity;
+}
+
+/// A query on one entity. Queries without `fields` or `select` select `*`.
+pub struct QueryBuilder<E: BuildEntity> {
+    ids: Vec<E::Id>,
+    filters: Vec<E::Filter>,
+    columns: Option<Vec<SelectItem>>,
+    expr_filters: Vec<Expr>,
+    distinct: Option<Distinct>,
+    dump: Option<Dump>,
+}
+
+impl<E: BuildEntity> QueryBuilder<E> {
+    fn new() -> Self {
+        Self {
+            ids: vec![],
+            filters: vec![],
+            columns: None,
+            expr_filters: vec![],
+            distinct: None,
+            dump: None,
+        }
+    }
+
+    /// Fetches the entities with these ids, like `FROM tx <digest>, <digest>`.
+    pub fn ids(mut self, ids: impl IntoIterator<Item = impl Into<E::Id>>) -> Self {
+        self.ids.extend(ids.into_iter().map(Into::into));
+        self
+    }
+
+    /// Selects fields of the entity, in the order they are given.
+    pub fn fields(mut self, fields: impl IntoIterator<Item = E::Field>) -> Self {
+        let columns = self.columns.get_or_insert_with(Vec::new);
+        columns.extend(
+            fields
+                .into_iter()
+                .map(|field| SelectItem::column(field.to_string())),
+        );
+        self
+    }
+
+    /// Selects a computed column, like `SELECT gas_budget - gas_used AS unused_gas`.
+    pub fn select(mut self, expr: Expr, alias: impl Into<String>) -> Self {
+        self.columns.get_or_insert_with(Vec::new).push(SelectItem {
+            expr,
+            name: alias.into(),
+        });
+        self
+    }
+
+    /// Adds a filter of the entity, like `sender = 0x...`.
+    pub fn where_(mut self, filter: E::Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Adds an expression filter, checked on the rows after they are fetched.
+    pub fn where_expr(mut self, expr: Expr) -> Self {
+        self.expr_filters.push(expr);
+        self
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.distinct = Some(Distinct::All);
+        self
+    }
+
+    pub fn distinct_on(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.distinct = Some(Distinct::On(columns.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Writes the results to `name.format`, like `>> name.format`.
+    pub fn dump(mut self, name: impl Into<String>, format: DumpFormat) -> Self {
+        self.dump = Some(Dump::new(name.into(), format));
+        self
+    }
+
+    /// Runs the query on a chain or an RPC.
+    pub fn on(self, chain: impl Into<ChainOrRpc>) -> GetExpression {
+        self.build(vec![chain.into()])
+    }
+
+    /// Runs the query on every chain, like `ON sui_mainnet, sui_testnet`.
+    pub fn on_chains(
+        self,
+        chains: impl IntoIterator<Item = impl Into<ChainOrRpc>>,
+    ) -> GetExpression {
+        self.build(unique_ids(chains.into_iter().map(Into::into)))
+    }
+
+    /// Builds the expression the parser builds for the same query: the entity fetches the
+    /// selected fields and the ones the expressions refer to, and the select list, expression
+    /// filters and `DISTINCT` go to the projection.
+    fn build(self, chains: Vec<ChainOrRpc>) -> GetExpression {
+        let mut fields = match &self.columns {
+            Some(_) => vec![],
+            None => E::wildcard(),
+        };
+        let exprs = self
+            .columns
+            .iter()
+            .flatten()
+            .map(|item| &item.expr)
+            .chain(&self.expr_filters);
+        for column in exprs.flat_map(Expr::columns) {
+            match E::field(&column) {
+                Some(field) if !fields.contains(&field) => fields.push(field),
+                _ => {}
+            }
+        }
+
+        let ids = (!self.ids.is_empty()).then_some(self.ids);
+        let filters = (!self.filters.is_empty()).then_some(self.filters);
+
+        GetExpression::new(
+            E::build(ids, filters, fields),
+            chains,
+            self.dump,
+            Some(Projection::new(E::ENTITY, self.columns, self.expr_filters)),
+            self.distinct,
+        )
+    }
+}
+
+impl BuildEntity for Account {
+    type Id = NameOrAddress;
+    type Field = AccountField;
+    type Filter = AccountFilter;
+
+    const ENTITY: JoinEntity = JoinEntity::Account;
+
+    fn wildcard() -> Vec<AccountField> {
+        AccountField::all_variants().to_vec()
+    }
+
+    fn field(column: &str) -> Option<AccountField> {
+        AccountField::try_from(column).ok()
+    }
+
+    fn build(
+        ids: Option<Vec<NameOrAddress>>,
+        filters: Option<Vec<AccountFilter>>,
+        fields: Vec<AccountField>,
+    ) -> Entity {
+        Entity::Account(Account::new(ids, filters, fields))
+    }
+}
+
+impl BuildEntity for Checkpoint {
+    type Id = CheckpointId;
+    type Field = CheckpointField;
+    type Filter = CheckpointFilter;
+
+    const ENTITY: JoinEntity = JoinEntity::Checkpoint;
+
+    fn wildcard() -> Vec<CheckpointField> {
+        CheckpointField::wildcard_variants()
+    }
+
+    fn field(column: &str) -> Option<CheckpointField> {
+        CheckpointField::try_from(column).ok()
+    }
+
+    // Checkpoint queries always have a list of ids, empty when they only have filters.
+    fn build(
+        ids: Option<Vec<CheckpointId>>,
+        filters: Option<Vec<CheckpointFilter>>,
+        fields: Vec<CheckpointField>,
+    ) -> Entity {
+        Entity::Checkpoint(Checkpoint::new(
+            Some(ids.unwrap_or_default()),
+            filters,
+            fields,
+        ))
+    }
+}
+
+impl BuildEntity for Transaction {
+    type Id = TransactionDigest;
+    type Field = TransactionField;
+    type Filter = TransactionFilter;
+
+    const ENTITY: JoinEntity = JoinEntity::Transaction;
+
+    fn wildcard() -> Vec<TransactionField> {
+        TransactionField::wildcard_variants()
+    }
+
+    fn field(column: &str) -> Option<TransactionField> {
+        TransactionField::try_from(column).ok()
+    }
+
+    fn build(
+        ids: Option<Vec<TransactionDigest>>,
+        filters: Option<Vec<TransactionFilter>>,
+        fields: Vec<TransactionField>,
+    ) -> Entity {
+        Entity::Transaction(Transaction::new(ids, filters, fields))
+    }
+}
+
+impl BuildEntity for Coin {
+    type Id = String;
+    type Field = CoinField;
+    type Filter = CoinFilter;
+
+    const ENTITY: JoinEntity = JoinEntity::Coin;
+
+    fn wildcard() -> Vec<CoinField> {
+        CoinField::all_variants().to_vec()
+    }
+
+    fn field(column: &str) -> Option<CoinField> {
+        CoinField::try_from(column).ok()
+    }
+
+    fn build(
+        ids: Option<Vec<String>>,
+        filters: Option<Vec<CoinFilter>>,
+        fields: Vec<CoinField>,
+    ) -> Entity {
+        Entity::Coin(Coin::new(ids, filters, fields))
+    }
+}
+
+impl BuildEntity for Object {
+    type Id = ObjectID;
+    type Field = ObjectField;
+    type Filter = ObjectFilter;
+
+    const ENTITY: JoinEntity = JoinEntity::Object;
+
+    fn wildcard() -> Vec<ObjectField> {
+        ObjectField::all_variants().to_vec()
+    }
+
+    fn field(column: &str) -> Option<ObjectField> {
+        ObjectField::try_from(column).ok()
+    }
+
+    fn build(
+        ids: Option<Vec<ObjectID>>,
+        filters: Option<Vec<ObjectFilter>>,
+        fields: Vec<ObjectField>,
+    ) -> Entity {
+        Entity::Object(Object::new(ids, filters, fields))
+    }
+}
+
+impl BuildEntity for Name {
+    type Id = String;
+    type Field = NameField;
+    type Filter = NameFilter;
+
+    const ENTITY: JoinEntity = JoinEntity::Name;
+
+    fn wildcard() -> Vec<NameField> {
+        NameField::all_variants().to_vec()
+    }
+
+    fn field(column: &str) -> Option<NameField> {
+        NameField::try_from(column).ok()
+    }
+
+    fn build(
+        ids: Option<Vec<String>>,
+        filters: Option<Vec<NameFilter>>,
+        fields: Vec<NameField>,
+    ) -> Entity {
+        Entity::Name(Name::new(ids, filters, fields))
+    }
+}
+
+/// A field that is only filtered with `=`.
+pub struct Column<T, F> {
+    filter: fn(T) -> F,
+}
+
+impl<T, F> Column<T, F> {
+    pub fn eq(self, value: impl Into<T>) -> F {
+        (self.filter)(value.into())
+    }
+}
+
+/// A field that is filtered with `=` and `!=`.
+pub struct EqualityColumn<T, F> {
+    filter: fn(EqualityFilter<T>) -> F,
+}
+
+impl<T, F> EqualityColumn<T, F> {
+    pub fn eq(self, value: impl Into<T>) -> F {
+        (self.filter)(EqualityFilter::Eq(value.into()))
+    }
+
+    pub fn neq(self, value: impl Into<T>) -> F {
+        (self.filter)(EqualityFilter::Neq(value.into()))
+    }
+}
+
+/// A field that is filtered with `=`, `!=`, `>`, `>=`, `<` and `<=`. Values are taken as
+/// they are, so that number literals don't need a suffix.
+pub struct ComparisonColumn<T, F> {
+    filter: fn(FilterType<T>) -> F,
+}
+
+impl<T, F> ComparisonColumn<T, F> {
+    pub fn eq(self, value: T) -> F {
+        (self.filter)(FilterType::Equality(EqualityFilter::Eq(value)))
+    }
+
+    pub fn neq(self, value: T) -> F {
+        (self.filter)(FilterType::Equality(EqualityFilter::Neq(value)))
+    }
+
+    pub fn gt(self, value: T) -> F {
+        (self.filter)(FilterType::Comparison(ComparisonFilter::Gt(value)))
+    }
+
+    pub fn gte(self, value: T) -> F {
+        (self.filter)(FilterType::Comparison(ComparisonFilter::Gte(value)))
+    }
+
+    pub fn lt(self, value: T) -> F {
+        (self.filter)(FilterType::Comparison(ComparisonFilter::Lt(value)))
+    }
+
+    pub fn lte(self, value: T) -> F {
+        (self.filter)(FilterType::Comparison(ComparisonFilter::Lte(value)))
+    }
+}
+
+/// The `checkpoint` filter: `checkpoint = 10`, `checkpoint = 10:20` or `checkpoint >= latest - 100`.
+pub struct CheckpointColumn<F> {
+    range: fn(CheckpointRange) -> F,
+    comparison: fn(ComparisonFilter<CheckpointNumberOrTag>) -> F,
+}
+
+impl<F> CheckpointColumn<F> {
+    pub fn eq(self, checkpoint: impl Into<CheckpointNumberOrTag>) -> F {
+        (self.range)(CheckpointRange::new(checkpoint.into(), None))
+    }
+
+    pub fn between(
+        self,
+        start: impl Into<CheckpointNumberOrTag>,
+        end: impl Into<CheckpointNumberOrTag>,
+    ) -> F {
+        (self.range)(CheckpointRange::new(start.into(), Some(end.into())))
+    }
+
+    pub fn gt(self, checkpoint: impl Into<CheckpointNumberOrTag>) -> F {
+        (self.comparison)(ComparisonFilter::Gt(checkpoint.into()))
+    }
+
+    pub fn gte(self, checkpoint: impl Into<CheckpointNumberOrTag>) -> F {
+        (self.comparison)(ComparisonFilter::Gte(checkpoint.into()))
+    }
+
+    pub fn lt(self, checkpoint: impl Into<CheckpointNumberOrTag>) -> F {
+        (self.comparison)(ComparisonFilter::Lt(checkpoint.into()))
+    }
+
+    pub fn lte(self, checkpoint: impl Into<CheckpointNumberOrTag>) -> F {
+        (self.comparison)(ComparisonFilter::Lte(checkpoint.into()))
+    }
+}
+
+/// The filters of `account` queries.
+pub mod account {
+    use super::*;
+
+    pub fn address() -> Column<NameOrAddress, AccountFilter> {
+        Column {
+            filter: AccountFilter::Address,
+        }
+    }
+}
+
+/// The filters of `checkpoint` queries.
+pub mod checkpoint {
+    use super::*;
+    use crate::common::timestamp::Timestamp;
+
+    pub fn checkpoint() -> CheckpointColumn<CheckpointFilter> {
+        CheckpointColumn {
+            range: CheckpointFilter::Range,
+            comparison: CheckpointFilter::Comparison,
+        }
+    }
+
+    pub fn timestamp_ms() -> ComparisonColumn<Timestamp, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::Timestamp,
+        }
+    }
+
+    pub fn epoch() -> ComparisonColumn<u64, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::Epoch,
+        }
+    }
+
+    pub fn transactions() -> ComparisonColumn<usize, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::Transactions,
+        }
+    }
+
+    pub fn computation_cost() -> ComparisonColumn<u64, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::ComputationCost,
+        }
+    }
+
+    pub fn storage_cost() -> ComparisonColumn<u64, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::StorageCost,
+        }
+    }
+
+    pub fn storage_rebate() -> ComparisonColumn<u64, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::StorageRebate,
+        }
+    }
+
+    pub fn non_refundable_storage_fee() -> ComparisonColumn<u64, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::NonRefundableStorageFee,
+        }
+    }
+
+    pub fn network_total_transactions() -> ComparisonColumn<u64, CheckpointFilter> {
+        ComparisonColumn {
+            filter: CheckpointFilter::NetworkTotalTransactions,
+        }
+    }
+}
+
+/// The filters of `tx` queries.
+pub mod tx {
+    use super::*;
+    use crate::common::timestamp::Timestamp;
+
+    pub fn kind() -> EqualityColumn<String, TransactionFilter> {
+        EqualityColumn {
+            filter: TransactionFilter::Kind,
+        }
+    }
+
+    pub fn sender() -> EqualityColumn<SuiAddress, TransactionFilter> {
+        EqualityColumn {
+            filter: |filter| TransactionFilter::Sender(FilterType::Equality(filter)),
+        }
+    }
+
+    pub fn recipient() -> EqualityColumn<SuiAddress, TransactionFilter> {
+        EqualityColumn {
+            filter: |filter| TransactionFilter::Recipient(FilterType::Equality(filter)),
+        }
+    }
+
+    pub fn gas_budget() -> ComparisonColumn<u64, TransactionFilter> {
+        ComparisonColumn {
+            filter: TransactionFilter::GasBudget,
+        }
+    }
+
+    pub fn gas_price() -> ComparisonColumn<u64, TransactionFilter> {
+        ComparisonColumn {
+            filter: TransactionFilter::GasPrice,
+        }
+    }
+
+    pub fn gas_used() -> ComparisonColumn<u64, TransactionFilter> {
+        ComparisonColumn {
+            filter: TransactionFilter::GasUsed,
+        }
+    }
+
+    pub fn status() -> EqualityColumn<bool, TransactionFilter> {
+        EqualityColumn {
+            filter: TransactionFilter::Status,
+        }
+    }
+
+    pub fn executed_epoch() -> ComparisonColumn<u64, TransactionFilter> {
+        ComparisonColumn {
+            filter: TransactionFilter::ExecutedEpoch,
+        }
+    }
+
+    /// `checkpoint = 10` and `checkpoint = 10:20` select checkpoints, comparisons filter them.
+    pub fn checkpoint() -> CheckpointColumn<TransactionFilter> {
+        CheckpointColumn {
+            range: |range| TransactionFilter::CheckpointId(CheckpointId::Range(range)),
+            comparison: TransactionFilter::Checkpoint,
+        }
+    }
+
+    pub fn timestamp_ms() -> ComparisonColumn<Timestamp, TransactionFilter> {
+        ComparisonColumn {
+            filter: TransactionFilter::TimestampMs,
+        }
+    }
+
+    /// `sender IN (a, b)`.
+    pub fn sender_in(senders: impl IntoIterator<Item = SuiAddress>) -> TransactionFilter {
+        TransactionFilter::SenderIn(senders.into_iter().collect())
+    }
+}
+
+/// The filters of `coin` queries.
+pub mod coin {
+    use super::*;
+
+    pub fn coin_type() -> EqualityColumn<String, CoinFilter> {
+        EqualityColumn {
+            filter: CoinFilter::CoinType,
+        }
+    }
+
+    pub fn owner() -> Column<SuiAddress, CoinFilter> {
+        Column {
+            filter: CoinFilter::Owner,
+        }
+    }
+
+    pub fn decimals() -> ComparisonColumn<u8, CoinFilter> {
+        ComparisonColumn {
+            filter: CoinFilter::Decimals,
+        }
+    }
+}
+
+/// The filters of `object` queries.
+pub mod object {
+    use super::*;
+
+    pub fn object() -> Column<ObjectID, ObjectFilter> {
+        Column {
+            filter: ObjectFilter::ObjectId,
+        }
+    }
+}
+
+/// The filters of `name` queries.
+pub mod name {
+    use super::*;
+
+    pub fn name() -> EqualityColumn<String, NameFilter> {
+        EqualityColumn {
+            filter: NameFilter::Name,
+        }
+    }
+
+    pub fn address() -> EqualityColumn<SuiAddress, NameFilter> {
+        EqualityColumn {
+            filter: NameFilter::Address,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        common::{chain::Chain, expression::BinaryOperator, types::Expression},
+        interpreter::frontend::parser::Parser,
+    };
+    use serde_json::Value;
+    use std::str::FromStr;
+
+    const SENDER: &str = "0x214a4199264348df2364acd683a3971a9927a5252747f4e0776f0506922f9db0";
+
+    fn parse(source: &str) -> GetExpression {
+        match Parser::new(source).parse_expressions().unwrap().remove(0) {
+            Expression::Get(get) => get,
+            expression => panic!("expected a query, got {expression:?}"),
+        }
+    }
+
+    #[test]
+    fn test_builder_matches_parser() {
+        let sender = SuiAddress::from_str(SENDER).unwrap();
+        let query = Query::tx()
+            .fields([TransactionField::Digest, TransactionField::GasUsed])
+            .where_(tx::sender().eq(sender))
+            .where_(tx::checkpoint().between(10, 20))
+            .on(Chain::Mainnet);
+        let source = format!(
+            "SELECT digest, gas_used FROM tx WHERE sender = {SENDER}, checkpoint = 10:20 \
+             ON sui_mainnet"
+        );
+        assert_eq!(query, parse(&source));
+        let Entity::Transaction(transaction) = &query.entity else {
+            panic!("expected a tx query");
+        };
+        assert_eq!(
+            transaction.fields(),
+            &vec![
+                TransactionField::Digest,
+                TransactionField::GasUsed,
+                TransactionField::Sender
+            ]
+        );
+
+        let unused_gas = Expr::Binary(
+            Box::new(Expr::Column("gas_budget".to_string())),
+            BinaryOperator::Sub,
+            Box::new(Expr::Column("gas_used".to_string())),
+        );
+        let query = Query::tx()
+            .fields([TransactionField::Digest])
+            .select(unused_gas, "unused_gas")
+            .where_(tx::gas_price().gte(1000))
+            .where_expr(Expr::Binary(
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Column("gas_price".to_string())),
+                    BinaryOperator::Mul,
+                    Box::new(Expr::Literal(Value::from(2))),
+                )),
+                BinaryOperator::Gt,
+                Box::new(Expr::Literal(Value::from(1000))),
+            ))
+            .distinct()
+            .on_chains([Chain::Mainnet, Chain::Testnet, Chain::Mainnet]);
+        let source = "SELECT DISTINCT digest, gas_budget - gas_used AS unused_gas FROM tx \
+                      WHERE gas_price >= 1000, gas_price * 2 > 1000 ON sui_mainnet, sui_testnet";
+        assert_eq!(query, parse(source));
+
+        let query = Query::checkpoint()
+            .ids([CheckpointNumberOrTag::Latest])
+            .on(Chain::Testnet);
+        assert_eq!(
+            query,
+            parse("SELECT * FROM checkpoint latest ON sui_testnet")
+        );
+
+        let query = Query::checkpoint()
+            .where_(checkpoint::epoch().eq(700))
+            .where_(checkpoint::transactions().gt(10))
+            .dump("checkpoints", DumpFormat::Csv)
+            .on(Chain::Mainnet);
+        let source = "SELECT * FROM checkpoint WHERE epoch = 700, transactions > 10 \
+                      ON sui_mainnet >> checkpoints.csv";
+        assert_eq!(query, parse(source));
+
+        let query = Query::name()
+            .fields([NameField::Address])
+            .where_(name::name().eq("example.sui"))
+            .on(Chain::Mainnet);
+        assert_eq!(
+            query,
+            parse("SELECT address FROM name WHERE name = 'example.sui' ON sui_mainnet")
+        );
+    }
+
+    #[test]
+    fn test_display_and_serde_round_trip() {
+        let sources = [
+            format!("SELECT * FROM tx WHERE sender = {SENDER}, gas_used > 1000 ON sui_mainnet"),
+            "SELECT DISTINCT ON (sender) sender, gas_budget - gas_used AS unused_gas FROM tx \
+             WHERE checkpoint >= latest - 100, timestamp_ms > now() - 1h, gas_used * 2 > 1000 \
+             ON sui_mainnet"
+                .to_string(),
+            "SELECT number, epoch FROM checkpoint 10:20, latest ON sui_mainnet, sui_testnet"
+                .to_string(),
+            "SELECT * FROM coin '0x2::sui::SUI' ON sui_testnet >> coins.json".to_string(),
+            "SELECT tx.digest, checkpoint.epoch AS epoch FROM tx JOIN checkpoint \
+             ON tx.checkpoint = checkpoint.number WHERE checkpoint = 10 ON sui_mainnet"
+                .to_string(),
+            "SELECT * FROM account WHERE address IN (SELECT address FROM name \
+             WHERE name = 'example.sui') ON sui_mainnet"
+                .to_string(),
+        ];
+
+        for source in sources {
+            let query = parse(&source);
+            assert_eq!(parse(&query.to_string()), query, "{query}");
+
+            let json = serde_json::to_string(&query).unwrap();
+            let deserialized: GetExpression = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, query, "{json}");
+        }
+    }
+}
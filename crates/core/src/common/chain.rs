@@ -1,20 +1,84 @@
-use super::config::Config;
-use crate::interpreter::frontend::parser::Rule;
+use super::{config::Config, entity_id::unique_ids};
 use anyhow::Result;
 use core::fmt;
-use eql_macros::EnumVariants;
-use pest::iterators::Pairs;
 use serde::{Deserialize, Serialize};
+use std::env;
 use sui_sdk::SuiClientBuilder;
 use url::Url;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Sets the chains of the queries without `ON`, e.g. `SUI_QL_CHAIN=sui_testnet`.
+pub const DEFAULT_CHAIN_ENV: &str = "SUI_QL_CHAIN";
+
+/// The chain identifiers of the public networks, the start of their genesis checkpoint
+/// digest. Devnet gets a new one at every reset, so it is only recognized by its RPC URL.
+const MAINNET_CHAIN_ID: &str = "35834a8a";
+const TESTNET_CHAIN_ID: &str = "4c78adac";
+
+static PUBLIC_CHAINS: [Chain; 3] = [Chain::Mainnet, Chain::Testnet, Chain::Devnet];
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
 pub enum ChainOrRpc {
     Chain(Chain),
     Rpc(Url),
 }
 
+impl From<Chain> for ChainOrRpc {
+    fn from(chain: Chain) -> Self {
+        ChainOrRpc::Chain(chain)
+    }
+}
+
+impl From<Url> for ChainOrRpc {
+    fn from(url: Url) -> Self {
+        ChainOrRpc::Rpc(url)
+    }
+}
+
+impl fmt::Display for ChainOrRpc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainOrRpc::Chain(chain) => write!(f, "{chain}"),
+            ChainOrRpc::Rpc(url) => write!(f, "{url}"),
+        }
+    }
+}
+
 impl ChainOrRpc {
+    /// Reads the chains of a `SET chain` or `ON` clause outside a query: an RPC URL, a list
+    /// of chains or `*`. `networks` are the networks of the config file, like `localnet`.
+    pub fn from_selector(
+        selector: &str,
+        networks: &[String],
+    ) -> Result<Vec<ChainOrRpc>, ChainError> {
+        let selector = selector.trim();
+        if selector.starts_with("http://") || selector.starts_with("https://") {
+            let url =
+                Url::parse(selector).map_err(|_| ChainError::InvalidRpc(selector.to_string()))?;
+            return Ok(vec![ChainOrRpc::Rpc(url)]);
+        }
+        Chain::from_selector(selector, networks)
+    }
+
+    /// The chains of the queries without `ON` or `SET chain`: the `SUI_QL_CHAIN` environment
+    /// variable, then the `default_chain` of the config file, then mainnet.
+    pub fn defaults() -> Result<Vec<ChainOrRpc>> {
+        ChainOrRpc::defaults_from(env::var(DEFAULT_CHAIN_ENV).ok(), Config::load())
+    }
+
+    /// The default chains for a `SUI_QL_CHAIN` value, if the variable is set, and a config.
+    fn defaults_from(selector: Option<String>, config: &Config) -> Result<Vec<ChainOrRpc>> {
+        if let Some(selector) = selector {
+            return Ok(ChainOrRpc::from_selector(
+                &selector,
+                &config.get_networks()?,
+            )?);
+        }
+        match config.get_default_chain()? {
+            Some(chains) => Ok(chains),
+            None => Ok(vec![ChainOrRpc::Chain(Chain::default())]),
+        }
+    }
+
     pub fn rpc_url(&self) -> Result<Url> {
         match self {
             ChainOrRpc::Chain(chain) => Ok(chain.rpc_url()?.clone()),
@@ -22,51 +86,61 @@ impl ChainOrRpc {
         }
     }
 
+    /// The chain an RPC serves: a public network when its URL or chain identifier is one of
+    /// theirs, else the network the config file names it, else the URL itself.
     pub async fn to_chain(&self) -> Result<Chain> {
         match self {
             ChainOrRpc::Chain(chain) => Ok(chain.clone()),
             ChainOrRpc::Rpc(rpc) => {
+                for chain in Chain::public_chains() {
+                    if chain.rpc_url()? == *rpc {
+                        return Ok(chain.clone());
+                    }
+                }
+
                 let provider = SuiClientBuilder::default().build(rpc.clone()).await?;
                 let chain_id = provider.read_api().get_chain_identifier().await?;
-                Err(anyhow::anyhow!("Unknown chain ID: {}", chain_id)) // You only support 3 chains. No detection.
+                match chain_id.as_str() {
+                    MAINNET_CHAIN_ID => Ok(Chain::Mainnet),
+                    TESTNET_CHAIN_ID => Ok(Chain::Testnet),
+                    _ => match Config::load().get_network_by_rpc(rpc)? {
+                        Some(network) => Ok(Chain::Custom(network)),
+                        None => Ok(Chain::Custom(rpc.to_string())),
+                    },
+                }
             }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, EnumVariants, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
 pub enum Chain {
     Mainnet,
     Testnet,
     Devnet,
+    /// A network named in the config file, like `localnet`, or the URL of an RPC that doesn't
+    /// serve a public network.
+    #[serde(untagged)]
+    Custom(String),
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum ChainError {
     #[error("Invalid chain {0}")]
     InvalidChain(String),
-}
-
-impl TryFrom<Pairs<'_, Rule>> for Chain {
-    type Error = ChainError;
-
-    fn try_from(pairs: Pairs<'_, Rule>) -> Result<Self, Self::Error> {
-        for pair in pairs {
-            match pair.as_rule() {
-                Rule::chain => return Ok(Chain::try_from(pair.as_str())?),
-                _ => return Err(ChainError::InvalidChain(pair.as_str().to_string())),
-            }
-        }
-        Ok(Chain::default())
-    }
+    #[error("Invalid RPC URL {0}")]
+    InvalidRpc(String),
 }
 
 impl Chain {
-    pub fn from_selector(selector: &str) -> Result<Vec<ChainOrRpc>, ChainError> {
+    pub fn from_selector(
+        selector: &str,
+        networks: &[String],
+    ) -> Result<Vec<ChainOrRpc>, ChainError> {
         if selector == "*" {
-            let chains = Chain::all_variants();
+            let chains = Chain::public_chains();
             let chains = chains
-                .into_iter()
+                .iter()
                 .map(|chain| ChainOrRpc::Chain(chain.clone()))
                 .collect::<Vec<ChainOrRpc>>();
             Ok(chains)
@@ -74,15 +148,34 @@ impl Chain {
             let chains = selector
                 .split(',')
                 .map(str::trim)
-                .map(|s| Chain::try_from(s).map(ChainOrRpc::Chain))
+                .map(|s| Chain::from_name(s, networks).map(ChainOrRpc::Chain))
                 .collect::<Result<Vec<ChainOrRpc>, ChainError>>()?;
 
-            Ok(chains)
+            // A chain listed twice is only queried once.
+            Ok(unique_ids(chains))
         }
     }
 
+    /// The chain called `name`: a public network or one of `networks`.
+    pub fn from_name(name: &str, networks: &[String]) -> Result<Chain, ChainError> {
+        match name {
+            "sui_mainnet" => Ok(Chain::Mainnet),
+            "sui_testnet" => Ok(Chain::Testnet),
+            "sui_devnet" => Ok(Chain::Devnet),
+            _ if networks.iter().any(|network| network == name) => {
+                Ok(Chain::Custom(name.to_string()))
+            }
+            _ => Err(ChainError::InvalidChain(name.to_string())),
+        }
+    }
+
+    /// The public networks, which `*` selects.
+    pub fn public_chains() -> &'static [Chain] {
+        &PUBLIC_CHAINS
+    }
+
     pub fn rpc_url(&self) -> Result<Url> {
-        match Config::new().get_chain_default_rpc(self) {
+        match Config::load().get_chain_default_rpc(self) {
             Ok(Some(url)) => Ok(url),
             Ok(None) => Ok(self.rpc_fallback().parse()?),
             Err(e) => Err(e),
@@ -94,6 +187,8 @@ impl Chain {
             Chain::Mainnet => "https://fullnode.mainnet.sui.io:443",
             Chain::Testnet => "https://fullnode.testnet.sui.io:443",
             Chain::Devnet => "https://fullnode.devnet.sui.io:443",
+            // Networks of the config file have an RPC there, RPCs are their own.
+            Chain::Custom(rpc) => rpc,
         }
     }
 }
@@ -104,26 +199,129 @@ impl Default for Chain {
     }
 }
 
-impl TryFrom<&str> for Chain {
-    type Error = ChainError;
-
-    fn try_from(chain: &str) -> Result<Self, Self::Error> {
-        match chain {
-            "sui_mainnet" => Ok(Chain::Mainnet),
-            "sui_testnet" => Ok(Chain::Testnet),
-            "sui_devnet" => Ok(Chain::Devnet),
-            _ => Err(ChainError::InvalidChain(chain.to_string())),
-        }
-    }
-}
-
 impl fmt::Display for Chain {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let chain_str = match self {
             Chain::Mainnet => "sui_mainnet",
             Chain::Testnet => "sui_testnet",
             Chain::Devnet => "sui_devnet",
+            Chain::Custom(network) => network,
         };
         write!(f, "{}", chain_str)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_chain_or_rpc_from_selector() {
+        assert_eq!(
+            ChainOrRpc::from_selector("sui_testnet, sui_devnet", &[]).unwrap(),
+            vec![
+                ChainOrRpc::Chain(Chain::Testnet),
+                ChainOrRpc::Chain(Chain::Devnet)
+            ]
+        );
+        assert_eq!(
+            ChainOrRpc::from_selector("http://localhost:9000", &[]).unwrap(),
+            vec![ChainOrRpc::Rpc("http://localhost:9000".parse().unwrap())]
+        );
+        assert_eq!(ChainOrRpc::from_selector("*", &[]).unwrap().len(), 3);
+        assert!(ChainOrRpc::from_selector("localnet", &[]).is_err());
+
+        let networks = vec!["localnet".to_string()];
+        assert_eq!(
+            ChainOrRpc::from_selector("localnet, sui_devnet", &networks).unwrap(),
+            vec![
+                ChainOrRpc::Chain(Chain::Custom("localnet".to_string())),
+                ChainOrRpc::Chain(Chain::Devnet)
+            ]
+        );
+        // `*` only selects the public networks.
+        assert_eq!(ChainOrRpc::from_selector("*", &networks).unwrap().len(), 3);
+    }
+
+    /// A config file holding `content` in `dir`, which is removed at the end of the test.
+    fn config(dir: &TempDir, name: &str, content: &str) -> Config {
+        let path = dir.path().join(format!("{name}.json"));
+        std::fs::write(&path, content).unwrap();
+        Config::from_path(path)
+    }
+
+    #[test]
+    fn test_default_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        let devnet = config(&dir, "default_chain", r#"{"default_chain": "sui_devnet"}"#);
+        assert_eq!(
+            ChainOrRpc::defaults_from(None, &devnet).unwrap(),
+            vec![ChainOrRpc::Chain(Chain::Devnet)]
+        );
+        // The environment variable comes before the config file.
+        assert_eq!(
+            ChainOrRpc::defaults_from(Some("sui_testnet, sui_mainnet".to_string()), &devnet)
+                .unwrap(),
+            vec![
+                ChainOrRpc::Chain(Chain::Testnet),
+                ChainOrRpc::Chain(Chain::Mainnet)
+            ]
+        );
+        assert_eq!(
+            ChainOrRpc::defaults_from(Some("http://localhost:9000".to_string()), &devnet).unwrap(),
+            vec![ChainOrRpc::Rpc("http://localhost:9000".parse().unwrap())]
+        );
+        assert!(ChainOrRpc::defaults_from(Some("sui_localnet".to_string()), &devnet).is_err());
+
+        let rpc = config(&dir, "rpc", r#"{"default_chain": "http://localhost:9000"}"#);
+        assert_eq!(
+            ChainOrRpc::defaults_from(None, &rpc).unwrap(),
+            vec![ChainOrRpc::Rpc("http://localhost:9000".parse().unwrap())]
+        );
+
+        let empty = config(&dir, "no_default_chain", r#"{"chains": {}}"#);
+        assert_eq!(
+            ChainOrRpc::defaults_from(None, &empty).unwrap(),
+            vec![ChainOrRpc::Chain(Chain::Mainnet)]
+        );
+
+        // The networks of the config file can be selected, from it or from the variable.
+        let localnet = config(
+            &dir,
+            "localnet",
+            r#"{
+                "chains": {"localnet": {"default": "http://localhost:9000"}},
+                "default_chain": "localnet"
+            }"#,
+        );
+        let localnet_chain = vec![ChainOrRpc::Chain(Chain::Custom("localnet".to_string()))];
+        assert_eq!(
+            ChainOrRpc::defaults_from(None, &localnet).unwrap(),
+            localnet_chain
+        );
+        assert_eq!(
+            ChainOrRpc::defaults_from(Some("localnet".to_string()), &localnet).unwrap(),
+            localnet_chain
+        );
+        assert!(ChainOrRpc::defaults_from(Some("localnet".to_string()), &empty).is_err());
+    }
+
+    #[test]
+    fn test_custom_chain() {
+        let chain = Chain::Custom("localnet".to_string());
+        assert_eq!(chain.to_string(), "localnet");
+        assert_eq!(serde_json::to_string(&chain).unwrap(), "\"localnet\"");
+        assert_eq!(
+            serde_json::from_str::<Chain>("\"localnet\"").unwrap(),
+            chain
+        );
+        assert_eq!(
+            serde_json::from_str::<Chain>("\"Testnet\"").unwrap(),
+            Chain::Testnet
+        );
+
+        let rpc = Chain::Custom("http://127.0.0.1:9000/".to_string());
+        assert_eq!(rpc.rpc_url().unwrap().as_str(), "http://127.0.0.1:9000/");
+    }
+}
@@ -1,4 +1,17 @@
-use super::entity_id::{parse_checkpoint_number_or_tag, EntityIdError};
+use super::{
+    entity::QueryText,
+    entity_id::{parse_checkpoint_number_or_tag, EntityIdError},
+    expression::add_fields,
+    filters::{
+        inclusive_bounds, ComparisonFilter, ComparisonFilterError, EqualityFilter, Filter,
+        FilterError, FilterType,
+    },
+    join::JoinEntity,
+    subquery::{
+        parse_in_values, take_subquery_filters, InFilter, InFilterError, InFilterTarget, Subquery,
+    },
+    timestamp::{Timestamp, TimestampError},
+};
 use crate::interpreter::frontend::parser::Rule;
 use anyhow::Result;
 use eql_macros::EnumVariants;
@@ -6,22 +19,59 @@ use pest::iterators::{Pair, Pairs};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
     str::FromStr,
 };
-use sui_json_rpc_types::CheckpointId as SuiCheckpointId;
+use sui_json_rpc_api::ExtendedApiClient;
+use sui_json_rpc_types::{Checkpoint as RpcCheckpoint, CheckpointId as SuiCheckpointId, EpochInfo};
 use sui_sdk::SuiClient;
+use sui_types::{gas::GasCostSummary, sui_serde::BigInt};
 
 #[derive(thiserror::Error, Debug)]
 pub enum CheckpointNumberOrTagError {
     #[error("Invalid checkpoint range: {0}")]
     InvalidCheckpointRange(String),
+    #[error("`{0}` points past the latest checkpoint, offsets from `latest` must be negative")]
+    PastLatestCheckpoint(String),
 }
 
-#[derive(Debug, Copy, PartialEq, Eq, Clone)]
+#[derive(Debug, Copy, PartialEq, Eq, Clone, Hash)]
 pub enum CheckpointNumberOrTag {
     Number(u64),
     Latest,
     Earliest,
+    /// First checkpoint of an epoch, e.g. `epoch_start(700)`
+    EpochStart(u64),
+    /// A tag shifted by a number of checkpoints, e.g. `latest - 100`
+    Relative(CheckpointTag, i64),
+}
+
+/// Checkpoint tags that are resolved against the chain when the query runs.
+#[derive(Debug, Copy, PartialEq, Eq, Clone, Hash)]
+pub enum CheckpointTag {
+    Latest,
+    Earliest,
+    EpochStart(u64),
+}
+
+impl From<CheckpointTag> for CheckpointNumberOrTag {
+    fn from(tag: CheckpointTag) -> Self {
+        match tag {
+            CheckpointTag::Latest => Self::Latest,
+            CheckpointTag::Earliest => Self::Earliest,
+            CheckpointTag::EpochStart(epoch) => Self::EpochStart(epoch),
+        }
+    }
+}
+
+impl fmt::Display for CheckpointTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointTag::Latest => write!(f, "latest"),
+            CheckpointTag::Earliest => write!(f, "earliest"),
+            CheckpointTag::EpochStart(epoch) => write!(f, "epoch_start({epoch})"),
+        }
+    }
 }
 
 impl CheckpointNumberOrTag {
@@ -62,6 +112,11 @@ impl fmt::Display for CheckpointNumberOrTag {
             CheckpointNumberOrTag::Number(n) => write!(f, "{n}"),
             CheckpointNumberOrTag::Latest => write!(f, "latest"),
             CheckpointNumberOrTag::Earliest => write!(f, "earliest"),
+            CheckpointNumberOrTag::EpochStart(epoch) => write!(f, "epoch_start({epoch})"),
+            CheckpointNumberOrTag::Relative(tag, offset) if *offset < 0 => {
+                write!(f, "{tag} - {}", offset.unsigned_abs())
+            }
+            CheckpointNumberOrTag::Relative(tag, offset) => write!(f, "{tag} + {offset}"),
         }
     }
 }
@@ -69,14 +124,52 @@ impl fmt::Display for CheckpointNumberOrTag {
 impl FromStr for CheckpointNumberOrTag {
     type Err = CheckpointNumberOrTagError;
 
+    /// Parses checkpoint numbers and tags, optionally shifted by an offset:
+    /// `100`, `latest`, `earliest`, `epoch_start(700)`, `latest - 100`, `epoch_start(700) + 5`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "latest" => Ok(Self::Latest),
-            "earliest" => Ok(Self::Number(0)),
-            _ => s
-                .parse::<u64>()
-                .map(Self::Number)
-                .map_err(|_| CheckpointNumberOrTagError::InvalidCheckpointRange(s.to_string())),
+        let invalid = || CheckpointNumberOrTagError::InvalidCheckpointRange(s.to_string());
+        let compact = s.split_whitespace().collect::<String>();
+
+        let (base, offset) = match compact.find(|c| c == '+' || c == '-') {
+            Some(index) => {
+                let (base, offset) = compact.split_at(index);
+                let amount = offset[1..].parse::<i64>().map_err(|_| invalid())?;
+                let amount = if offset.starts_with('-') {
+                    -amount
+                } else {
+                    amount
+                };
+                (base, amount)
+            }
+            None => (compact.as_str(), 0),
+        };
+
+        let tag = match base {
+            "latest" => CheckpointTag::Latest,
+            "earliest" => CheckpointTag::Earliest,
+            _ => match base
+                .strip_prefix("epoch_start(")
+                .and_then(|epoch| epoch.strip_suffix(')'))
+            {
+                Some(epoch) => CheckpointTag::EpochStart(epoch.parse().map_err(|_| invalid())?),
+                // Offsets on plain numbers are folded right away.
+                None => {
+                    return base
+                        .parse::<u64>()
+                        .ok()
+                        .and_then(|number| number.checked_add_signed(offset))
+                        .map(Self::Number)
+                        .ok_or_else(invalid)
+                }
+            },
+        };
+
+        match (tag, offset) {
+            (tag, 0) => Ok(tag.into()),
+            (CheckpointTag::Latest, offset) if offset > 0 => Err(
+                CheckpointNumberOrTagError::PastLatestCheckpoint(s.trim().to_string()),
+            ),
+            (tag, offset) => Ok(Self::Relative(tag, offset)),
         }
     }
 }
@@ -88,8 +181,7 @@ impl serde::Serialize for CheckpointNumberOrTag {
     {
         match *self {
             Self::Number(x) => serializer.serialize_str(&format!("0x{x:x}")),
-            Self::Latest => serializer.serialize_str("latest"),
-            Self::Earliest => serializer.serialize_str("earliest"),
+            _ => serializer.serialize_str(&self.to_string()),
         }
     }
 }
@@ -100,7 +192,13 @@ impl<'de> serde::Deserialize<'de> for CheckpointNumberOrTag {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?.to_lowercase();
-        s.parse().map_err(serde::de::Error::custom)
+        // Numbers are serialized in hex.
+        match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16)
+                .map(Self::Number)
+                .map_err(serde::de::Error::custom),
+            None => s.parse().map_err(serde::de::Error::custom),
+        }
     }
 }
 
@@ -117,18 +215,41 @@ pub enum CheckpointError {
 
     #[error(transparent)]
     EntityIdError(#[from] EntityIdError),
+
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
 pub enum CheckpointId {
     Number(CheckpointNumberOrTag),
     Range(CheckpointRange),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+impl Display for CheckpointId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CheckpointId::Number(number) => write!(f, "{number}"),
+            CheckpointId::Range(range) => write!(f, "{range}"),
+        }
+    }
+}
+
+impl From<CheckpointNumberOrTag> for CheckpointId {
+    fn from(number: CheckpointNumberOrTag) -> Self {
+        CheckpointId::Number(number)
+    }
+}
+
+impl From<CheckpointRange> for CheckpointId {
+    fn from(range: CheckpointRange) -> Self {
+        CheckpointId::Range(range)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Checkpoint {
     // TODO: ids should be mandatory
-    // TODO: ids should be a HashSet
     ids: Option<Vec<CheckpointId>>,
     filter: Option<Vec<CheckpointFilter>>,
     fields: Vec<CheckpointField>,
@@ -151,6 +272,16 @@ impl Checkpoint {
         self.ids.as_ref()
     }
 
+    pub(crate) fn query_text(&self) -> QueryText {
+        QueryText::new(
+            JoinEntity::Checkpoint,
+            &self.fields,
+            &CheckpointField::wildcard_variants(),
+            self.ids.iter().flatten(),
+            self.filter.iter().flatten(),
+        )
+    }
+
     pub fn fields(&self) -> &Vec<CheckpointField> {
         &self.fields
     }
@@ -158,6 +289,138 @@ impl Checkpoint {
     pub fn filters(&self) -> Option<&Vec<CheckpointFilter>> {
         self.filter.as_ref()
     }
+
+    /// Returns the `checkpoint` range and comparison filters as a flat list of bounds.
+    pub fn checkpoint_filters(&self) -> Vec<FilterType<CheckpointNumberOrTag>> {
+        self.filters()
+            .map(|filters| {
+                filters
+                    .iter()
+                    .flat_map(|f| match f {
+                        CheckpointFilter::Range(range) => range.to_filters(),
+                        CheckpointFilter::Comparison(c) => vec![FilterType::Comparison(c.clone())],
+                        _ => vec![],
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the timestamp filters resolved to unix milliseconds. Relative timestamps are
+    /// resolved against the current time, so this is called once per query.
+    pub fn timestamp_filters(&self) -> Result<Vec<FilterType<u64>>, TimestampError> {
+        self.filters()
+            .into_iter()
+            .flatten()
+            .filter_map(|f| match f {
+                CheckpointFilter::Timestamp(t) => Some(t.resolve()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the inclusive `(start, end)` time bounds, in milliseconds, set by the timestamp filters.
+    pub fn timestamp_bounds(&self) -> Result<(Option<u64>, Option<u64>), TimestampError> {
+        Ok(inclusive_bounds(&self.timestamp_filters()?))
+    }
+
+    pub fn has_timestamp_filter(&self) -> bool {
+        match self.filters() {
+            Some(filters) => filters
+                .iter()
+                .any(|f| matches!(f, CheckpointFilter::Timestamp(_))),
+            None => false,
+        }
+    }
+
+    pub fn epoch_filters(&self) -> Vec<FilterType<u64>> {
+        self.filters()
+            .map(|filters| {
+                filters
+                    .iter()
+                    .filter_map(|f| match f {
+                        CheckpointFilter::Epoch(e) => Some(e.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the filters narrow the query down to a range of checkpoints that can be fetched.
+    pub fn has_range_filter(&self) -> bool {
+        match self.filters() {
+            Some(filters) => filters.iter().any(|f| {
+                matches!(
+                    f,
+                    CheckpointFilter::Range(_)
+                        | CheckpointFilter::Comparison(_)
+                        | CheckpointFilter::Timestamp(_)
+                        | CheckpointFilter::Epoch(_)
+                )
+            }),
+            None => false,
+        }
+    }
+
+    /// Whether the query needs the per-checkpoint gas costs, either as a column or in a filter.
+    pub fn uses_gas_costs(&self) -> bool {
+        self.fields.iter().any(CheckpointField::is_gas_cost)
+            || self.filters().map_or(false, |filters| {
+                filters.iter().any(|f| {
+                    matches!(
+                        f,
+                        CheckpointFilter::ComputationCost(_)
+                            | CheckpointFilter::StorageCost(_)
+                            | CheckpointFilter::StorageRebate(_)
+                            | CheckpointFilter::NonRefundableStorageFee(_)
+                    )
+                })
+            })
+    }
+
+    /// Checks a checkpoint against the filters. `gas_cost_summary` holds the costs of the
+    /// checkpoint's own transactions, not the epoch's rolling totals, and `timestamp_filters`
+    /// are the filters returned by `timestamp_filters`.
+    pub fn filter(
+        &self,
+        checkpoint: &RpcCheckpoint,
+        gas_cost_summary: &GasCostSummary,
+        timestamp_filters: &[FilterType<u64>],
+    ) -> bool {
+        if let Some(filters) = &self.filter {
+            filters.iter().all(|filter| match filter {
+                // Checkpoint ranges are resolved into the checkpoints that are fetched.
+                CheckpointFilter::Range(_) | CheckpointFilter::Comparison(_) => true,
+                CheckpointFilter::Timestamp(_) => timestamp_filters
+                    .iter()
+                    .all(|t| t.compare(&checkpoint.timestamp_ms)),
+                CheckpointFilter::Epoch(e) => e.compare(&checkpoint.epoch),
+                CheckpointFilter::Transactions(t) => t.compare(&checkpoint.transactions.len()),
+                CheckpointFilter::ComputationCost(c) => {
+                    c.compare(&gas_cost_summary.computation_cost)
+                }
+                CheckpointFilter::StorageCost(c) => c.compare(&gas_cost_summary.storage_cost),
+                CheckpointFilter::StorageRebate(r) => r.compare(&gas_cost_summary.storage_rebate),
+                CheckpointFilter::NonRefundableStorageFee(f) => {
+                    f.compare(&gas_cost_summary.non_refundable_storage_fee)
+                }
+                CheckpointFilter::NetworkTotalTransactions(n) => {
+                    n.compare(&checkpoint.network_total_transactions)
+                }
+                // Subqueries are resolved into ids before the query runs.
+                CheckpointFilter::Subquery(_) => true,
+            })
+        } else {
+            true
+        }
+    }
+}
+
+impl Display for Checkpoint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.query_text().fmt(f)
+    }
 }
 
 impl TryFrom<Pairs<'_, Rule>> for Checkpoint {
@@ -167,22 +430,22 @@ impl TryFrom<Pairs<'_, Rule>> for Checkpoint {
         let mut fields: Vec<CheckpointField> = vec![];
         let mut ids: Vec<CheckpointId> = vec![];
         let mut filter: Option<Vec<CheckpointFilter>> = None;
+        let mut in_filters: Vec<InFilter> = vec![];
 
         for pair in pairs {
             match pair.as_rule() {
+                Rule::distinct => {}
                 Rule::checkpoint_fields => {
-                    let inner_pairs = pair.into_inner();
+                    let inner_pairs = pair.clone().into_inner();
 
                     if let Some(pair) = inner_pairs.peek() {
                         if pair.as_rule() == Rule::wildcard {
-                            fields = CheckpointField::all_variants().to_vec();
+                            fields = CheckpointField::wildcard_variants();
                             continue;
                         }
                     }
 
-                    fields = inner_pairs
-                        .map(|pair| CheckpointField::try_from(pair.as_str()))
-                        .collect::<Result<Vec<CheckpointField>, CheckpointFieldError>>()?;
+                    add_fields(&mut fields, pair, |field| CheckpointField::try_from(field))?;
                 }
                 // TODO: handle block number list
                 Rule::checkpoint_id => {
@@ -215,11 +478,27 @@ impl TryFrom<Pairs<'_, Rule>> for Checkpoint {
                     }
                 }
                 Rule::checkpoint_filter => {
-                    filter = Some(
-                        pair.into_inner()
-                            .map(|pair| CheckpointFilter::try_from(pair))
-                            .collect::<Result<Vec<CheckpointFilter>, CheckpointFilterError>>()?,
-                    );
+                    let inner_pairs = pair.into_inner();
+                    if let Some(pair) = inner_pairs.peek() {
+                        if pair.as_rule() == Rule::expr_filter {
+                            add_fields(&mut fields, pair, |field| {
+                                CheckpointField::try_from(field)
+                            })?;
+                            continue;
+                        }
+                        if pair.as_rule() == Rule::in_filter {
+                            in_filters.push(InFilter::try_from(pair)?);
+                            continue;
+                        }
+                    }
+                    let next_filters = inner_pairs
+                        .map(|pair| CheckpointFilter::try_from(pair))
+                        .collect::<Result<Vec<CheckpointFilter>, CheckpointFilterError>>(
+                    )?;
+                    filter.get_or_insert_with(Vec::new).extend(next_filters);
+                }
+                Rule::variable => {
+                    in_filters.push(InFilter::variable(Checkpoint::IN_COLUMNS[0], pair));
                 }
                 _ => {
                     return Err(CheckpointError::UnexpectedToken(pair.as_str().to_string()));
@@ -227,10 +506,43 @@ impl TryFrom<Pairs<'_, Rule>> for Checkpoint {
             }
         }
 
-        Ok(Checkpoint {
+        let mut checkpoint = Checkpoint {
             ids: Some(ids),
             filter,
             fields,
+        };
+        for in_filter in in_filters {
+            checkpoint.add_in_filter(in_filter)?;
+        }
+
+        Ok(checkpoint)
+    }
+}
+
+impl InFilterTarget for Checkpoint {
+    const IN_COLUMNS: &'static [&'static str] = &["number", "checkpoint"];
+
+    fn apply_in(&mut self, column: &str, values: &[String]) -> Result<(), InFilterError> {
+        let numbers = parse_in_values(column, values, CheckpointNumberOrTag::from_str)?;
+        let numbers: Vec<CheckpointId> = numbers.into_iter().map(CheckpointId::Number).collect();
+        // Checkpoints parsed without ids have an empty id list rather than none.
+        match self.ids.as_mut() {
+            Some(ids) if !ids.is_empty() => ids.retain(|id| numbers.contains(id)),
+            _ => self.ids = Some(numbers),
+        }
+        Ok(())
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        self.filter
+            .get_or_insert_with(Vec::new)
+            .push(CheckpointFilter::Subquery(subquery));
+    }
+
+    fn take_subqueries(&mut self) -> Vec<Subquery> {
+        take_subquery_filters(&mut self.filter, |f| match f {
+            CheckpointFilter::Subquery(subquery) => Some(subquery),
+            _ => None,
         })
     }
 }
@@ -240,13 +552,80 @@ pub enum CheckpointFilterError {
     #[error("Invalid block filter property: {0}")]
     InvalidCheckpointFilterProperty(String),
 
+    #[error("Missing operator in filter")]
+    MissingOperator,
+
+    #[error(transparent)]
+    ComparisonFilterError(#[from] ComparisonFilterError),
+
     #[error(transparent)]
     EntityIdError(#[from] EntityIdError),
+
+    #[error(transparent)]
+    TimestampError(#[from] TimestampError),
+
+    #[error(transparent)]
+    FilterError(#[from] FilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum CheckpointFilter {
     Range(CheckpointRange),
+    Comparison(ComparisonFilter<CheckpointNumberOrTag>),
+    Timestamp(FilterType<Timestamp>),
+    Epoch(FilterType<u64>),
+    Transactions(FilterType<usize>), // Number of transactions in the checkpoint
+    ComputationCost(FilterType<u64>),
+    StorageCost(FilterType<u64>),
+    StorageRebate(FilterType<u64>),
+    NonRefundableStorageFee(FilterType<u64>),
+    NetworkTotalTransactions(FilterType<u64>),
+    Subquery(Subquery),
+}
+
+impl Display for CheckpointFilter {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CheckpointFilter::Range(range) => write!(f, "checkpoint = {range}"),
+            CheckpointFilter::Comparison(filter) => write!(f, "checkpoint {filter}"),
+            CheckpointFilter::Timestamp(filter) => write!(f, "timestamp_ms {filter}"),
+            CheckpointFilter::Epoch(filter) => write!(f, "epoch {filter}"),
+            CheckpointFilter::Transactions(filter) => write!(f, "transactions {filter}"),
+            CheckpointFilter::ComputationCost(filter) => write!(f, "computation_cost {filter}"),
+            CheckpointFilter::StorageCost(filter) => write!(f, "storage_cost {filter}"),
+            CheckpointFilter::StorageRebate(filter) => write!(f, "storage_rebate {filter}"),
+            CheckpointFilter::NonRefundableStorageFee(filter) => {
+                write!(f, "non_refundable_storage_fee {filter}")
+            }
+            CheckpointFilter::NetworkTotalTransactions(filter) => {
+                write!(f, "network_total_transactions {filter}")
+            }
+            CheckpointFilter::Subquery(subquery) => write!(f, "{subquery}"),
+        }
+    }
+}
+
+impl CheckpointFilter {
+    // Helper function to parse filter components
+    fn parse_filter<'a, T>(
+        pair: Pair<'a, Rule>,
+        constructor: impl FnOnce(FilterType<T>) -> CheckpointFilter,
+    ) -> Result<CheckpointFilter, CheckpointFilterError>
+    where
+        T: FromStr,
+        FilterType<T>: TryFrom<(Pair<'a, Rule>, T), Error = FilterError>,
+    {
+        let mut inner_pair = pair.into_inner();
+        let operator = inner_pair
+            .next()
+            .ok_or(CheckpointFilterError::MissingOperator)?;
+        let value = inner_pair.as_str().trim();
+        let value = value.parse::<T>().map_err(|_| {
+            CheckpointFilterError::InvalidCheckpointFilterProperty(value.to_string())
+        })?;
+
+        Ok(constructor(FilterType::try_from((operator, value))?))
+    }
 }
 
 impl TryFrom<Pair<'_, Rule>> for CheckpointFilter {
@@ -255,17 +634,61 @@ impl TryFrom<Pair<'_, Rule>> for CheckpointFilter {
     fn try_from(value: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match value.as_rule() {
             Rule::checkpointrange_filter => {
-                let range = value.as_str().trim_start_matches("block ").trim();
-                let (start, end) = match range.split_once(":") {
-                    //if ":" is present, we have an start and an end.
-                    Some((start, end)) => (
-                        parse_checkpoint_number_or_tag(start)?,
-                        Some(parse_checkpoint_number_or_tag(end)?),
-                    ),
-                    //else we only have start.
-                    None => (parse_checkpoint_number_or_tag(range)?, None),
-                };
-                Ok(CheckpointFilter::Range(CheckpointRange { start, end }))
+                let mut inner_pair = value
+                    .into_inner()
+                    .next()
+                    .ok_or(CheckpointFilterError::MissingOperator)?
+                    .into_inner();
+                let operator = inner_pair
+                    .next()
+                    .ok_or(CheckpointFilterError::MissingOperator)?;
+                let checkpoint_id = inner_pair.as_str().trim();
+
+                match operator.as_rule() {
+                    Rule::eq_operator => {
+                        let (start, end) = match checkpoint_id.split_once(":") {
+                            //if ":" is present, we have an start and an end.
+                            Some((start, end)) => (
+                                parse_checkpoint_number_or_tag(start)?,
+                                Some(parse_checkpoint_number_or_tag(end)?),
+                            ),
+                            //else we only have start.
+                            None => (parse_checkpoint_number_or_tag(checkpoint_id)?, None),
+                        };
+                        Ok(CheckpointFilter::Range(CheckpointRange { start, end }))
+                    }
+                    Rule::comparison_operators => {
+                        let number = parse_checkpoint_number_or_tag(checkpoint_id)?;
+                        Ok(CheckpointFilter::Comparison(ComparisonFilter::try_from((
+                            operator, number,
+                        ))?))
+                    }
+                    _ => Err(CheckpointFilterError::InvalidCheckpointFilterProperty(
+                        operator.as_str().to_string(),
+                    )),
+                }
+            }
+            Rule::timestamp_ms_filter_type => {
+                Ok(CheckpointFilter::Timestamp(FilterType::try_from(value)?))
+            }
+            Rule::epoch_filter_type => Self::parse_filter(value, CheckpointFilter::Epoch),
+            Rule::transactions_filter_type => {
+                Self::parse_filter(value, CheckpointFilter::Transactions)
+            }
+            Rule::computation_cost_filter_type => {
+                Self::parse_filter(value, CheckpointFilter::ComputationCost)
+            }
+            Rule::storage_cost_filter_type => {
+                Self::parse_filter(value, CheckpointFilter::StorageCost)
+            }
+            Rule::storage_rebate_filter_type => {
+                Self::parse_filter(value, CheckpointFilter::StorageRebate)
+            }
+            Rule::non_refundable_storage_fee_filter_type => {
+                Self::parse_filter(value, CheckpointFilter::NonRefundableStorageFee)
+            }
+            Rule::network_total_transactions_filter_type => {
+                Self::parse_filter(value, CheckpointFilter::NetworkTotalTransactions)
             }
             _ => Err(CheckpointFilterError::InvalidCheckpointFilterProperty(
                 value.as_str().to_string(),
@@ -295,7 +718,40 @@ pub enum CheckpointField {
     StorageCost,
     StorageRebate,
     NonRefundableStorageFee,
+    TransactionDigests,
+    TransactionDigest,
+    EndOfEpoch,
+    NextEpochProtocolVersion,
+    CheckpointCommitments,
+}
+
+impl CheckpointField {
+    /// Fields that `*` expands to. `transaction_digest` returns one row per transaction and
+    /// `transaction_digests` can get large, so both have to be selected explicitly.
+    pub fn wildcard_variants() -> Vec<CheckpointField> {
+        CheckpointField::all_variants()
+            .iter()
+            .filter(|field| {
+                !matches!(
+                    field,
+                    CheckpointField::TransactionDigests | CheckpointField::TransactionDigest
+                )
+            })
+            .copied()
+            .collect()
+    }
+
+    pub fn is_gas_cost(&self) -> bool {
+        matches!(
+            self,
+            CheckpointField::ComputationCost
+                | CheckpointField::StorageCost
+                | CheckpointField::StorageRebate
+                | CheckpointField::NonRefundableStorageFee
+        )
+    }
 }
+
 impl Display for CheckpointField {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -312,6 +768,11 @@ impl Display for CheckpointField {
             CheckpointField::StorageCost => write!(f, "storage_cost"),
             CheckpointField::StorageRebate => write!(f, "storage_rebate"),
             CheckpointField::NonRefundableStorageFee => write!(f, "non_refundable_storage_fee"),
+            CheckpointField::TransactionDigests => write!(f, "transaction_digests"),
+            CheckpointField::TransactionDigest => write!(f, "transaction_digest"),
+            CheckpointField::EndOfEpoch => write!(f, "end_of_epoch"),
+            CheckpointField::NextEpochProtocolVersion => write!(f, "next_epoch_protocol_version"),
+            CheckpointField::CheckpointCommitments => write!(f, "checkpoint_commitments"),
         }
     }
 }
@@ -334,6 +795,11 @@ impl TryFrom<&str> for CheckpointField {
             "storage_cost" => Ok(CheckpointField::StorageCost),
             "storage_rebate" => Ok(CheckpointField::StorageRebate),
             "non_refundable_storage_fee" => Ok(CheckpointField::NonRefundableStorageFee),
+            "transaction_digests" => Ok(CheckpointField::TransactionDigests),
+            "transaction_digest" => Ok(CheckpointField::TransactionDigest),
+            "end_of_epoch" => Ok(CheckpointField::EndOfEpoch),
+            "next_epoch_protocol_version" => Ok(CheckpointField::NextEpochProtocolVersion),
+            "checkpoint_commitments" => Ok(CheckpointField::CheckpointCommitments),
             invalid_field => Err(CheckpointFieldError::InvalidCheckpointField(
                 invalid_field.to_string(),
             )),
@@ -347,9 +813,11 @@ pub enum CheckpointRangeError {
     UnableToFetchCheckpointNumber(CheckpointNumberOrTag),
     #[error("Start block must be less than end block")]
     StartCheckpointMustBeLessThanEndCheckpoint,
+    #[error("Epoch {0} not found")]
+    EpochNotFound(u64),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
 pub struct CheckpointRange {
     start: CheckpointNumberOrTag,
     end: Option<CheckpointNumberOrTag>,
@@ -372,7 +840,43 @@ impl CheckpointRange {
         self.end
     }
 
-    pub async fn resolve_checkpoint_numbers(&self, provider: &SuiClient) -> Result<Vec<u64>> {
+    /// Expresses the range as the filters it stands for: `a:b` is `>= a` and `<= b`,
+    /// a single checkpoint `a` is `= a`.
+    pub fn to_filters(&self) -> Vec<FilterType<CheckpointNumberOrTag>> {
+        match self.end {
+            Some(end) => vec![
+                FilterType::Comparison(ComparisonFilter::Gte(self.start)),
+                FilterType::Comparison(ComparisonFilter::Lte(end)),
+            ],
+            None => vec![FilterType::Equality(EqualityFilter::Eq(self.start))],
+        }
+    }
+
+    /// Normalizes a list of `checkpoint` filters (`checkpoint >= 1000, checkpoint < 2000`) into
+    /// the single range of checkpoints they select, resolving tags on the way.
+    /// Returns `None` when the filters don't overlap.
+    pub async fn from_filters(
+        filters: &[FilterType<CheckpointNumberOrTag>],
+        provider: &SuiClient,
+    ) -> Result<Option<CheckpointRange>> {
+        let mut resolved_filters = Vec::with_capacity(filters.len());
+        for filter in filters {
+            let number = get_checkpoint_number_from_tag(provider, filter.value()).await?;
+            resolved_filters.push(filter.map(|_| number));
+        }
+        let latest =
+            get_checkpoint_number_from_tag(provider, &CheckpointNumberOrTag::Latest).await?;
+
+        Ok(checkpoint_bounds(&resolved_filters, latest)
+            .map(|(start, end)| CheckpointRange::new(start.into(), Some(end.into()))))
+    }
+
+    /// The checkpoint numbers of the range, with its tags resolved. They aren't collected, a
+    /// range up to `latest` can hold millions of checkpoints.
+    pub async fn resolve_checkpoint_numbers(
+        &self,
+        provider: &SuiClient,
+    ) -> Result<RangeInclusive<u64>> {
         let (start_block, end_block) = self.range();
         let start_block_number = get_checkpoint_number_from_tag(provider, &start_block).await?;
 
@@ -389,13 +893,7 @@ impl CheckpointRange {
             }
         }
 
-        match end_block_number {
-            Some(end) => {
-                let range = start_block_number..=end;
-                return Ok(range.collect());
-            }
-            None => Ok(vec![start_block_number]),
-        }
+        Ok(start_block_number..=end_block_number.unwrap_or(start_block_number))
     }
 }
 
@@ -418,23 +916,329 @@ impl Display for CheckpointRange {
     }
 }
 
+/// Folds resolved `checkpoint` filters into the inclusive `(start, end)` checkpoints they select.
+/// The range starts at genesis and ends at `latest` unless the filters narrow it, and `!=`
+/// filters don't bound it. Returns `None` when the range is empty.
+pub fn checkpoint_bounds(filters: &[FilterType<u64>], latest: u64) -> Option<(u64, u64)> {
+    // `checkpoint < 0` has no inclusive upper bound to fold.
+    if filters
+        .iter()
+        .any(|f| matches!(f, FilterType::Comparison(ComparisonFilter::Lt(0))))
+    {
+        return None;
+    }
+
+    let (start, end) = inclusive_bounds(filters);
+    let (start, end) = (
+        start.unwrap_or(0),
+        end.map_or(latest, |end| end.min(latest)),
+    );
+    (start <= end).then_some((start, end))
+}
+
 pub async fn get_checkpoint_number_from_tag(
     provider: &SuiClient,
     number_or_tag: &CheckpointNumberOrTag,
 ) -> Result<u64> {
-    match number_or_tag {
-        CheckpointNumberOrTag::Number(number) => Ok(*number),
-        CheckpointNumberOrTag::Earliest => Ok(0),
-        CheckpointNumberOrTag::Latest => match provider
+    let (tag, offset) = match *number_or_tag {
+        CheckpointNumberOrTag::Number(number) => return Ok(number),
+        CheckpointNumberOrTag::Latest => (CheckpointTag::Latest, 0),
+        CheckpointNumberOrTag::Earliest => (CheckpointTag::Earliest, 0),
+        CheckpointNumberOrTag::EpochStart(epoch) => (CheckpointTag::EpochStart(epoch), 0),
+        CheckpointNumberOrTag::Relative(tag, offset) => (tag, offset),
+    };
+
+    if tag == CheckpointTag::Latest && offset > 0 {
+        return Err(
+            CheckpointNumberOrTagError::PastLatestCheckpoint(number_or_tag.to_string()).into(),
+        );
+    }
+
+    let number = match tag {
+        CheckpointTag::Earliest => 0,
+        CheckpointTag::Latest => provider
             .read_api()
             .get_latest_checkpoint_sequence_number()
             .await
-        {
-            Ok(number) => Ok(number),
-            Err(_) => Err(CheckpointRangeError::UnableToFetchCheckpointNumber(
-                number_or_tag.clone(),
-            )
-            .into()),
+            .map_err(|_| CheckpointRangeError::UnableToFetchCheckpointNumber(*number_or_tag))?,
+        CheckpointTag::EpochStart(epoch) => get_epoch_start_checkpoint(provider, epoch).await?,
+    };
+
+    // Offsets that go past genesis are clamped to the first checkpoint.
+    Ok(number.saturating_add_signed(offset))
+}
+
+/// Fetches the first checkpoint of `epoch` through the epoch API.
+async fn get_epoch_start_checkpoint(provider: &SuiClient, epoch: u64) -> Result<u64> {
+    Ok(get_epoch_info(provider, epoch).await?.first_checkpoint_id)
+}
+
+/// Returns the first and last checkpoints of `epoch`. The last checkpoint of the ongoing epoch
+/// is the latest one.
+pub async fn get_epoch_checkpoints(provider: &SuiClient, epoch: u64) -> Result<(u64, u64)> {
+    let info = get_epoch_info(provider, epoch).await?;
+    let last_checkpoint = match info.end_of_epoch_info {
+        Some(end_of_epoch) => end_of_epoch.last_checkpoint_id,
+        None => get_checkpoint_number_from_tag(provider, &CheckpointNumberOrTag::Latest).await?,
+    };
+
+    Ok((info.first_checkpoint_id, last_checkpoint))
+}
+
+async fn get_epoch_info(provider: &SuiClient, epoch: u64) -> Result<EpochInfo> {
+    // The cursor is exclusive, so it points at the epoch right before the one we want.
+    let cursor = epoch.checked_sub(1).map(BigInt::from);
+    let epochs = provider
+        .http()
+        .get_epochs(cursor, Some(1), Some(false))
+        .await
+        .map_err(|_| {
+            CheckpointRangeError::UnableToFetchCheckpointNumber(CheckpointNumberOrTag::EpochStart(
+                epoch,
+            ))
+        })?;
+
+    epochs
+        .data
+        .into_iter()
+        .find(|info| info.epoch == epoch)
+        .ok_or_else(|| CheckpointRangeError::EpochNotFound(epoch).into())
+}
+
+/// Translates `epoch` filters into the checkpoint bounds of the epochs they select, e.g.
+/// `epoch = 500` becomes `checkpoint >= epoch_start(500)` and `checkpoint <=` its last checkpoint.
+/// `!=` filters don't bound the range and are left to [`Checkpoint::filter`].
+pub async fn resolve_epoch_filters(
+    epoch_filters: &[FilterType<u64>],
+    provider: &SuiClient,
+) -> Result<Vec<FilterType<CheckpointNumberOrTag>>> {
+    let mut checkpoint_filters = Vec::new();
+
+    for filter in epoch_filters {
+        if matches!(filter, FilterType::Equality(EqualityFilter::Neq(_))) {
+            continue;
+        }
+
+        let (first, last) = get_epoch_checkpoints(provider, *filter.value()).await?;
+        let (first, last) = (
+            CheckpointNumberOrTag::Number(first),
+            CheckpointNumberOrTag::Number(last),
+        );
+
+        match filter {
+            FilterType::Equality(_) => checkpoint_filters.extend([
+                FilterType::Comparison(ComparisonFilter::Gte(first)),
+                FilterType::Comparison(ComparisonFilter::Lte(last)),
+            ]),
+            FilterType::Comparison(ComparisonFilter::Gt(_)) => {
+                checkpoint_filters.push(FilterType::Comparison(ComparisonFilter::Gt(last)))
+            }
+            FilterType::Comparison(ComparisonFilter::Gte(_)) => {
+                checkpoint_filters.push(FilterType::Comparison(ComparisonFilter::Gte(first)))
+            }
+            FilterType::Comparison(ComparisonFilter::Lt(_)) => {
+                checkpoint_filters.push(FilterType::Comparison(ComparisonFilter::Lt(first)))
+            }
+            FilterType::Comparison(ComparisonFilter::Lte(_)) => {
+                checkpoint_filters.push(FilterType::Comparison(ComparisonFilter::Lte(last)))
+            }
+        }
+    }
+
+    Ok(checkpoint_filters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{
+            entity::Entity,
+            types::{Expression, GetExpression},
         },
+        interpreter::frontend::parser::Parser,
+    };
+
+    fn checkpoint(source: &str) -> Checkpoint {
+        let expressions = Parser::new(source).parse_expressions().unwrap();
+        match expressions.into_iter().next() {
+            Some(Expression::Get(GetExpression {
+                entity: Entity::Checkpoint(checkpoint),
+                ..
+            })) => checkpoint,
+            _ => panic!("expected a checkpoint query"),
+        }
+    }
+
+    #[test]
+    fn test_parse_checkpoint_tags() {
+        assert_eq!(
+            "earliest".parse::<CheckpointNumberOrTag>().unwrap(),
+            CheckpointNumberOrTag::Earliest
+        );
+        assert_eq!(
+            "latest - 100".parse::<CheckpointNumberOrTag>().unwrap(),
+            CheckpointNumberOrTag::Relative(CheckpointTag::Latest, -100)
+        );
+        assert_eq!(
+            "epoch_start(700)".parse::<CheckpointNumberOrTag>().unwrap(),
+            CheckpointNumberOrTag::EpochStart(700)
+        );
+        assert_eq!(
+            "epoch_start(700)+5"
+                .parse::<CheckpointNumberOrTag>()
+                .unwrap(),
+            CheckpointNumberOrTag::Relative(CheckpointTag::EpochStart(700), 5)
+        );
+        assert_eq!(
+            "1000 - 10".parse::<CheckpointNumberOrTag>().unwrap(),
+            CheckpointNumberOrTag::Number(990)
+        );
+        assert!("10 - 20".parse::<CheckpointNumberOrTag>().is_err());
+        assert!(matches!(
+            "latest + 10".parse::<CheckpointNumberOrTag>(),
+            Err(CheckpointNumberOrTagError::PastLatestCheckpoint(_))
+        ));
+        assert_eq!(
+            "latest + 0".parse::<CheckpointNumberOrTag>().unwrap(),
+            CheckpointNumberOrTag::Latest
+        );
+        assert!("latest - ".parse::<CheckpointNumberOrTag>().is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_bounds() {
+        let gt = |n| FilterType::Comparison(ComparisonFilter::Gt(n));
+        let gte = |n| FilterType::Comparison(ComparisonFilter::Gte(n));
+        let lt = |n| FilterType::Comparison(ComparisonFilter::Lt(n));
+        let lte = |n| FilterType::Comparison(ComparisonFilter::Lte(n));
+        let eq = |n| FilterType::Equality(EqualityFilter::Eq(n));
+        let neq = |n| FilterType::Equality(EqualityFilter::Neq(n));
+        let latest = 5000;
+
+        assert_eq!(checkpoint_bounds(&[], latest), Some((0, latest)));
+        assert_eq!(
+            checkpoint_bounds(&[gte(1000), lt(2000)], latest),
+            Some((1000, 1999))
+        );
+        assert_eq!(
+            checkpoint_bounds(&[gt(1000), lte(2000)], latest),
+            Some((1001, 2000))
+        );
+        assert_eq!(checkpoint_bounds(&[gt(4900)], latest), Some((4901, latest)));
+        assert_eq!(checkpoint_bounds(&[lt(10)], latest), Some((0, 9)));
+        assert_eq!(checkpoint_bounds(&[eq(42)], latest), Some((42, 42)));
+        // The tightest bounds win.
+        assert_eq!(
+            checkpoint_bounds(&[gte(10), gte(20), lte(40), lt(30)], latest),
+            Some((20, 29))
+        );
+        // `!=` doesn't bound the range, it is checked on the fetched checkpoints.
+        assert_eq!(checkpoint_bounds(&[neq(42)], latest), Some((0, latest)));
+        assert_eq!(
+            checkpoint_bounds(&[gte(40), neq(42), lte(50)], latest),
+            Some((40, 50))
+        );
+        // Upper bounds past the latest checkpoint are clamped to it.
+        assert_eq!(
+            checkpoint_bounds(&[gte(4000), lte(9000)], latest),
+            Some((4000, latest))
+        );
+    }
+
+    #[test]
+    fn test_empty_checkpoint_bounds() {
+        let gt = |n| FilterType::Comparison(ComparisonFilter::Gt(n));
+        let gte = |n| FilterType::Comparison(ComparisonFilter::Gte(n));
+        let lt = |n| FilterType::Comparison(ComparisonFilter::Lt(n));
+        let lte = |n| FilterType::Comparison(ComparisonFilter::Lte(n));
+        let eq = |n| FilterType::Equality(EqualityFilter::Eq(n));
+        let latest = 5000;
+
+        // Inverted ranges.
+        assert_eq!(checkpoint_bounds(&[gte(2000), lt(1000)], latest), None);
+        assert_eq!(checkpoint_bounds(&[gt(10), lt(11)], latest), None);
+        assert_eq!(checkpoint_bounds(&[eq(10), eq(11)], latest), None);
+        // Nothing below genesis or past the latest checkpoint.
+        assert_eq!(checkpoint_bounds(&[lt(0)], latest), None);
+        assert_eq!(checkpoint_bounds(&[gt(latest)], latest), None);
+        assert_eq!(
+            checkpoint_bounds(&[gte(latest + 1), lte(latest + 10)], latest),
+            None
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_filters() {
+        let query = checkpoint(
+            "SELECT * FROM checkpoint WHERE checkpoint >= 1000, checkpoint < latest - 10, \
+             transactions > 5, computation_cost > 100 ON sui_mainnet",
+        );
+        assert_eq!(
+            query.checkpoint_filters(),
+            vec![
+                FilterType::Comparison(ComparisonFilter::Gte(CheckpointNumberOrTag::Number(1000))),
+                FilterType::Comparison(ComparisonFilter::Lt(CheckpointNumberOrTag::Relative(
+                    CheckpointTag::Latest,
+                    -10
+                ))),
+            ]
+        );
+        assert!(query.has_range_filter());
+        assert!(query.uses_gas_costs());
+
+        let query = checkpoint(
+            "SELECT number FROM checkpoint WHERE checkpoint = 10:20, epoch != 3 ON sui_mainnet",
+        );
+        assert_eq!(
+            query.checkpoint_filters(),
+            vec![
+                FilterType::Comparison(ComparisonFilter::Gte(CheckpointNumberOrTag::Number(10))),
+                FilterType::Comparison(ComparisonFilter::Lte(CheckpointNumberOrTag::Number(20))),
+            ]
+        );
+        assert_eq!(
+            query.epoch_filters(),
+            vec![FilterType::Equality(EqualityFilter::Neq(3))]
+        );
+        assert!(!query.uses_gas_costs());
+
+        let query = checkpoint(
+            "SELECT * FROM checkpoint WHERE timestamp_ms >= '2025-01-01', \
+             timestamp_ms < 1735776000000 ON sui_mainnet",
+        );
+        assert!(query.checkpoint_filters().is_empty());
+        assert!(query.has_timestamp_filter());
+        assert_eq!(
+            query.timestamp_bounds().unwrap(),
+            (Some(1735689600000), Some(1735775999999))
+        );
+    }
+
+    #[test]
+    fn test_filters_without_range() {
+        // Transaction counts and gas costs are only known once checkpoints are fetched, so
+        // they don't say which checkpoints to fetch.
+        let query = checkpoint(
+            "SELECT * FROM checkpoint WHERE transactions > 1000, storage_cost > 0 ON sui_mainnet",
+        );
+        assert!(!query.has_range_filter());
+        assert!(query.checkpoint_filters().is_empty());
+        assert!(query.epoch_filters().is_empty());
+        assert!(query.uses_gas_costs());
+    }
+
+    #[test]
+    fn test_checkpoint_tag_display_round_trip() {
+        for source in [
+            "latest",
+            "earliest",
+            "epoch_start(3)",
+            "latest - 100",
+            "epoch_start(3) + 2",
+        ] {
+            let tag = source.parse::<CheckpointNumberOrTag>().unwrap();
+            assert_eq!(tag.to_string(), source);
+        }
     }
 }
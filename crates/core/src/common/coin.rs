@@ -1,8 +1,17 @@
+use super::entity::QueryText;
+use super::expression::add_fields;
+use super::filters::{EqualityFilter, EqualityFilterError, Filter, FilterError, FilterType};
+use super::join::JoinEntity;
+use super::literal::unquote;
+use super::subquery::{
+    apply_in_ids, take_subquery_filters, InFilter, InFilterError, InFilterTarget, Subquery,
+};
 use crate::interpreter::frontend::parser::Rule;
 use eql_macros::EnumVariants;
 use pest::iterators::{Pair, Pairs};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
+use sui_types::base_types::SuiAddress;
 
 #[derive(thiserror::Error, Debug)]
 pub enum CoinError {
@@ -14,9 +23,12 @@ pub enum CoinError {
 
     #[error(transparent)]
     CoinFilterError(#[from] CoinFilterError),
+
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Coin {
     id: Option<Vec<String>>,
     filter: Option<Vec<CoinFilter>>,
@@ -43,6 +55,56 @@ impl Coin {
     pub fn fields(&self) -> Vec<CoinField> {
         self.fields.clone()
     }
+
+    /// Coin types selected with `type = ...` filters.
+    pub fn coin_type_filters(&self) -> Vec<&EqualityFilter<String>> {
+        self.filter
+            .iter()
+            .flatten()
+            .filter_map(|f| match f {
+                CoinFilter::CoinType(filter) => Some(filter),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Addresses whose coin types are selected with `owner = ...` filters.
+    pub fn owners(&self) -> Vec<SuiAddress> {
+        self.filter
+            .iter()
+            .flatten()
+            .filter_map(|f| match f {
+                CoinFilter::Owner(owner) => Some(*owner),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks the coin metadata against the filters that can't be used to find coin types.
+    pub fn matches_decimals(&self, decimals: u8) -> bool {
+        self.filter.iter().flatten().all(|f| match f {
+            CoinFilter::Decimals(filter) => filter.compare(&decimals),
+            _ => true,
+        })
+    }
+}
+
+impl Coin {
+    pub(crate) fn query_text(&self) -> QueryText {
+        QueryText::new(
+            JoinEntity::Coin,
+            &self.fields,
+            CoinField::all_variants(),
+            self.id.iter().flatten(),
+            self.filter.iter().flatten(),
+        )
+    }
+}
+
+impl Display for Coin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.query_text().fmt(f)
+    }
 }
 
 impl TryFrom<Pairs<'_, Rule>> for Coin {
@@ -52,24 +114,24 @@ impl TryFrom<Pairs<'_, Rule>> for Coin {
         let mut fields: Vec<CoinField> = vec![];
         let mut id: Option<Vec<String>> = None;
         let mut filter: Option<Vec<CoinFilter>> = None;
+        let mut in_filters: Vec<InFilter> = vec![];
 
         for pair in pairs {
             match pair.as_rule() {
+                Rule::distinct => {}
                 Rule::coin_fields => {
-                    let inner_pairs = pair.into_inner();
+                    let inner_pairs = pair.clone().into_inner();
                     if let Some(pair) = inner_pairs.peek() {
                         if pair.as_rule() == Rule::wildcard {
                             fields = CoinField::all_variants().to_vec();
                             continue;
                         }
                     }
-                    fields = inner_pairs
-                        .map(CoinField::try_from)
-                        .collect::<Result<Vec<_>, _>>()?;
+                    add_fields(&mut fields, pair, |field| CoinField::try_from(field))?;
                 }
 
                 Rule::coin_id => {
-                    let val = pair.as_str().to_string();
+                    let val = unquote(pair.as_str());
 
                     if let Some(ref mut vec) = id {
                         vec.push(val);
@@ -78,19 +140,57 @@ impl TryFrom<Pairs<'_, Rule>> for Coin {
                     }
                 }
 
-                Rule::coin_filter_list => {
-                    filter = Some(
-                        pair.into_inner()
-                            .map(CoinFilter::try_from)
-                            .collect::<Result<Vec<_>, _>>()?,
-                    );
+                Rule::coin_filter => {
+                    let inner = pair.into_inner().next().unwrap();
+                    if inner.as_rule() == Rule::expr_filter {
+                        add_fields(&mut fields, inner, |field| CoinField::try_from(field))?;
+                        continue;
+                    }
+                    if inner.as_rule() == Rule::in_filter {
+                        in_filters.push(InFilter::try_from(inner)?);
+                        continue;
+                    }
+                    filter
+                        .get_or_insert_with(Vec::new)
+                        .push(CoinFilter::try_from(inner)?);
+                }
+
+                Rule::variable => {
+                    in_filters.push(InFilter::variable(Coin::IN_COLUMNS[0], pair));
                 }
 
                 _ => return Err(CoinError::UnexpectedToken(pair.as_str().to_string())),
             }
         }
 
-        Ok(Coin { id, filter, fields })
+        let mut coin = Coin { id, filter, fields };
+        for in_filter in in_filters {
+            coin.add_in_filter(in_filter)?;
+        }
+
+        Ok(coin)
+    }
+}
+
+impl InFilterTarget for Coin {
+    const IN_COLUMNS: &'static [&'static str] = &["id", "type", "coin_type"];
+
+    fn apply_in(&mut self, _column: &str, values: &[String]) -> Result<(), InFilterError> {
+        apply_in_ids(&mut self.id, values.to_vec());
+        Ok(())
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        self.filter
+            .get_or_insert_with(Vec::new)
+            .push(CoinFilter::Subquery(subquery));
+    }
+
+    fn take_subqueries(&mut self) -> Vec<Subquery> {
+        take_subquery_filters(&mut self.filter, |f| match f {
+            CoinFilter::Subquery(subquery) => Some(subquery),
+            _ => None,
+        })
     }
 }
 
@@ -101,47 +201,93 @@ pub enum CoinFilterError {
 
     #[error("Failed to parse coin id: {0}")]
     CoinParseError(String),
+
+    #[error("Missing operator in filter")]
+    MissingOperator,
+
+    #[error(transparent)]
+    EqualityFilterError(#[from] EqualityFilterError),
+
+    #[error(transparent)]
+    FilterError(#[from] FilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum CoinFilter {
-    CoinId(String),
+    CoinType(EqualityFilter<String>),
+    Owner(SuiAddress),
+    Decimals(FilterType<u8>),
+    Subquery(Subquery),
+}
+
+impl Display for CoinFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoinFilter::CoinType(filter) => write!(f, "coin_type {filter}"),
+            CoinFilter::Owner(owner) => write!(f, "owner = {owner}"),
+            CoinFilter::Decimals(filter) => write!(f, "decimals {filter}"),
+            CoinFilter::Subquery(subquery) => write!(f, "{subquery}"),
+        }
+    }
 }
 
 impl TryFrom<Pair<'_, Rule>> for CoinFilter {
     type Error = CoinFilterError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
-        match pair.as_rule() {
-            Rule::coin_id => {
-                let id = String::from_str(pair.into_inner().as_str())
+        let rule = pair.as_rule();
+        let mut inner_pair = pair.into_inner();
+        let operator = inner_pair.next().ok_or(CoinFilterError::MissingOperator)?;
+        let value = inner_pair.as_str().trim();
+
+        match rule {
+            Rule::coin_type_filter_type => Ok(CoinFilter::CoinType(EqualityFilter::try_from((
+                operator,
+                unquote(value),
+            ))?)),
+            Rule::coin_owner_filter_type => {
+                let owner = SuiAddress::from_str(value)
+                    .map_err(|e| CoinFilterError::CoinParseError(e.to_string()))?;
+                Ok(CoinFilter::Owner(owner))
+            }
+            Rule::decimals_filter_type => {
+                let decimals = value
+                    .parse::<u8>()
                     .map_err(|e| CoinFilterError::CoinParseError(e.to_string()))?;
-                Ok(CoinFilter::CoinId(id))
+                Ok(CoinFilter::Decimals(FilterType::try_from((
+                    operator, decimals,
+                ))?))
             }
-            _ => Err(CoinFilterError::UnexpectedToken(pair.as_str().to_string())),
+            _ => Err(CoinFilterError::UnexpectedToken(value.to_string())),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, EnumVariants)]
 pub enum CoinField {
+    Id,
     Decimals,
     Name,
     Symbol,
     Description,
     IconUrl,
     Chain,
+    TotalSupply,
+    TreasuryCap,
 }
 
 impl Display for CoinField {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
+            CoinField::Id => "id",
             CoinField::Name => "name",
             CoinField::Symbol => "symbol",
             CoinField::Description => "description",
             CoinField::IconUrl => "icon_url",
             CoinField::Chain => "chain",
             CoinField::Decimals => "decimals",
+            CoinField::TotalSupply => "total_supply",
+            CoinField::TreasuryCap => "treasury_cap",
         };
         write!(f, "{}", s)
     }
@@ -166,12 +312,15 @@ impl TryFrom<&str> for CoinField {
 
     fn try_from(value: &str) -> Result<Self, CoinFieldError> {
         match value {
+            "id" => Ok(CoinField::Id),
             "name" => Ok(CoinField::Name),
             "symbol" => Ok(CoinField::Symbol),
             "description" => Ok(CoinField::Description),
             "icon_url" => Ok(CoinField::IconUrl),
             "chain" => Ok(CoinField::Chain),
             "decimals" => Ok(CoinField::Decimals),
+            "total_supply" => Ok(CoinField::TotalSupply),
+            "treasury_cap" => Ok(CoinField::TreasuryCap),
             _ => Err(CoinFieldError::InvalidField(value.to_string())),
         }
     }
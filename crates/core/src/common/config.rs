@@ -1,27 +1,36 @@
-use super::chain::Chain;
+use super::chain::{Chain, ChainOrRpc};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use url::Url;
 
 const CONFIG_FILE: &str = "sui-ql-config.json";
 
 #[derive(Serialize, Deserialize, Debug)]
 struct ConfigFile {
+    #[serde(default)]
     chains: HashMap<String, ChainConfig>,
+    /// The chains of the queries without `ON`, e.g. `"sui_testnet"`.
+    #[serde(default)]
+    default_chain: Option<String>,
 }
 
+/// The RPCs of a public network, or of a network the config file names, like `localnet`.
 #[derive(Serialize, Deserialize, Debug)]
 struct ChainConfig {
     default: String,
+    #[serde(default)]
     rpcs: Vec<String>,
 }
 
 pub struct Config {
     file_path: Option<PathBuf>,
+    /// The config file, read the first time it is needed.
+    file: OnceLock<Option<ConfigFile>>,
 }
 
 impl Config {
@@ -34,65 +43,126 @@ impl Config {
         if let Ok(env_config_path) = env::var("EQL_CONFIG_PATH") {
             let env_config_path = PathBuf::from(&env_config_path);
             if env_config_path.exists() {
-                return Config {
-                    file_path: Some(env_config_path),
-                };
+                return Config::from_path(env_config_path);
             }
         }
 
         if let Ok(curr_dir) = env::current_dir() {
             let curr_dir_config_path = curr_dir.join(CONFIG_FILE);
             if curr_dir_config_path.exists() {
-                return Config {
-                    file_path: Some(curr_dir_config_path),
-                };
+                return Config::from_path(curr_dir_config_path);
             }
         }
 
         if let Ok(env_home) = env::var("HOME") {
             let home_config_path = PathBuf::from(env_home).join(CONFIG_FILE);
             if home_config_path.exists() {
-                return Config {
-                    file_path: Some(home_config_path),
-                };
+                return Config::from_path(home_config_path);
             }
         }
 
-        Config { file_path: None }
+        Config {
+            file_path: None,
+            file: OnceLock::new(),
+        }
+    }
+
+    /// The config of the file at `file_path`.
+    pub fn from_path(file_path: impl Into<PathBuf>) -> Self {
+        Config {
+            file_path: Some(file_path.into()),
+            file: OnceLock::new(),
+        }
+    }
+
+    /// The config found by [`Config::new`], loaded once and shared by every query.
+    pub fn load() -> &'static Config {
+        static CONFIG: OnceLock<Config> = OnceLock::new();
+        CONFIG.get_or_init(Config::new)
     }
 
     pub fn get_chain_default_rpc(&self, chain: &Chain) -> Result<Option<Url>> {
-        match &self.file_path {
-            Some(file_path) => {
-                let file = fs::read_to_string(file_path)?;
-                let config_file: ConfigFile = serde_json::from_str(&file)?;
-
-                if let Some(chain_config) = config_file.chains.get(&chain.to_string()) {
-                    let url = chain_config.default.parse::<Url>()?;
-                    Ok(Some(url))
-                } else {
-                    Ok(None)
-                }
-            }
+        match self.chain_config(chain)? {
+            Some(chain_config) => Ok(Some(chain_config.default.parse::<Url>()?)),
             None => Ok(None),
         }
     }
 
     pub fn get_chain_rpcs(&self, chain: &Chain) -> Result<Option<Vec<Url>>> {
-        match &self.file_path {
-            Some(file_path) => {
-                let file = fs::read_to_string(file_path)?;
-                let config_file: ConfigFile = serde_json::from_str(&file)?;
-
-                if let Some(chain_config) = config_file.chains.get(&chain.to_string()) {
-                    let urls: Result<Vec<Url>, _> =
-                        chain_config.rpcs.iter().map(|rpc| rpc.parse()).collect();
-                    Ok(Some(urls?))
-                } else {
-                    Ok(None)
-                }
+        match self.chain_config(chain)? {
+            Some(chain_config) => {
+                let urls: Result<Vec<Url>, _> =
+                    chain_config.rpcs.iter().map(|rpc| rpc.parse()).collect();
+                Ok(Some(urls?))
             }
             None => Ok(None),
         }
     }
+
+    /// The networks named in the config file besides the public ones, in name order.
+    pub fn get_networks(&self) -> Result<Vec<String>> {
+        let public = Chain::public_chains()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let mut networks = match self.read()? {
+            Some(config_file) => config_file
+                .chains
+                .keys()
+                .filter(|name| !public.contains(*name))
+                .cloned()
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+        networks.sort();
+        Ok(networks)
+    }
+
+    /// The network of the config file whose default RPC is `rpc`.
+    pub fn get_network_by_rpc(&self, rpc: &Url) -> Result<Option<String>> {
+        let networks = self.get_networks()?;
+        Ok(self.read()?.and_then(|config_file| {
+            networks.into_iter().find(|network| {
+                config_file.chains[network]
+                    .default
+                    .parse::<Url>()
+                    .is_ok_and(|url| url == *rpc)
+            })
+        }))
+    }
+
+    pub fn get_default_chain(&self) -> Result<Option<Vec<ChainOrRpc>>> {
+        match self
+            .read()?
+            .and_then(|config_file| config_file.default_chain.as_ref())
+        {
+            Some(selector) => Ok(Some(ChainOrRpc::from_selector(
+                selector,
+                &self.get_networks()?,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    fn chain_config(&self, chain: &Chain) -> Result<Option<&ChainConfig>> {
+        Ok(self
+            .read()?
+            .and_then(|config_file| config_file.chains.get(&chain.to_string())))
+    }
+
+    /// The config file, which is only read once: a file that can't be read or parsed is
+    /// retried on the next call.
+    fn read(&self) -> Result<Option<&ConfigFile>> {
+        if let Some(config_file) = self.file.get() {
+            return Ok(config_file.as_ref());
+        }
+        let config_file = match &self.file_path {
+            Some(file_path) => {
+                let file = fs::read_to_string(file_path)?;
+                Some(serde_json::from_str(&file)?)
+            }
+            None => None,
+        };
+        Ok(self.file.get_or_init(|| config_file).as_ref())
+    }
 }
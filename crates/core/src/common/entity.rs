@@ -2,10 +2,18 @@ use super::account::AccountError;
 use super::transaction::TransactionError;
 use crate::common::{
     account::Account, checkpoint::Checkpoint, checkpoint::CheckpointError, coin::Coin,
-    coin::CoinError, object::Object, object::ObjectError, transaction::Transaction,
+    coin::CoinError, expression::Distinct, join::Join, join::JoinEntity, join::JoinError,
+    name::Name, name::NameError, object::Object, object::ObjectError, subquery::InFilterError,
+    subquery::InFilterTarget, subquery::Subquery, subquery::SubquerySource,
+    transaction::Transaction,
 };
 use crate::interpreter::frontend::parser::Rule;
 use pest::iterators::Pairs;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum EntityError {
@@ -29,15 +37,204 @@ pub enum EntityError {
 
     #[error(transparent)]
     ObjectError(#[from] ObjectError),
+
+    #[error(transparent)]
+    NameError(#[from] NameError),
+
+    #[error(transparent)]
+    JoinError(#[from] JoinError),
+
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Entity {
     Account(Account),
     Checkpoint(Checkpoint),
     Transaction(Transaction),
     Coin(Coin),
     Object(Object),
+    Name(Name),
+    Join(Join),
+}
+
+impl Entity {
+    /// The number of columns the query selects.
+    pub fn field_count(&self) -> usize {
+        match self {
+            Entity::Account(account) => account.fields().len(),
+            Entity::Checkpoint(checkpoint) => checkpoint.fields().len(),
+            Entity::Transaction(transaction) => transaction.fields().len(),
+            Entity::Coin(coin) => coin.fields().len(),
+            Entity::Object(object) => object.fields().len(),
+            Entity::Name(name) => name.fields().len(),
+            Entity::Join(join) => join.columns().len(),
+        }
+    }
+
+    /// The entity as query text, see [`QueryText`].
+    pub(crate) fn query_text(&self) -> QueryText {
+        match self {
+            Entity::Account(account) => account.query_text(),
+            Entity::Checkpoint(checkpoint) => checkpoint.query_text(),
+            Entity::Transaction(transaction) => transaction.query_text(),
+            Entity::Coin(coin) => coin.query_text(),
+            Entity::Object(object) => object.query_text(),
+            Entity::Name(name) => name.query_text(),
+            Entity::Join(join) => join.query_text(),
+        }
+    }
+
+    /// Removes the `IN (SELECT ...)` subqueries from the entity filters, so they can be
+    /// executed before the entity is resolved. The subqueries of a join source are taken
+    /// when the source itself is resolved.
+    pub fn take_subqueries(&mut self) -> Vec<Subquery> {
+        match self {
+            Entity::Account(account) => account.take_subqueries(),
+            Entity::Checkpoint(checkpoint) => checkpoint.take_subqueries(),
+            Entity::Transaction(transaction) => transaction.take_subqueries(),
+            Entity::Coin(coin) => coin.take_subqueries(),
+            Entity::Object(object) => object.take_subqueries(),
+            Entity::Name(name) => name.take_subqueries(),
+            Entity::Join(_) => vec![],
+        }
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        match self {
+            Entity::Account(account) => account.push_subquery(subquery),
+            Entity::Checkpoint(checkpoint) => checkpoint.push_subquery(subquery),
+            Entity::Transaction(transaction) => transaction.push_subquery(subquery),
+            Entity::Coin(coin) => coin.push_subquery(subquery),
+            Entity::Object(object) => object.push_subquery(subquery),
+            Entity::Name(name) => name.push_subquery(subquery),
+            Entity::Join(join) => join.source_mut().push_subquery(subquery),
+        }
+    }
+
+    /// Replaces the `$variable` subqueries with the queries bound to them by `LET`.
+    pub fn bind_variables(
+        &mut self,
+        variables: &HashMap<String, Entity>,
+    ) -> Result<(), EntityError> {
+        if let Entity::Join(join) = self {
+            return join.source_mut().bind_variables(variables);
+        }
+
+        for mut subquery in self.take_subqueries() {
+            match &mut subquery.source {
+                SubquerySource::Variable(name) => {
+                    let entity = variables
+                        .get(name)
+                        .ok_or_else(|| InFilterError::UnknownVariable(name.clone()))?;
+                    let columns = entity.field_count();
+                    if columns != 1 {
+                        return Err(InFilterError::SubqueryColumns(columns).into());
+                    }
+                    subquery.source = SubquerySource::Query(Box::new(entity.clone()));
+                }
+                SubquerySource::Query(entity) => entity.bind_variables(variables)?,
+            }
+            self.push_subquery(subquery);
+        }
+
+        Ok(())
+    }
+
+    /// Applies the values returned by a subquery to `column`.
+    pub fn apply_in(&mut self, column: &str, values: &[String]) -> Result<(), EntityError> {
+        match self {
+            Entity::Account(account) => account.apply_in(column, values)?,
+            Entity::Checkpoint(checkpoint) => checkpoint.apply_in(column, values)?,
+            Entity::Transaction(transaction) => transaction.apply_in(column, values)?,
+            Entity::Coin(coin) => coin.apply_in(column, values)?,
+            Entity::Object(object) => object.apply_in(column, values)?,
+            Entity::Name(name) => name.apply_in(column, values)?,
+            Entity::Join(_) => {
+                return Err(InFilterError::UnsupportedColumn(column.to_string()).into())
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Entity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.query_text().fmt(f)
+    }
+}
+
+/// An entity written back as query text: `SELECT fields FROM entity ids`, or
+/// `SELECT fields FROM entity WHERE filters` when it has filters. Ids are then written as
+/// an `IN` filter, which the parser turns back into ids.
+pub(crate) struct QueryText {
+    pub distinct: Option<Distinct>,
+    /// The select list, `None` for `*`.
+    pub columns: Option<Vec<String>>,
+    /// What follows `FROM`: the entity, or the entities of a join and their condition.
+    pub source: String,
+    /// The column the ids are matched against in an `IN` filter.
+    pub id_column: &'static str,
+    pub ids: Vec<String>,
+    pub filters: Vec<String>,
+    /// Joins only take a `WHERE` clause, so their ids are always written as a filter.
+    pub ids_in_where: bool,
+}
+
+impl QueryText {
+    /// The query text of a plain entity. Fields that match what `*` selects are written as `*`.
+    pub fn new<F: Display + PartialEq>(
+        entity: JoinEntity,
+        fields: &[F],
+        wildcard: &[F],
+        ids: impl IntoIterator<Item = impl Display>,
+        filters: impl IntoIterator<Item = impl Display>,
+    ) -> Self {
+        QueryText {
+            distinct: None,
+            columns: (fields != wildcard).then(|| fields.iter().map(ToString::to_string).collect()),
+            source: entity.to_string(),
+            id_column: entity.in_columns()[0],
+            ids: ids.into_iter().map(|id| id.to_string()).collect(),
+            filters: filters
+                .into_iter()
+                .map(|filter| filter.to_string())
+                .collect(),
+            ids_in_where: false,
+        }
+    }
+}
+
+impl Display for QueryText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SELECT ")?;
+        if let Some(distinct) = &self.distinct {
+            write!(f, "{distinct} ")?;
+        }
+        match &self.columns {
+            Some(columns) => write!(f, "{}", columns.join(", "))?,
+            None => write!(f, "*")?,
+        }
+        write!(f, " FROM {}", self.source)?;
+
+        if self.filters.is_empty() && !self.ids_in_where {
+            if !self.ids.is_empty() {
+                write!(f, " {}", self.ids.join(", "))?;
+            }
+            return Ok(());
+        }
+
+        let mut filters = vec![];
+        if !self.ids.is_empty() {
+            filters.push(format!("{} IN ({})", self.id_column, self.ids.join(", ")));
+        }
+        filters.extend(self.filters.iter().cloned());
+        if !filters.is_empty() {
+            write!(f, " WHERE {}", filters.join(", "))?;
+        }
+        Ok(())
+    }
 }
 
 impl TryFrom<Pairs<'_, Rule>> for Entity {
@@ -66,6 +263,14 @@ impl TryFrom<Pairs<'_, Rule>> for Entity {
                     let object = Object::try_from(pair.into_inner())?;
                     return Ok(Entity::Object(object));
                 }
+                Rule::name_get => {
+                    let name = Name::try_from(pair.into_inner())?;
+                    return Ok(Entity::Name(name));
+                }
+                Rule::join_get => {
+                    let join = Join::try_from(pair.into_inner())?;
+                    return Ok(Entity::Join(join));
+                }
                 _ => return Err(EntityError::UnexpectedToken(pair.as_str().to_string())),
             }
         }
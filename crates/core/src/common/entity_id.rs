@@ -1,4 +1,5 @@
 use super::checkpoint::CheckpointNumberOrTag;
+use std::{collections::HashSet, hash::Hash};
 
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
 pub enum EntityIdError {
@@ -20,3 +21,23 @@ pub fn parse_checkpoint_number_or_tag(id: &str) -> Result<CheckpointNumberOrTag,
             .map_err(|_| EntityIdError::InvalidCheckpointNumberOrTag(id.to_string())),
     }
 }
+
+/// Removes repeated ids, keeping the first occurrence, so every id is only fetched once.
+pub fn unique_ids<T: Eq + Hash + Clone>(ids: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut seen = HashSet::new();
+    ids.into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unique_ids() {
+        assert_eq!(unique_ids([3, 1, 3, 2, 1]), vec![3, 1, 2]);
+        assert_eq!(unique_ids(["b", "a", "b"]), vec!["b", "a"]);
+        assert!(unique_ids(Vec::<u64>::new()).is_empty());
+    }
+}
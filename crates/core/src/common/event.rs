@@ -111,22 +111,6 @@ pub enum EventFilter {
     Transaction(TransactionDigest),
 }
 
-// impl TryFrom<Pair<'_, Rule>> for EventFilter {
-//     type Error = EventFilterError;
-
-//     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
-//         match pair.as_rule() {
-//             Rule::address_filter => {
-//                 let address = NameOrAddress::from_str(pair.as_str())?;
-//                 Ok(EventFilter::Address(address))
-//             }
-//             _ => {
-//                 return Err(EventFilterError::UnexpectedToken(pair.as_str().to_string()));
-//             }
-//         }
-//     }
-// }
-
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, EnumVariants)]
 pub enum EventField {
     TxDigest,
@@ -0,0 +1,1366 @@
+use super::{
+    join::JoinEntity,
+    literal::{strip_comments, unquote},
+    query_result::RowQueryRes,
+};
+use crate::interpreter::frontend::parser::Rule;
+use chrono::{DateTime, SecondsFormat, Utc};
+use pest::{
+    iterators::Pair,
+    pratt_parser::{Assoc, Op, PrattParser},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+/// A result row, keyed by the serialized field names of the entity.
+pub type Row = Map<String, Value>;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ExpressionError {
+    #[error("Unexpected token {0} in expression")]
+    UnexpectedToken(String),
+
+    #[error("Unknown function {0}")]
+    UnknownFunction(String),
+
+    #[error("Function {0} expects {1} arguments, got {2}")]
+    InvalidArgumentCount(Function, &'static str, usize),
+
+    #[error("Invalid number {0}")]
+    InvalidNumber(String),
+
+    #[error("Can't apply {0} to {1}")]
+    InvalidOperand(String, String),
+
+    #[error("{0} of {1} overflows an integer")]
+    IntegerOverflow(String, String),
+
+    #[error("Expressions and aliases can't be used in {0}")]
+    Unsupported(&'static str),
+
+    #[error("DISTINCT ON column {0} isn't selected")]
+    UnknownDistinctColumn(String),
+}
+
+/// A scalar expression, evaluated over the columns of a result row.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    Unary(UnaryOperator, Box<Expr>),
+    Binary(Box<Expr>, BinaryOperator, Box<Expr>),
+    Function(Function, Vec<Expr>),
+    /// `CASE WHEN condition THEN value ... ELSE value END`
+    Case(Vec<(Expr, Expr)>, Option<Box<Expr>>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum UnaryOperator {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Concat,
+    Eq,
+    Neq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    And,
+    Or,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Function {
+    Lower,
+    Upper,
+    Length,
+    Trim,
+    Substr,
+    StartsWith,
+    EndsWith,
+    Contains,
+    Replace,
+    Concat,
+    Coalesce,
+    Abs,
+    Round,
+    ToDatetime,
+    ToDate,
+    Now,
+}
+
+impl BinaryOperator {
+    /// How tightly the operator binds, as in [`pratt_parser`].
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Eq
+            | BinaryOperator::Neq
+            | BinaryOperator::Lt
+            | BinaryOperator::Lte
+            | BinaryOperator::Gt
+            | BinaryOperator::Gte => 4,
+            BinaryOperator::Add | BinaryOperator::Sub | BinaryOperator::Concat => 5,
+            BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Rem => 6,
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        self.precedence() == 4
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Rem => "%",
+            BinaryOperator::Concat => "||",
+            BinaryOperator::Eq => "=",
+            BinaryOperator::Neq => "!=",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::Lte => "<=",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::Gte => ">=",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(_, operator, _) => operator.precedence(),
+            Expr::Unary(UnaryOperator::Not, _) => 3,
+            Expr::Unary(UnaryOperator::Neg, _) => 7,
+            _ => 8,
+        }
+    }
+
+    /// Writes the expression as a `WHERE` filter. Filters that start by comparing a column,
+    /// like `gas_used > 1000 OR status = false`, are parenthesized so that they aren't read
+    /// back as an entity filter.
+    pub fn to_filter_string(&self) -> String {
+        fn compares_column(expr: &Expr) -> bool {
+            match expr {
+                Expr::Binary(lhs, operator, _) => {
+                    matches!(**lhs, Expr::Column(_)) && operator.is_comparison()
+                        || compares_column(lhs)
+                }
+                _ => false,
+            }
+        }
+
+        match compares_column(self) {
+            true => format!("({self})"),
+            false => self.to_string(),
+        }
+    }
+
+    /// The columns the expression refers to, in the order they are written, like
+    /// [`referenced_columns`] does for a parsed expression.
+    pub fn columns(&self) -> Vec<String> {
+        fn collect(expr: &Expr, columns: &mut Vec<String>) {
+            match expr {
+                Expr::Column(column) => {
+                    if !columns.contains(column) {
+                        columns.push(column.clone());
+                    }
+                }
+                Expr::Literal(_) => {}
+                Expr::Unary(_, operand) => collect(operand, columns),
+                Expr::Binary(lhs, _, rhs) => {
+                    collect(lhs, columns);
+                    collect(rhs, columns);
+                }
+                Expr::Function(_, args) => args.iter().for_each(|arg| collect(arg, columns)),
+                Expr::Case(branches, otherwise) => {
+                    for (condition, value) in branches {
+                        collect(condition, columns);
+                        collect(value, columns);
+                    }
+                    if let Some(otherwise) = otherwise {
+                        collect(otherwise, columns);
+                    }
+                }
+            }
+        }
+
+        let mut columns = vec![];
+        collect(self, &mut columns);
+        columns
+    }
+}
+
+/// Writes `expr`, in parentheses when `parenthesize` is set.
+fn fmt_operand(f: &mut Formatter<'_>, expr: &Expr, parenthesize: bool) -> fmt::Result {
+    match parenthesize {
+        true => write!(f, "({expr})"),
+        false => write!(f, "{expr}"),
+    }
+}
+
+fn fmt_literal(f: &mut Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::String(s) if is_hex_literal(s) => write!(f, "{s}"),
+        Value::String(s) => write!(f, "'{}'", s.replace('\\', "\\\\").replace('\'', "''")),
+        value => write!(f, "{value}"),
+    }
+}
+
+fn is_hex_literal(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Writes the expression back as query text, with the parentheses the precedence of its
+/// operators needs.
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(column) => write!(f, "{column}"),
+            Expr::Literal(value) => fmt_literal(f, value),
+            // `- -x` is written `-(-x)`, as `--` starts a comment.
+            Expr::Unary(UnaryOperator::Neg, operand) => {
+                write!(f, "-")?;
+                fmt_operand(f, operand, operand.precedence() <= 7)
+            }
+            Expr::Unary(UnaryOperator::Not, operand) => {
+                write!(f, "NOT ")?;
+                fmt_operand(f, operand, operand.precedence() < 4)
+            }
+            Expr::Binary(lhs, operator, rhs) => {
+                fmt_operand(f, lhs, lhs.precedence() < operator.precedence())?;
+                write!(f, " {operator} ")?;
+                fmt_operand(f, rhs, rhs.precedence() <= operator.precedence())
+            }
+            Expr::Function(function, args) => {
+                write!(f, "{function}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
+            Expr::Case(branches, otherwise) => {
+                write!(f, "CASE")?;
+                for (condition, value) in branches {
+                    write!(f, " WHEN {condition} THEN {value}")?;
+                }
+                if let Some(otherwise) = otherwise {
+                    write!(f, " ELSE {otherwise}")?;
+                }
+                write!(f, " END")
+            }
+        }
+    }
+}
+
+impl Function {
+    pub const ALL: [Function; 16] = [
+        Function::Lower,
+        Function::Upper,
+        Function::Length,
+        Function::Trim,
+        Function::Substr,
+        Function::StartsWith,
+        Function::EndsWith,
+        Function::Contains,
+        Function::Replace,
+        Function::Concat,
+        Function::Coalesce,
+        Function::Abs,
+        Function::Round,
+        Function::ToDatetime,
+        Function::ToDate,
+        Function::Now,
+    ];
+
+    /// The minimum and maximum number of arguments of the function.
+    fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Function::Now => (0, Some(0)),
+            Function::Lower
+            | Function::Upper
+            | Function::Length
+            | Function::Trim
+            | Function::Abs
+            | Function::ToDatetime
+            | Function::ToDate => (1, Some(1)),
+            Function::StartsWith | Function::EndsWith | Function::Contains => (2, Some(2)),
+            Function::Round => (1, Some(2)),
+            Function::Substr => (2, Some(3)),
+            Function::Replace => (3, Some(3)),
+            Function::Concat | Function::Coalesce => (1, None),
+        }
+    }
+
+    fn check_arity(&self, count: usize) -> Result<(), ExpressionError> {
+        let (min, max) = self.arity();
+        if count >= min && max.map_or(true, |max| count <= max) {
+            return Ok(());
+        }
+        let expected = match (min, max) {
+            (0, Some(0)) => "no",
+            (1, Some(1)) => "1",
+            (2, Some(2)) => "2",
+            (3, Some(3)) => "3",
+            (1, Some(2)) => "1 or 2",
+            (2, Some(3)) => "2 or 3",
+            _ => "at least 1",
+        };
+        Err(ExpressionError::InvalidArgumentCount(
+            *self, expected, count,
+        ))
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Function::Lower => write!(f, "lower"),
+            Function::Upper => write!(f, "upper"),
+            Function::Length => write!(f, "length"),
+            Function::Trim => write!(f, "trim"),
+            Function::Substr => write!(f, "substr"),
+            Function::StartsWith => write!(f, "starts_with"),
+            Function::EndsWith => write!(f, "ends_with"),
+            Function::Contains => write!(f, "contains"),
+            Function::Replace => write!(f, "replace"),
+            Function::Concat => write!(f, "concat"),
+            Function::Coalesce => write!(f, "coalesce"),
+            Function::Abs => write!(f, "abs"),
+            Function::Round => write!(f, "round"),
+            Function::ToDatetime => write!(f, "to_datetime"),
+            Function::ToDate => write!(f, "to_date"),
+            Function::Now => write!(f, "now"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Function {
+    type Error = ExpressionError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "lower" => Ok(Function::Lower),
+            "upper" => Ok(Function::Upper),
+            "length" => Ok(Function::Length),
+            "trim" => Ok(Function::Trim),
+            "substr" | "substring" => Ok(Function::Substr),
+            "starts_with" => Ok(Function::StartsWith),
+            "ends_with" => Ok(Function::EndsWith),
+            "contains" => Ok(Function::Contains),
+            "replace" => Ok(Function::Replace),
+            "concat" => Ok(Function::Concat),
+            "coalesce" => Ok(Function::Coalesce),
+            "abs" => Ok(Function::Abs),
+            "round" => Ok(Function::Round),
+            "to_datetime" => Ok(Function::ToDatetime),
+            "to_date" => Ok(Function::ToDate),
+            "now" => Ok(Function::Now),
+            function => Err(ExpressionError::UnknownFunction(function.to_string())),
+        }
+    }
+}
+
+fn pratt_parser() -> PrattParser<Rule> {
+    PrattParser::new()
+        .op(Op::infix(Rule::or, Assoc::Left))
+        .op(Op::infix(Rule::and, Assoc::Left))
+        .op(Op::prefix(Rule::not))
+        .op(Op::infix(Rule::eq, Assoc::Left)
+            | Op::infix(Rule::neq, Assoc::Left)
+            | Op::infix(Rule::lt, Assoc::Left)
+            | Op::infix(Rule::lte, Assoc::Left)
+            | Op::infix(Rule::gt, Assoc::Left)
+            | Op::infix(Rule::gte, Assoc::Left))
+        .op(Op::infix(Rule::add, Assoc::Left)
+            | Op::infix(Rule::sub, Assoc::Left)
+            | Op::infix(Rule::concat, Assoc::Left))
+        .op(Op::infix(Rule::mul, Assoc::Left)
+            | Op::infix(Rule::div, Assoc::Left)
+            | Op::infix(Rule::rem, Assoc::Left))
+        .op(Op::prefix(Rule::neg))
+}
+
+impl TryFrom<Pair<'_, Rule>> for Expr {
+    type Error = ExpressionError;
+
+    fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        let pratt = pratt_parser();
+        parse_expr(pair, &pratt)
+    }
+}
+
+fn parse_expr(pair: Pair<'_, Rule>, pratt: &PrattParser<Rule>) -> Result<Expr, ExpressionError> {
+    pratt
+        .map_primary(|primary| parse_operand(primary, pratt))
+        .map_prefix(|op, operand| {
+            let operator = match op.as_rule() {
+                Rule::neg => UnaryOperator::Neg,
+                _ => UnaryOperator::Not,
+            };
+            Ok(Expr::Unary(operator, Box::new(operand?)))
+        })
+        .map_infix(|lhs, op, rhs| {
+            let operator = match op.as_rule() {
+                Rule::add => BinaryOperator::Add,
+                Rule::sub => BinaryOperator::Sub,
+                Rule::mul => BinaryOperator::Mul,
+                Rule::div => BinaryOperator::Div,
+                Rule::rem => BinaryOperator::Rem,
+                Rule::concat => BinaryOperator::Concat,
+                Rule::eq => BinaryOperator::Eq,
+                Rule::neq => BinaryOperator::Neq,
+                Rule::lt => BinaryOperator::Lt,
+                Rule::lte => BinaryOperator::Lte,
+                Rule::gt => BinaryOperator::Gt,
+                Rule::gte => BinaryOperator::Gte,
+                Rule::and => BinaryOperator::And,
+                Rule::or => BinaryOperator::Or,
+                _ => return Err(ExpressionError::UnexpectedToken(op.as_str().to_string())),
+            };
+            Ok(Expr::Binary(Box::new(lhs?), operator, Box::new(rhs?)))
+        })
+        .parse(pair.into_inner())
+}
+
+fn parse_operand(pair: Pair<'_, Rule>, pratt: &PrattParser<Rule>) -> Result<Expr, ExpressionError> {
+    match pair.as_rule() {
+        Rule::expr => parse_expr(pair, pratt),
+        Rule::column_ref => Ok(Expr::Column(pair.as_str().to_string())),
+        Rule::hex_literal => Ok(Expr::Literal(Value::String(pair.as_str().to_string()))),
+        Rule::number_literal => parse_number(pair.as_str())
+            .map(Expr::Literal)
+            .ok_or_else(|| ExpressionError::InvalidNumber(pair.as_str().to_string())),
+        Rule::string_literal => Ok(Expr::Literal(Value::String(unquote(pair.as_str())))),
+        Rule::bool_literal => Ok(Expr::Literal(Value::Bool(
+            pair.as_str().eq_ignore_ascii_case("true"),
+        ))),
+        Rule::null_literal => Ok(Expr::Literal(Value::Null)),
+        Rule::function => {
+            let mut inner = pair.into_inner();
+            let name = inner
+                .next()
+                .ok_or_else(|| ExpressionError::UnexpectedToken("()".to_string()))?;
+            let function = Function::try_from(name.as_str())?;
+            let args = inner
+                .map(|arg| parse_expr(arg, pratt))
+                .collect::<Result<Vec<_>, _>>()?;
+            function.check_arity(args.len())?;
+            Ok(Expr::Function(function, args))
+        }
+        Rule::case_expr => {
+            let mut branches = vec![];
+            let mut otherwise = None;
+            for branch in pair.into_inner() {
+                let rule = branch.as_rule();
+                let mut exprs = branch.into_inner().map(|expr| parse_expr(expr, pratt));
+                match (rule, exprs.next(), exprs.next()) {
+                    (Rule::case_when, Some(condition), Some(value)) => {
+                        branches.push((condition?, value?))
+                    }
+                    (Rule::case_else, Some(value), None) => otherwise = Some(Box::new(value?)),
+                    _ => return Err(ExpressionError::UnexpectedToken("CASE".to_string())),
+                }
+            }
+            Ok(Expr::Case(branches, otherwise))
+        }
+        _ => Err(ExpressionError::UnexpectedToken(pair.as_str().to_string())),
+    }
+}
+
+/// The columns an expression, a select list or a filter refers to, in order of appearance.
+pub fn referenced_columns(pair: Pair<'_, Rule>) -> Vec<String> {
+    let mut columns: Vec<String> = vec![];
+    for column in pair.into_inner().flatten() {
+        if column.as_rule() == Rule::column_ref && !columns.iter().any(|c| c == column.as_str()) {
+            columns.push(column.as_str().to_string());
+        }
+    }
+    columns
+}
+
+/// Adds the fields referenced by `pair` that aren't selected yet, so they are fetched.
+pub fn add_fields<F: PartialEq, E>(
+    fields: &mut Vec<F>,
+    pair: Pair<'_, Rule>,
+    parse: impl Fn(&str) -> Result<F, E>,
+) -> Result<(), E> {
+    for column in referenced_columns(pair) {
+        let field = parse(&column)?;
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+    Ok(())
+}
+
+/// A column of the select list. Columns are named by their alias, or by the expression.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SelectItem {
+    pub expr: Expr,
+    pub name: String,
+}
+
+impl SelectItem {
+    /// Selects a field of the entity under its own name.
+    pub fn column(name: impl Into<String>) -> Self {
+        let name = name.into();
+        SelectItem {
+            expr: Expr::Column(name.clone()),
+            name,
+        }
+    }
+}
+
+/// Writes `expr AS alias`, or the expression alone when it is named after itself. Columns
+/// named by an expression that is written differently keep the text they were named by.
+impl Display for SelectItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let expr = self.expr.to_string();
+        let is_alias = self
+            .name
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if expr == self.name || !is_alias {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{expr} AS {}", self.name)
+        }
+    }
+}
+
+impl TryFrom<Pair<'_, Rule>> for SelectItem {
+    type Error = ExpressionError;
+
+    fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        let mut inner = pair.into_inner();
+        let expr_pair = inner
+            .next()
+            .ok_or_else(|| ExpressionError::UnexpectedToken(String::new()))?;
+        let source = strip_comments(expr_pair.as_str()).trim().to_string();
+        let expr = Expr::try_from(expr_pair)?;
+        let name = inner
+            .next()
+            .map_or(source, |alias| alias.as_str().to_string());
+
+        Ok(SelectItem { expr, name })
+    }
+}
+
+/// The select list and expression filters of a query, applied to the rows the entity returns.
+/// Rows have the selected columns in the order they were selected, named by their alias, and
+/// keep the columns without a value as `null`. Every query of an entity has a projection, so
+/// `SELECT *` rows have all the fields of the entity, in the order `DESCRIBE` lists them.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Projection {
+    entity: JoinEntity,
+    /// `None` selects every field of the entity.
+    columns: Option<Vec<SelectItem>>,
+    filters: Vec<Expr>,
+}
+
+impl Projection {
+    pub fn new(entity: JoinEntity, columns: Option<Vec<SelectItem>>, filters: Vec<Expr>) -> Self {
+        Self {
+            entity,
+            columns,
+            filters,
+        }
+    }
+
+    /// The select list, `None` for `SELECT *`.
+    pub fn columns(&self) -> Option<&Vec<SelectItem>> {
+        self.columns.as_ref()
+    }
+
+    /// The expression filters, checked on the rows after they are fetched.
+    pub fn filters(&self) -> &Vec<Expr> {
+        &self.filters
+    }
+
+    /// Builds the projection of an `entity` pair. Joins build their own rows and don't have one.
+    pub fn from_entity(pair: &Pair<'_, Rule>) -> Result<Option<Self>, ExpressionError> {
+        let Some(get) = pair.clone().into_inner().next() else {
+            return Ok(None);
+        };
+        let Some(entity) = JoinEntity::from_rule(get.as_rule()) else {
+            return Ok(None);
+        };
+
+        let mut columns = None;
+        let mut filters = vec![];
+        for pair in get.into_inner() {
+            match pair.as_rule() {
+                Rule::account_fields
+                | Rule::checkpoint_fields
+                | Rule::tx_fields
+                | Rule::coin_fields
+                | Rule::object_fields
+                | Rule::name_fields => {
+                    columns = pair
+                        .into_inner()
+                        .filter(|item| item.as_rule() == Rule::select_item)
+                        .map(SelectItem::try_from)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(|items| (!items.is_empty()).then_some(items))?;
+                }
+                Rule::account_filter
+                | Rule::checkpoint_filter
+                | Rule::tx_filter
+                | Rule::coin_filter
+                | Rule::object_filter
+                | Rule::name_filter => {
+                    if let Some(filter) = pair.into_inner().next() {
+                        if filter.as_rule() == Rule::expr_filter {
+                            let expr = filter
+                                .into_inner()
+                                .next()
+                                .ok_or_else(|| ExpressionError::UnexpectedToken(String::new()))?;
+                            filters.push(Expr::try_from(expr)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Some(Projection {
+            entity,
+            columns,
+            filters,
+        }))
+    }
+
+    /// The names of the output columns, in order.
+    pub fn column_names(&self) -> Vec<String> {
+        match &self.columns {
+            Some(items) => items.iter().map(|item| item.name.clone()).collect(),
+            None => self.entity.all_fields(),
+        }
+    }
+
+    /// The number of expression filters, checked on the rows after they are fetched.
+    pub fn filter_count(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Whether the query only selects plain fields, without aliases or expression filters.
+    pub fn is_plain(&self) -> bool {
+        self.filters.is_empty()
+            && self.columns.iter().flatten().all(|item| match &item.expr {
+                Expr::Column(column) => *column == item.name,
+                _ => false,
+            })
+    }
+
+    /// Fails if the entity of `pair` computes columns, aliases them or filters them with
+    /// expressions, for queries that can only return plain fields, such as subqueries.
+    pub fn reject(pair: &Pair<'_, Rule>, context: &'static str) -> Result<(), ExpressionError> {
+        match Projection::from_entity(pair)? {
+            Some(projection) if !projection.is_plain() => {
+                Err(ExpressionError::Unsupported(context))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Filters the rows and evaluates the selected columns over them.
+    pub fn apply(&self, rows: Vec<Row>) -> Result<Vec<RowQueryRes>, ExpressionError> {
+        let mut projected = vec![];
+
+        for row in rows {
+            let context = Context {
+                row: &row,
+                entity: self.entity,
+            };
+            let mut keep = true;
+            for filter in &self.filters {
+                if as_bool(&context.eval(filter)?) != Some(true) {
+                    keep = false;
+                    break;
+                }
+            }
+            if !keep {
+                continue;
+            }
+
+            let columns = match &self.columns {
+                Some(items) => items
+                    .iter()
+                    .map(|item| Ok((item.name.clone(), context.eval(&item.expr)?)))
+                    .collect::<Result<Vec<_>, ExpressionError>>()?,
+                None => self
+                    .entity
+                    .all_fields()
+                    .into_iter()
+                    .map(|field| {
+                        let value = context.eval(&Expr::Column(field.clone()))?;
+                        Ok((field, value))
+                    })
+                    .collect::<Result<Vec<_>, ExpressionError>>()?,
+            };
+            projected.push(RowQueryRes { columns });
+        }
+
+        Ok(projected)
+    }
+}
+
+/// `SELECT DISTINCT` drops rows that repeat every column, `SELECT DISTINCT ON (columns)` keeps
+/// the first row of every combination of the listed columns.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum Distinct {
+    All,
+    On(Vec<String>),
+}
+
+impl Display for Distinct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Distinct::All => write!(f, "DISTINCT"),
+            Distinct::On(columns) => write!(f, "DISTINCT ON ({})", columns.join(", ")),
+        }
+    }
+}
+
+impl Distinct {
+    /// Reads the `DISTINCT` clause of an `entity` or `join` pair, if it has one.
+    pub fn from_entity(pair: &Pair<'_, Rule>) -> Option<Self> {
+        let get = match pair.as_rule() {
+            Rule::entity => pair.clone().into_inner().next()?,
+            _ => pair.clone(),
+        };
+        let distinct = get
+            .into_inner()
+            .find(|pair| pair.as_rule() == Rule::distinct)?;
+        let columns = distinct
+            .into_inner()
+            .filter(|pair| pair.as_rule() == Rule::distinct_column)
+            .map(|pair| pair.as_str().to_string())
+            .collect::<Vec<_>>();
+
+        Some(if columns.is_empty() {
+            Distinct::All
+        } else {
+            Distinct::On(columns)
+        })
+    }
+
+    /// Fails if a `DISTINCT ON` column isn't one of the output `columns`.
+    pub fn validate(&self, columns: &[String]) -> Result<(), ExpressionError> {
+        if let Distinct::On(on) = self {
+            if let Some(column) = on.iter().find(|column| !columns.contains(column)) {
+                return Err(ExpressionError::UnknownDistinctColumn(column.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, rows: Vec<RowQueryRes>) -> Vec<RowQueryRes> {
+        let mut seen = HashSet::new();
+        rows.into_iter()
+            .filter(|row| {
+                let key = match self {
+                    Distinct::All => row.columns.iter().map(|(_, value)| value).collect(),
+                    Distinct::On(on) => on
+                        .iter()
+                        .map(|name| {
+                            row.columns
+                                .iter()
+                                .find(|(column, _)| column == name)
+                                .map_or(&Value::Null, |(_, value)| value)
+                        })
+                        .collect::<Vec<_>>(),
+                };
+                seen.insert(Value::from(key.into_iter().cloned().collect::<Vec<_>>()).to_string())
+            })
+            .collect()
+    }
+}
+
+struct Context<'a> {
+    row: &'a Row,
+    entity: JoinEntity,
+}
+
+impl Context<'_> {
+    fn eval(&self, expr: &Expr) -> Result<Value, ExpressionError> {
+        match expr {
+            Expr::Column(column) => Ok(self
+                .row
+                .get(self.entity.serialized_field(column))
+                .cloned()
+                .unwrap_or(Value::Null)),
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Unary(operator, operand) => eval_unary(*operator, self.eval(operand)?),
+            Expr::Binary(lhs, BinaryOperator::And, rhs) => {
+                let lhs = as_bool(&self.eval(lhs)?);
+                if lhs == Some(false) {
+                    return Ok(Value::Bool(false));
+                }
+                Ok(match (lhs, as_bool(&self.eval(rhs)?)) {
+                    (_, Some(false)) => Value::Bool(false),
+                    (Some(true), Some(true)) => Value::Bool(true),
+                    _ => Value::Null,
+                })
+            }
+            Expr::Binary(lhs, BinaryOperator::Or, rhs) => {
+                let lhs = as_bool(&self.eval(lhs)?);
+                if lhs == Some(true) {
+                    return Ok(Value::Bool(true));
+                }
+                Ok(match (lhs, as_bool(&self.eval(rhs)?)) {
+                    (_, Some(true)) => Value::Bool(true),
+                    (Some(false), Some(false)) => Value::Bool(false),
+                    _ => Value::Null,
+                })
+            }
+            Expr::Binary(lhs, operator, rhs) => {
+                eval_binary(*operator, self.eval(lhs)?, self.eval(rhs)?)
+            }
+            Expr::Function(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                eval_function(*function, args)
+            }
+            Expr::Case(branches, otherwise) => {
+                for (condition, value) in branches {
+                    if as_bool(&self.eval(condition)?) == Some(true) {
+                        return self.eval(value);
+                    }
+                }
+                match otherwise {
+                    Some(otherwise) => self.eval(otherwise),
+                    None => Ok(Value::Null),
+                }
+            }
+        }
+    }
+}
+
+/// A numeric value. Result columns hold numbers either as JSON numbers or as strings.
+#[derive(Debug, Clone, Copy)]
+enum Num {
+    Int(i128),
+    Float(f64),
+}
+
+impl Num {
+    fn as_f64(self) -> f64 {
+        match self {
+            Num::Int(value) => value as f64,
+            Num::Float(value) => value,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Num::Int(value) => match (i64::try_from(value), u64::try_from(value)) {
+                (Ok(value), _) => Value::Number(value.into()),
+                (_, Ok(value)) => Value::Number(value.into()),
+                _ => Value::String(value.to_string()),
+            },
+            Num::Float(value) => Number::from_f64(value).map_or(Value::Null, Value::Number),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Option<Value> {
+    let value = value.trim();
+    if let Ok(int) = value.parse::<i128>() {
+        return Some(Num::Int(int).into_value());
+    }
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|float| float.is_finite())
+        .map(|float| Num::Float(float).into_value())
+}
+
+fn as_num(value: &Value) -> Option<Num> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(|n| Num::Int(n as i128))
+            .or_else(|| number.as_u64().map(|n| Num::Int(n as i128)))
+            .or_else(|| number.as_f64().map(Num::Float)),
+        Value::String(value) => {
+            let value = value.trim();
+            value.parse::<i128>().map(Num::Int).ok().or_else(|| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|float| float.is_finite())
+                    .map(Num::Float)
+            })
+        }
+        _ => None,
+    }
+}
+
+fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(value) => Some(*value),
+        Value::String(value) if value.eq_ignore_ascii_case("true") => Some(true),
+        Value::String(value) if value.eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
+fn as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn invalid_operand(operator: impl std::fmt::Debug, value: &Value) -> ExpressionError {
+    ExpressionError::InvalidOperand(format!("{:?}", operator), value.to_string())
+}
+
+fn overflow(operator: impl std::fmt::Debug, value: &Value) -> ExpressionError {
+    ExpressionError::IntegerOverflow(format!("{:?}", operator), value.to_string())
+}
+
+fn eval_unary(operator: UnaryOperator, value: Value) -> Result<Value, ExpressionError> {
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+    match operator {
+        UnaryOperator::Neg => match as_num(&value) {
+            Some(Num::Int(int)) => int
+                .checked_neg()
+                .map(|int| Num::Int(int).into_value())
+                .ok_or_else(|| overflow(operator, &value)),
+            Some(Num::Float(value)) => Ok(Num::Float(-value).into_value()),
+            None => Err(invalid_operand(operator, &value)),
+        },
+        UnaryOperator::Not => as_bool(&value)
+            .map(|value| Value::Bool(!value))
+            .ok_or_else(|| invalid_operand(operator, &value)),
+    }
+}
+
+fn eval_binary(operator: BinaryOperator, lhs: Value, rhs: Value) -> Result<Value, ExpressionError> {
+    if lhs.is_null() || rhs.is_null() {
+        return Ok(Value::Null);
+    }
+
+    match operator {
+        BinaryOperator::Concat => Ok(Value::String(format!(
+            "{}{}",
+            as_text(&lhs).unwrap_or_default(),
+            as_text(&rhs).unwrap_or_default()
+        ))),
+        BinaryOperator::Eq
+        | BinaryOperator::Neq
+        | BinaryOperator::Lt
+        | BinaryOperator::Lte
+        | BinaryOperator::Gt
+        | BinaryOperator::Gte => {
+            let ordering = compare(&lhs, &rhs);
+            let result = match operator {
+                BinaryOperator::Eq => ordering == Some(Ordering::Equal),
+                BinaryOperator::Neq => ordering != Some(Ordering::Equal),
+                BinaryOperator::Lt => ordering == Some(Ordering::Less),
+                BinaryOperator::Lte => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                BinaryOperator::Gt => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            };
+            Ok(Value::Bool(result))
+        }
+        _ => {
+            let a = as_num(&lhs).ok_or_else(|| invalid_operand(operator, &lhs))?;
+            let b = as_num(&rhs).ok_or_else(|| invalid_operand(operator, &rhs))?;
+            Ok(arithmetic(operator, a, b).map_or(Value::Null, Num::into_value))
+        }
+    }
+}
+
+/// Integer arithmetic falls back to floats on overflow. Division by zero is `null`.
+fn arithmetic(operator: BinaryOperator, a: Num, b: Num) -> Option<Num> {
+    if let (Num::Int(a), Num::Int(b)) = (a, b) {
+        let result = match operator {
+            BinaryOperator::Add => a.checked_add(b),
+            BinaryOperator::Sub => a.checked_sub(b),
+            BinaryOperator::Mul => a.checked_mul(b),
+            BinaryOperator::Div if b == 0 => return None,
+            BinaryOperator::Div if a % b == 0 => a.checked_div(b),
+            BinaryOperator::Div => None,
+            BinaryOperator::Rem => a.checked_rem(b),
+            _ => return None,
+        };
+        if let Some(result) = result {
+            return Some(Num::Int(result));
+        }
+    }
+
+    let (a, b) = (a.as_f64(), b.as_f64());
+    let result = match operator {
+        BinaryOperator::Add => a + b,
+        BinaryOperator::Sub => a - b,
+        BinaryOperator::Mul => a * b,
+        BinaryOperator::Div if b == 0.0 => return None,
+        BinaryOperator::Div => a / b,
+        BinaryOperator::Rem if b == 0.0 => return None,
+        BinaryOperator::Rem => a % b,
+        _ => return None,
+    };
+    Some(Num::Float(result))
+}
+
+/// Numbers compare by value, booleans as booleans and anything else as text.
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (as_num(lhs), as_num(rhs)) {
+        return match (a, b) {
+            (Num::Int(a), Num::Int(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        };
+    }
+    if let (Value::Bool(_), _) | (_, Value::Bool(_)) = (lhs, rhs) {
+        return match (as_bool(lhs), as_bool(rhs)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => None,
+        };
+    }
+    Some(as_text(lhs)?.cmp(&as_text(rhs)?))
+}
+
+fn eval_function(function: Function, args: Vec<Value>) -> Result<Value, ExpressionError> {
+    match function {
+        Function::Coalesce => {
+            return Ok(args
+                .into_iter()
+                .find(|arg| !arg.is_null())
+                .unwrap_or(Value::Null))
+        }
+        Function::Concat => {
+            return Ok(Value::String(
+                args.iter().filter_map(as_text).collect::<String>(),
+            ))
+        }
+        Function::Now => return Ok(Value::Number(Utc::now().timestamp_millis().into())),
+        _ => {}
+    }
+
+    // The remaining functions return `null` when any of their arguments is `null`.
+    if args.iter().any(Value::is_null) {
+        return Ok(Value::Null);
+    }
+    let text = |i: usize| as_text(&args[i]).unwrap_or_default();
+    let number = |i: usize| as_num(&args[i]).ok_or_else(|| invalid_operand(function, &args[i]));
+
+    let value = match function {
+        Function::Lower => Value::String(text(0).to_lowercase()),
+        Function::Upper => Value::String(text(0).to_uppercase()),
+        Function::Length => Value::Number(text(0).chars().count().into()),
+        Function::Trim => Value::String(text(0).trim().to_string()),
+        Function::StartsWith => Value::Bool(text(0).starts_with(&text(1))),
+        Function::EndsWith => Value::Bool(text(0).ends_with(&text(1))),
+        Function::Contains => Value::Bool(text(0).contains(&text(1))),
+        Function::Replace => Value::String(text(0).replace(&text(1), &text(2))),
+        Function::Substr => {
+            // Positions start at 1, as in SQL.
+            let start = match number(1)? {
+                Num::Int(start) => start,
+                Num::Float(start) => start as i128,
+            };
+            let length = match args.get(2).map(|_| number(2)).transpose()? {
+                Some(Num::Int(length)) => Some(length),
+                Some(Num::Float(length)) => Some(length as i128),
+                None => None,
+            };
+            let skip = usize::try_from(start.max(1) - 1).unwrap_or(usize::MAX);
+            let take = length
+                .map(|length| usize::try_from(length.max(0)).unwrap_or(usize::MAX))
+                .unwrap_or(usize::MAX);
+            Value::String(text(0).chars().skip(skip).take(take).collect())
+        }
+        Function::Abs => match number(0)? {
+            Num::Int(value) => value
+                .checked_abs()
+                .map(|value| Num::Int(value).into_value())
+                .ok_or_else(|| overflow(function, &args[0]))?,
+            Num::Float(value) => Num::Float(value.abs()).into_value(),
+        },
+        Function::Round => {
+            let digits = match args.get(1).map(|_| number(1)).transpose()? {
+                Some(digits) => digits.as_f64() as i32,
+                None => 0,
+            };
+            match number(0)? {
+                Num::Int(value) if digits >= 0 => Num::Int(value).into_value(),
+                value => {
+                    let scale = 10f64.powi(digits);
+                    let rounded = (value.as_f64() * scale).round() / scale;
+                    if digits <= 0 {
+                        Num::Int(rounded as i128).into_value()
+                    } else {
+                        Num::Float(rounded).into_value()
+                    }
+                }
+            }
+        }
+        Function::ToDatetime | Function::ToDate => {
+            let millis = match number(0)? {
+                Num::Int(millis) => i64::try_from(millis).ok(),
+                Num::Float(millis) => Some(millis as i64),
+            };
+            let datetime = millis
+                .and_then(DateTime::<Utc>::from_timestamp_millis)
+                .ok_or_else(|| invalid_operand(function, &args[0]))?;
+            match function {
+                Function::ToDate => Value::String(datetime.format("%Y-%m-%d").to_string()),
+                _ => Value::String(datetime.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            }
+        }
+        Function::Coalesce | Function::Concat | Function::Now => unreachable!(),
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interpreter::frontend::parser::Parser as SuiQLParser;
+    use pest::Parser;
+
+    fn projection(source: &str) -> Option<Projection> {
+        let mut pairs = SuiQLParser::parse(Rule::program, source).unwrap();
+        let entity = pairs.next().unwrap().into_inner().next().unwrap();
+        Projection::from_entity(&entity).unwrap()
+    }
+
+    #[test]
+    fn test_computed_columns() {
+        let projection = projection(
+            "SELECT digest, gas_budget - gas_used AS unused_gas, to_datetime(timestamp_ms), \
+             CASE WHEN status THEN 'ok' ELSE 'failed' END AS result, type \
+             FROM tx WHERE checkpoint = 10, gas_budget - gas_used > 1000 ON sui_mainnet",
+        )
+        .unwrap();
+
+        let rows: Vec<Row> = serde_json::from_str(
+            r#"[
+                {"digest": "a", "gas_budget": "5000", "gas_used": "1000", "status": "true", "timestamp_ms": "1700000000000", "kind": "ProgrammableTransaction"},
+                {"digest": "b", "gas_budget": "1500", "gas_used": "1000", "status": "false", "timestamp_ms": "1700000000000", "kind": "ProgrammableTransaction"}
+            ]"#,
+        )
+        .unwrap();
+
+        let rows = projection.apply(rows).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].columns,
+            vec![
+                ("digest".to_string(), Value::from("a")),
+                ("unused_gas".to_string(), Value::from(4000)),
+                (
+                    "to_datetime(timestamp_ms)".to_string(),
+                    Value::from("2023-11-14T22:13:20.000Z")
+                ),
+                ("result".to_string(), Value::from("ok")),
+                ("type".to_string(), Value::from("ProgrammableTransaction")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_order() {
+        let row: Row = serde_json::from_str(r#"{"digest": "a", "gas_used": "10"}"#).unwrap();
+
+        let selected = projection(
+            "SELECT sender, gas_used, digest AS tx FROM tx WHERE checkpoint = 1 ON sui_mainnet",
+        )
+        .unwrap();
+        assert!(!selected.is_plain());
+        assert_eq!(
+            selected.apply(vec![row.clone()]).unwrap()[0].columns,
+            vec![
+                ("sender".to_string(), Value::Null),
+                ("gas_used".to_string(), Value::from("10")),
+                ("tx".to_string(), Value::from("a")),
+            ]
+        );
+
+        let wildcard =
+            projection("SELECT * FROM tx WHERE checkpoint = 1, gas_used % 2 = 0 ON sui_mainnet")
+                .unwrap();
+        let columns = &wildcard.apply(vec![row]).unwrap()[0].columns;
+        let names = columns
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, JoinEntity::Transaction.all_fields());
+
+        let all = projection("SELECT * FROM tx WHERE checkpoint = 1 ON sui_mainnet").unwrap();
+        assert!(all.is_plain());
+        assert_eq!(all.column_names(), JoinEntity::Transaction.all_fields());
+    }
+
+    #[test]
+    fn test_distinct() {
+        let distinct = |source: &str| {
+            let mut pairs = SuiQLParser::parse(Rule::program, source).unwrap();
+            let entity = pairs.next().unwrap().into_inner().next().unwrap();
+            Distinct::from_entity(&entity)
+        };
+        let rows: Vec<Row> = serde_json::from_str(
+            r#"[
+                {"sender": "0x1", "digest": "a"},
+                {"sender": "0x1", "digest": "a"},
+                {"sender": "0x1", "digest": "b"},
+                {"sender": "0x2", "digest": "c"}
+            ]"#,
+        )
+        .unwrap();
+
+        let source = "SELECT DISTINCT sender, digest FROM tx WHERE checkpoint = 1 ON sui_mainnet";
+        assert_eq!(distinct(source), Some(Distinct::All));
+        let all = Distinct::All.apply(projection(source).unwrap().apply(rows.clone()).unwrap());
+        assert_eq!(all.len(), 3);
+
+        let source = "SELECT DISTINCT ON (sender) sender, digest FROM tx \
+                      WHERE checkpoint = 1 ON sui_mainnet";
+        let on = distinct(source).unwrap();
+        assert_eq!(on, Distinct::On(vec!["sender".to_string()]));
+        let projection = projection(source).unwrap();
+        assert!(on.validate(&projection.column_names()).is_ok());
+        let first = on.apply(projection.apply(rows).unwrap());
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].columns[1].1, Value::from("a"));
+
+        assert_eq!(
+            Distinct::On(vec!["gas_used".to_string()]).validate(&projection.column_names()),
+            Err(ExpressionError::UnknownDistinctColumn(
+                "gas_used".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_eval_expressions() {
+        let eval = |source: &str| {
+            let query =
+                format!("SELECT {source} AS value FROM tx WHERE checkpoint = 1 ON sui_mainnet");
+            let row: Row = serde_json::from_str(r#"{"sender": "0xABC", "gas_used": "7"}"#).unwrap();
+            let rows = projection(&query).unwrap().apply(vec![row]).unwrap();
+            rows[0].columns[0].1.clone()
+        };
+
+        assert_eq!(eval("1 + 2 * 3"), Value::from(7));
+        assert_eq!(eval("(1 + 2) * 3"), Value::from(9));
+        assert_eq!(eval("7 / 2"), Value::from(3.5));
+        assert_eq!(eval("-gas_used % 4"), Value::from(-3));
+        assert_eq!(eval("lower(sender) = '0xabc'"), Value::Bool(true));
+        assert_eq!(
+            eval("starts_with(sender, '0x') AND NOT gas_used > 10"),
+            Value::Bool(true)
+        );
+        assert_eq!(eval("substr(sender, 3, 2)"), Value::from("AB"));
+        assert_eq!(eval("coalesce(recipient, sender)"), Value::from("0xABC"));
+        assert_eq!(eval("recipient || 'x'"), Value::Null);
+        assert_eq!(eval("'it''s'"), Value::from("it's"));
+        assert_eq!(eval("to_date(86400000)"), Value::from("1970-01-02"));
+        assert_eq!(eval("round(2.567, 2)"), Value::from(2.57));
+        assert_eq!(eval("CASE WHEN gas_used < 5 THEN 'low' END"), Value::Null);
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        let eval = |source: &str| {
+            let query =
+                format!("SELECT {source} AS value FROM tx WHERE checkpoint = 1 ON sui_mainnet");
+            let row: Row = serde_json::from_value(serde_json::json!({
+                "gas_used": i128::MIN.to_string(),
+                "gas_price": i128::MAX.to_string(),
+            }))
+            .unwrap();
+            projection(&query)
+                .unwrap()
+                .apply(vec![row])
+                .map(|rows| rows[0].columns[0].1.clone())
+        };
+
+        let min = Value::from(i128::MIN.to_string());
+        assert_eq!(
+            eval("-gas_used"),
+            Err(ExpressionError::IntegerOverflow(
+                "Neg".to_string(),
+                min.to_string()
+            ))
+        );
+        assert_eq!(
+            eval("abs(gas_used)"),
+            Err(ExpressionError::IntegerOverflow(
+                "Abs".to_string(),
+                min.to_string()
+            ))
+        );
+        assert_eq!(
+            eval("-gas_price + 1 = -gas_price + 1"),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        let parse = |source: &str| {
+            let mut pairs = SuiQLParser::parse(Rule::program, source).unwrap();
+            let entity = pairs.next().unwrap().into_inner().next().unwrap();
+            Projection::from_entity(&entity)
+        };
+
+        assert_eq!(
+            parse("SELECT sha(digest) FROM tx WHERE checkpoint = 1 ON sui_mainnet"),
+            Err(ExpressionError::UnknownFunction("sha".to_string()))
+        );
+        assert!(matches!(
+            parse("SELECT lower(digest, 1) FROM tx WHERE checkpoint = 1 ON sui_mainnet"),
+            Err(ExpressionError::InvalidArgumentCount(
+                Function::Lower,
+                "1",
+                2
+            ))
+        ));
+        assert!(
+            parse("SELECT digest, sender FROM tx WHERE checkpoint = 1 ON sui_mainnet")
+                .unwrap()
+                .is_some_and(|projection| projection.is_plain())
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use crate::interpreter::frontend::parser::Rule;
 use pest::iterators::Pair;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
 
 pub trait Filter<T> {
     fn compare(&self, a: &T) -> bool;
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum FilterType<T> {
     Equality(EqualityFilter<T>),
     Comparison(ComparisonFilter<T>),
@@ -24,6 +26,76 @@ where
     }
 }
 
+impl<T> FilterType<T> {
+    pub fn value(&self) -> &T {
+        match self {
+            FilterType::Equality(filter) => filter.value(),
+            FilterType::Comparison(filter) => filter.value(),
+        }
+    }
+
+    /// Maps the filter value while keeping its operator.
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> U) -> FilterType<U> {
+        match self {
+            FilterType::Equality(filter) => FilterType::Equality(filter.map(f)),
+            FilterType::Comparison(filter) => FilterType::Comparison(filter.map(f)),
+        }
+    }
+}
+
+/// Writes the operator and the value, e.g. `>= 1000`, as they follow the column in a query.
+impl<T: Display> Display for FilterType<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterType::Equality(filter) => filter.fmt(f),
+            FilterType::Comparison(filter) => filter.fmt(f),
+        }
+    }
+}
+
+impl FilterType<u64> {
+    /// Returns the inclusive lower bound this filter puts on a value, if any.
+    pub fn lower_bound(&self) -> Option<u64> {
+        match self {
+            FilterType::Equality(EqualityFilter::Eq(value)) => Some(*value),
+            FilterType::Comparison(ComparisonFilter::Gt(value)) => Some(value.saturating_add(1)),
+            FilterType::Comparison(ComparisonFilter::Gte(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the inclusive upper bound this filter puts on a value, if any.
+    pub fn upper_bound(&self) -> Option<u64> {
+        match self {
+            FilterType::Equality(EqualityFilter::Eq(value)) => Some(*value),
+            FilterType::Comparison(ComparisonFilter::Lt(value)) => Some(value.saturating_sub(1)),
+            FilterType::Comparison(ComparisonFilter::Lte(value)) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Folds a list of numeric filters into the tightest inclusive `(lower, upper)` bounds.
+/// Filters that can't be expressed as a bound (e.g. `!=`) are ignored and must still be
+/// evaluated on the results.
+pub fn inclusive_bounds<'a>(
+    filters: impl IntoIterator<Item = &'a FilterType<u64>>,
+) -> (Option<u64>, Option<u64>) {
+    filters
+        .into_iter()
+        .fold((None, None), |(lower, upper), filter| {
+            let lower = match (lower, filter.lower_bound()) {
+                (Some(a), Some(b)) => Some(u64::max(a, b)),
+                (a, b) => a.or(b),
+            };
+            let upper = match (upper, filter.upper_bound()) {
+                (Some(a), Some(b)) => Some(u64::min(a, b)),
+                (a, b) => a.or(b),
+            };
+            (lower, upper)
+        })
+}
+
 impl<'a, T> TryFrom<(Pair<'a, Rule>, T)> for FilterType<T>
 where
     EqualityFilter<T>: TryFrom<(Pair<'a, Rule>, T), Error = EqualityFilterError>,
@@ -56,12 +128,36 @@ pub enum FilterError {
     ComparisonFilterError(#[from] ComparisonFilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum EqualityFilter<T> {
     Eq(T),
     Neq(T),
 }
 
+impl<T> EqualityFilter<T> {
+    pub fn value(&self) -> &T {
+        match self {
+            EqualityFilter::Eq(value) | EqualityFilter::Neq(value) => value,
+        }
+    }
+
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> U) -> EqualityFilter<U> {
+        match self {
+            EqualityFilter::Eq(value) => EqualityFilter::Eq(f(value)),
+            EqualityFilter::Neq(value) => EqualityFilter::Neq(f(value)),
+        }
+    }
+}
+
+impl<T: Display> Display for EqualityFilter<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EqualityFilter::Eq(value) => write!(f, "= {value}"),
+            EqualityFilter::Neq(value) => write!(f, "!= {value}"),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum EqualityFilterError {
     #[error("Invalid operator {0}")]
@@ -98,7 +194,7 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum ComparisonFilter<T> {
     Gt(T),
     Gte(T),
@@ -106,6 +202,26 @@ pub enum ComparisonFilter<T> {
     Lte(T),
 }
 
+impl<T> ComparisonFilter<T> {
+    pub fn value(&self) -> &T {
+        match self {
+            ComparisonFilter::Gt(value)
+            | ComparisonFilter::Gte(value)
+            | ComparisonFilter::Lt(value)
+            | ComparisonFilter::Lte(value) => value,
+        }
+    }
+
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> U) -> ComparisonFilter<U> {
+        match self {
+            ComparisonFilter::Gt(value) => ComparisonFilter::Gt(f(value)),
+            ComparisonFilter::Gte(value) => ComparisonFilter::Gte(f(value)),
+            ComparisonFilter::Lt(value) => ComparisonFilter::Lt(f(value)),
+            ComparisonFilter::Lte(value) => ComparisonFilter::Lte(f(value)),
+        }
+    }
+}
+
 impl<T> Filter<T> for ComparisonFilter<T>
 where
     T: PartialOrd,
@@ -120,6 +236,17 @@ where
     }
 }
 
+impl<T: Display> Display for ComparisonFilter<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparisonFilter::Gt(value) => write!(f, "> {value}"),
+            ComparisonFilter::Gte(value) => write!(f, ">= {value}"),
+            ComparisonFilter::Lt(value) => write!(f, "< {value}"),
+            ComparisonFilter::Lte(value) => write!(f, "<= {value}"),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ComparisonFilterError {
     #[error("Invalid operator {0}")]
@@ -181,4 +308,22 @@ mod tests {
         assert!(!filter.compare(&5));
         assert!(!filter.compare(&7));
     }
+
+    #[test]
+    fn test_inclusive_bounds() {
+        let filters = vec![
+            FilterType::Comparison(ComparisonFilter::Gte(1000)),
+            FilterType::Comparison(ComparisonFilter::Lt(2000)),
+            FilterType::Comparison(ComparisonFilter::Gt(1500)),
+            FilterType::Equality(EqualityFilter::Neq(1700)),
+        ];
+        assert_eq!(inclusive_bounds(&filters), (Some(1501), Some(1999)));
+        assert_eq!(inclusive_bounds(&[]), (None, None));
+    }
+
+    #[test]
+    fn test_filter_type_map() {
+        let filter = FilterType::Comparison(ComparisonFilter::Lte("10")).map(|s| s.len());
+        assert_eq!(filter, FilterType::Comparison(ComparisonFilter::Lte(2)));
+    }
 }
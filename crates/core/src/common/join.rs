@@ -0,0 +1,642 @@
+use super::{
+    account::{Account, AccountField},
+    checkpoint::{
+        Checkpoint, CheckpointError, CheckpointField, CheckpointId, CheckpointNumberOrTag,
+    },
+    coin::{Coin, CoinError, CoinField},
+    entity::{Entity, QueryText},
+    expression::ExpressionError,
+    name::{Name, NameError, NameField},
+    name_services::NameOrAddress,
+    object::{Object, ObjectField},
+    subquery::InFilterTarget,
+    transaction::{Transaction, TransactionError, TransactionField},
+};
+use crate::interpreter::frontend::parser::Rule;
+use pest::iterators::{Pair, Pairs};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+use sui_types::{base_types::ObjectID, digests::TransactionDigest};
+
+#[derive(thiserror::Error, Debug)]
+pub enum JoinError {
+    #[error("Unexpected token {0} for join")]
+    UnexpectedToken(String),
+
+    #[error("Unknown entity {0} in join")]
+    UnknownEntity(String),
+
+    #[error("Missing join condition")]
+    MissingJoinCondition,
+
+    #[error("Join condition {0} should compare a field of the source with the id of the joined entity, e.g. tx.sender = account.address")]
+    InvalidJoinCondition(String),
+
+    #[error("Joining an entity with itself isn't supported")]
+    SelfJoin,
+
+    #[error("Column {0} doesn't belong to an entity of the join")]
+    UnknownColumn(String),
+
+    #[error("Column {0} selects several fields and can't have an alias")]
+    WildcardAlias(String),
+
+    #[error("Invalid field {1} for entity {0}")]
+    InvalidField(JoinEntity, String),
+
+    #[error("Join key {1} isn't a valid {0} id")]
+    InvalidKey(JoinEntity, String),
+
+    #[error(transparent)]
+    TransactionError(#[from] TransactionError),
+
+    #[error(transparent)]
+    CheckpointError(#[from] CheckpointError),
+
+    #[error(transparent)]
+    CoinError(#[from] CoinError),
+
+    #[error(transparent)]
+    NameError(#[from] NameError),
+
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+}
+
+/// The entities that can appear in a join.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum JoinEntity {
+    Account,
+    Checkpoint,
+    Transaction,
+    Coin,
+    Object,
+    Name,
+}
+
+impl JoinEntity {
+    /// The field an entity is looked up by when it is the joined (right-hand) entity.
+    pub fn id_field(&self) -> &'static str {
+        match self {
+            JoinEntity::Account => "address",
+            JoinEntity::Checkpoint => "number",
+            JoinEntity::Transaction => "digest",
+            JoinEntity::Coin => "id",
+            JoinEntity::Object => "object_id",
+            JoinEntity::Name => "name",
+        }
+    }
+
+    /// All the fields of the entity, used to expand `entity.*`.
+    pub fn all_fields(&self) -> Vec<String> {
+        fn names<T: ToString>(fields: &[T]) -> Vec<String> {
+            fields.iter().map(ToString::to_string).collect()
+        }
+
+        match self {
+            JoinEntity::Account => names(AccountField::all_variants()),
+            JoinEntity::Checkpoint => names(&CheckpointField::wildcard_variants()),
+            JoinEntity::Transaction => names(&TransactionField::wildcard_variants()),
+            JoinEntity::Coin => names(CoinField::all_variants()),
+            JoinEntity::Object => names(ObjectField::all_variants()),
+            JoinEntity::Name => names(NameField::all_variants()),
+        }
+    }
+
+    /// The columns the entity can be filtered on with `IN`.
+    pub fn in_columns(&self) -> &'static [&'static str] {
+        match self {
+            JoinEntity::Account => Account::IN_COLUMNS,
+            JoinEntity::Checkpoint => Checkpoint::IN_COLUMNS,
+            JoinEntity::Transaction => Transaction::IN_COLUMNS,
+            JoinEntity::Coin => Coin::IN_COLUMNS,
+            JoinEntity::Object => Object::IN_COLUMNS,
+            JoinEntity::Name => Name::IN_COLUMNS,
+        }
+    }
+
+    /// The key a field is serialized under in the query results of the entity.
+    pub fn serialized_field<'a>(&self, field: &'a str) -> &'a str {
+        match (self, field) {
+            (JoinEntity::Account, "stake_amount") => "staked_amount",
+            (JoinEntity::Transaction, "type") => "kind",
+            (_, field) => field,
+        }
+    }
+
+    /// The entity queried by a `*_get` or `*_join` rule.
+    pub fn from_rule(rule: Rule) -> Option<JoinEntity> {
+        match rule {
+            Rule::account_get => Some(JoinEntity::Account),
+            Rule::checkpoint_get | Rule::checkpoint_join => Some(JoinEntity::Checkpoint),
+            Rule::tx_get | Rule::tx_join => Some(JoinEntity::Transaction),
+            Rule::coin_get | Rule::coin_join => Some(JoinEntity::Coin),
+            Rule::object_get => Some(JoinEntity::Object),
+            Rule::name_get | Rule::name_join => Some(JoinEntity::Name),
+            _ => None,
+        }
+    }
+
+    pub fn has_field(&self, field: &str) -> bool {
+        match self {
+            JoinEntity::Account => AccountField::try_from(field).is_ok(),
+            JoinEntity::Checkpoint => CheckpointField::try_from(field).is_ok(),
+            JoinEntity::Transaction => TransactionField::try_from(field).is_ok(),
+            JoinEntity::Coin => CoinField::try_from(field).is_ok(),
+            JoinEntity::Object => ObjectField::try_from(field).is_ok(),
+            JoinEntity::Name => NameField::try_from(field).is_ok(),
+        }
+    }
+
+    fn validate_field(&self, field: &str) -> Result<(), JoinError> {
+        if self.has_field(field) {
+            Ok(())
+        } else {
+            Err(JoinError::InvalidField(*self, field.to_string()))
+        }
+    }
+
+    /// Builds the query that looks up the entity by the given join keys.
+    pub fn lookup(&self, keys: &[String], fields: &[String]) -> Result<Entity, JoinError> {
+        let entity = match self {
+            JoinEntity::Account => Entity::Account(Account::new(
+                Some(self.parse_keys(keys, |key| NameOrAddress::from_str(key).ok())?),
+                None,
+                self.parse_fields(fields, AccountField::try_from)?,
+            )),
+            JoinEntity::Checkpoint => Entity::Checkpoint(Checkpoint::new(
+                Some(self.parse_keys(keys, |key| {
+                    key.parse::<u64>()
+                        .ok()
+                        .map(|number| CheckpointId::Number(CheckpointNumberOrTag::Number(number)))
+                })?),
+                None,
+                self.parse_fields(fields, CheckpointField::try_from)?,
+            )),
+            JoinEntity::Transaction => Entity::Transaction(Transaction::new(
+                Some(self.parse_keys(keys, |key| TransactionDigest::from_str(key).ok())?),
+                None,
+                self.parse_fields(fields, TransactionField::try_from)?,
+            )),
+            JoinEntity::Coin => Entity::Coin(Coin::new(
+                Some(keys.to_vec()),
+                None,
+                self.parse_fields(fields, CoinField::try_from)?,
+            )),
+            JoinEntity::Object => Entity::Object(Object::new(
+                Some(self.parse_keys(keys, |key| ObjectID::from_str(key).ok())?),
+                None,
+                self.parse_fields(fields, ObjectField::try_from)?,
+            )),
+            JoinEntity::Name => Entity::Name(Name::new(
+                Some(keys.to_vec()),
+                None,
+                self.parse_fields(fields, NameField::try_from)?,
+            )),
+        };
+
+        Ok(entity)
+    }
+
+    /// Builds the query for the source (left-hand) entity of a join from its `WHERE` clause.
+    fn source(
+        &self,
+        filters: Option<Pairs<'_, Rule>>,
+        fields: &[String],
+    ) -> Result<Entity, JoinError> {
+        let entity = match self {
+            JoinEntity::Transaction => {
+                let tx = match filters {
+                    Some(filters) => Transaction::try_from(filters)?,
+                    None => Transaction::new(None, None, vec![]),
+                };
+                Entity::Transaction(Transaction::new(
+                    tx.ids().cloned(),
+                    tx.filters().cloned(),
+                    self.parse_fields(fields, TransactionField::try_from)?,
+                ))
+            }
+            JoinEntity::Checkpoint => {
+                let checkpoint = match filters {
+                    Some(filters) => Checkpoint::try_from(filters)?,
+                    None => Checkpoint::new(None, None, vec![]),
+                };
+                Entity::Checkpoint(Checkpoint::new(
+                    checkpoint.ids().cloned(),
+                    checkpoint.filters().cloned(),
+                    self.parse_fields(fields, CheckpointField::try_from)?,
+                ))
+            }
+            JoinEntity::Coin => {
+                let coin = match filters {
+                    Some(filters) => Coin::try_from(filters)?,
+                    None => Coin::new(None, None, vec![]),
+                };
+                Entity::Coin(Coin::new(
+                    coin.ids().cloned(),
+                    coin.filter(),
+                    self.parse_fields(fields, CoinField::try_from)?,
+                ))
+            }
+            JoinEntity::Name => {
+                let name = match filters {
+                    Some(filters) => Name::try_from(filters)?,
+                    None => Name::new(None, None, vec![]),
+                };
+                Entity::Name(Name::new(
+                    name.ids().cloned(),
+                    name.filter(),
+                    self.parse_fields(fields, NameField::try_from)?,
+                ))
+            }
+            JoinEntity::Account | JoinEntity::Object => {
+                return Err(JoinError::UnexpectedToken(self.to_string()))
+            }
+        };
+
+        Ok(entity)
+    }
+
+    fn parse_keys<T>(
+        &self,
+        keys: &[String],
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<Vec<T>, JoinError> {
+        keys.iter()
+            .map(|key| parse(key).ok_or_else(|| JoinError::InvalidKey(*self, key.clone())))
+            .collect()
+    }
+
+    fn parse_fields<'a, T, E>(
+        &self,
+        fields: &'a [String],
+        parse: impl Fn(&'a str) -> Result<T, E>,
+    ) -> Result<Vec<T>, JoinError> {
+        fields
+            .iter()
+            .map(|field| parse(field).map_err(|_| JoinError::InvalidField(*self, field.clone())))
+            .collect()
+    }
+}
+
+impl Display for JoinEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            JoinEntity::Account => "account",
+            JoinEntity::Checkpoint => "checkpoint",
+            JoinEntity::Transaction => "tx",
+            JoinEntity::Coin => "coin",
+            JoinEntity::Object => "object",
+            JoinEntity::Name => "name",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl TryFrom<&str> for JoinEntity {
+    type Error = JoinError;
+
+    fn try_from(value: &str) -> Result<Self, JoinError> {
+        match value {
+            "account" => Ok(JoinEntity::Account),
+            "checkpoint" => Ok(JoinEntity::Checkpoint),
+            "tx" => Ok(JoinEntity::Transaction),
+            "coin" => Ok(JoinEntity::Coin),
+            "object" => Ok(JoinEntity::Object),
+            "name" => Ok(JoinEntity::Name),
+            _ => Err(JoinError::UnknownEntity(value.to_string())),
+        }
+    }
+}
+
+/// A qualified `entity.field` column. `entity.*` columns are expanded when parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct JoinColumn {
+    pub entity: JoinEntity,
+    pub field: String,
+    pub alias: Option<String>,
+}
+
+impl JoinColumn {
+    fn parse_all(pair: Pair<'_, Rule>) -> Result<Vec<JoinColumn>, JoinError> {
+        let mut inner = pair.into_inner();
+        let entity = inner
+            .next()
+            .ok_or(JoinError::MissingJoinCondition)
+            .and_then(|entity| JoinEntity::try_from(entity.as_str()))?;
+
+        match inner.next() {
+            Some(field) if field.as_rule() == Rule::join_field_name => {
+                entity.validate_field(field.as_str())?;
+                Ok(vec![JoinColumn {
+                    entity,
+                    field: field.as_str().to_string(),
+                    alias: None,
+                }])
+            }
+            _ => Ok(entity
+                .all_fields()
+                .into_iter()
+                .map(|field| JoinColumn {
+                    entity,
+                    field,
+                    alias: None,
+                })
+                .collect()),
+        }
+    }
+}
+
+impl JoinColumn {
+    /// The name of the column in the results: its alias, or `entity.field`.
+    pub fn name(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.to_string())
+    }
+}
+
+impl Display for JoinColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.entity, self.field)
+    }
+}
+
+/// A lookup join: the source rows are fetched first, then the joined entity is fetched by id
+/// once for every distinct value of the source key.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Join {
+    source: Box<Entity>,
+    source_entity: JoinEntity,
+    source_key: String,
+    target: JoinEntity,
+    columns: Vec<JoinColumn>,
+}
+
+impl Join {
+    /// The query for the source rows, selecting the source columns and the join key.
+    pub fn source(&self) -> &Entity {
+        &self.source
+    }
+
+    pub fn source_mut(&mut self) -> &mut Entity {
+        &mut self.source
+    }
+
+    pub fn source_entity(&self) -> JoinEntity {
+        self.source_entity
+    }
+
+    pub fn source_key(&self) -> &str {
+        &self.source_key
+    }
+
+    pub fn target(&self) -> JoinEntity {
+        self.target
+    }
+
+    pub fn columns(&self) -> &Vec<JoinColumn> {
+        &self.columns
+    }
+
+    /// The fields to fetch for the joined entity: its selected columns and its id.
+    pub fn target_fields(&self) -> Vec<String> {
+        fields_with_key(&self.columns, self.target, self.target.id_field())
+    }
+
+    /// The join as query text, with the ids and filters of its source as the `WHERE` clause.
+    pub(crate) fn query_text(&self) -> QueryText {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| match &column.alias {
+                Some(alias) => format!("{column} AS {alias}"),
+                None => column.to_string(),
+            })
+            .collect();
+        let source = format!(
+            "{source} JOIN {target} ON {source}.{key} = {target}.{id}",
+            source = self.source_entity,
+            target = self.target,
+            key = self.source_key,
+            id = self.target.id_field(),
+        );
+
+        QueryText {
+            columns: Some(columns),
+            source,
+            ids_in_where: true,
+            ..self.source.query_text()
+        }
+    }
+}
+
+impl Display for Join {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.query_text().fmt(f)
+    }
+}
+
+/// The fields selected for an entity, followed by the join key if it isn't selected.
+fn fields_with_key(columns: &[JoinColumn], entity: JoinEntity, key: &str) -> Vec<String> {
+    let mut fields: Vec<String> = columns
+        .iter()
+        .filter(|column| column.entity == entity)
+        .map(|column| column.field.clone())
+        .collect();
+    if !fields.iter().any(|field| field == key) {
+        fields.push(key.to_string());
+    }
+    fields
+}
+
+impl TryFrom<Pairs<'_, Rule>> for Join {
+    type Error = JoinError;
+
+    fn try_from(pairs: Pairs<'_, Rule>) -> Result<Self, Self::Error> {
+        let mut columns: Vec<JoinColumn> = vec![];
+        let mut source: Option<(JoinEntity, Option<Pairs<'_, Rule>>, Pair<'_, Rule>)> = None;
+
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::distinct => {}
+                Rule::join_fields => {
+                    for select in pair.into_inner() {
+                        let mut inner = select.into_inner();
+                        let column = inner.next().ok_or(JoinError::MissingJoinCondition)?;
+                        let column_str = column.as_str().to_string();
+                        let mut selected = JoinColumn::parse_all(column)?;
+                        if let Some(alias) = inner.next() {
+                            match selected.as_mut_slice() {
+                                [column] => column.alias = Some(alias.as_str().to_string()),
+                                _ => return Err(JoinError::WildcardAlias(column_str)),
+                            }
+                        }
+                        columns.extend(selected);
+                    }
+                }
+                Rule::join_source => {
+                    let source_pair = pair
+                        .into_inner()
+                        .next()
+                        .ok_or(JoinError::MissingJoinCondition)?;
+                    let source_entity =
+                        JoinEntity::from_rule(source_pair.as_rule()).ok_or_else(|| {
+                            JoinError::UnexpectedToken(source_pair.as_str().to_string())
+                        })?;
+
+                    let mut inner = source_pair.into_inner();
+                    let join_clause = inner.next().ok_or(JoinError::MissingJoinCondition)?;
+                    let filters = inner.next().map(|filters| filters.into_inner());
+                    if let Some(filters) = &filters {
+                        if filters
+                            .clone()
+                            .flatten()
+                            .any(|filter| filter.as_rule() == Rule::expr_filter)
+                        {
+                            return Err(ExpressionError::Unsupported("joins").into());
+                        }
+                    }
+                    source = Some((source_entity, filters, join_clause));
+                }
+                _ => return Err(JoinError::UnexpectedToken(pair.as_str().to_string())),
+            }
+        }
+
+        let (source_entity, filters, join_clause) =
+            source.ok_or(JoinError::MissingJoinCondition)?;
+
+        let mut join_clause = join_clause.into_inner();
+        let target = join_clause
+            .next()
+            .ok_or(JoinError::MissingJoinCondition)
+            .and_then(|target| JoinEntity::try_from(target.as_str()))?;
+        if target == source_entity {
+            return Err(JoinError::SelfJoin);
+        }
+        let condition = join_clause.next().ok_or(JoinError::MissingJoinCondition)?;
+        let condition_str = condition.as_str().to_string();
+
+        let sides = condition
+            .into_inner()
+            .map(JoinColumn::parse_all)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // The condition can be written either way around.
+        let (source_key, target_key) = match sides.as_slice() {
+            [a, b] if a.entity == source_entity && b.entity == target => (a, b),
+            [a, b] if b.entity == source_entity && a.entity == target => (b, a),
+            _ => return Err(JoinError::InvalidJoinCondition(condition_str)),
+        };
+        if target_key.field != target.id_field() {
+            return Err(JoinError::InvalidJoinCondition(condition_str));
+        }
+
+        if let Some(column) = columns
+            .iter()
+            .find(|column| column.entity != source_entity && column.entity != target)
+        {
+            return Err(JoinError::UnknownColumn(column.to_string()));
+        }
+
+        let source_fields = fields_with_key(&columns, source_entity, &source_key.field);
+        let source = source_entity.source(filters, &source_fields)?;
+
+        Ok(Join {
+            source: Box::new(source),
+            source_entity,
+            source_key: source_key.field.clone(),
+            target,
+            columns,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        common::{query_result::TransactionQueryRes, types::Expression},
+        interpreter::frontend::parser::Parser as SuiQLParser,
+    };
+
+    fn parse_join(source: &str) -> anyhow::Result<Join> {
+        let expressions = SuiQLParser::new(source).parse_expressions()?;
+        match expressions.into_iter().next() {
+            Some(Expression::Get(get)) => match get.entity {
+                Entity::Join(join) => Ok(join),
+                entity => anyhow::bail!("Expected a join, got {:?}", entity),
+            },
+            Some(expression) => anyhow::bail!("Expected a query, got {:?}", expression),
+            None => anyhow::bail!("Missing expression"),
+        }
+    }
+
+    #[test]
+    fn test_parse_join() {
+        let join = parse_join(
+            "SELECT tx.digest, account.sui_balance AS balance FROM tx JOIN account ON account.address = tx.sender WHERE checkpoint = 100 ON sui_mainnet",
+        )
+        .unwrap();
+
+        assert_eq!(join.source_entity(), JoinEntity::Transaction);
+        assert_eq!(join.source_key(), "sender");
+        assert_eq!(join.target(), JoinEntity::Account);
+        assert_eq!(
+            join.columns()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["tx.digest", "account.sui_balance"]
+        );
+        assert_eq!(
+            join.columns()
+                .iter()
+                .map(JoinColumn::name)
+                .collect::<Vec<_>>(),
+            vec!["tx.digest", "balance"]
+        );
+        assert_eq!(join.target_fields(), vec!["sui_balance", "address"]);
+        match join.source() {
+            Entity::Transaction(tx) => assert_eq!(
+                tx.fields(),
+                &vec![TransactionField::Digest, TransactionField::Sender]
+            ),
+            entity => panic!("Expected a transaction source, got {:?}", entity),
+        }
+    }
+
+    #[test]
+    fn test_join_source_filters() {
+        let join = parse_join(
+            "SELECT tx.digest, account.sui_balance FROM tx JOIN account ON tx.sender = account.address WHERE checkpoint = 10, gas_used > 1000 ON sui_mainnet",
+        )
+        .unwrap();
+        let Entity::Transaction(tx) = join.source() else {
+            panic!("Expected a transaction source, got {:?}", join.source());
+        };
+
+        // `gas_used` isn't selected, but it is fetched to check the filter.
+        assert_eq!(
+            tx.fields(),
+            &vec![TransactionField::Digest, TransactionField::Sender]
+        );
+        assert_eq!(tx.filter_fields(), vec![TransactionField::GasUsed]);
+
+        let mut row = TransactionQueryRes::default();
+        assert!(!tx.filter(&row));
+        row.gas_used = Some(500);
+        assert!(!tx.filter(&row));
+        row.gas_used = Some(2000);
+        assert!(tx.filter(&row));
+    }
+
+    #[test]
+    fn test_join_condition_must_use_target_id() {
+        let result = parse_join(
+            "SELECT tx.digest FROM tx JOIN account ON tx.sender = account.sui_balance WHERE checkpoint = 100 ON sui_mainnet",
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,110 @@
+/// Strips the quotes of a `'single'` or `"double"` quoted string and resolves its escapes: a
+/// doubled quote, `\n`, `\r`, `\t`, and `\` followed by any other character, which is kept as
+/// it is. Values without quotes are returned unchanged.
+pub fn unquote(value: &str) -> String {
+    let Some(quote) = value.chars().next().filter(|c| *c == '\'' || *c == '"') else {
+        return value.to_string();
+    };
+    let Some(inner) = value[1..].strip_suffix(quote) else {
+        return value.to_string();
+    };
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some('r') => unquoted.push('\r'),
+                Some('t') => unquoted.push('\t'),
+                Some(escaped) => unquoted.push(escaped),
+                None => unquoted.push('\\'),
+            },
+            c if c == quote => {
+                chars.next();
+                unquoted.push(quote);
+            }
+            c => unquoted.push(c),
+        }
+    }
+
+    unquoted
+}
+
+/// The end of the comment or quoted string starting at byte `start`, if there is one.
+pub fn literal_end(source: &str, start: usize) -> Option<usize> {
+    let rest = &source[start..];
+    let len = if rest.starts_with("--") {
+        rest.find('\n').unwrap_or(rest.len())
+    } else if let Some(comment) = rest.strip_prefix("/*") {
+        comment.find("*/").map_or(rest.len(), |end| end + 4)
+    } else if let Some(quote) = rest.chars().next().filter(|c| *c == '\'' || *c == '"') {
+        let mut escaped = false;
+        rest.char_indices()
+            .skip(1)
+            .find(|(_, c)| {
+                let closes = !escaped && *c == quote;
+                escaped = !escaped && *c == '\\';
+                closes
+            })
+            .map_or(rest.len(), |(end, _)| end + 1)
+    } else {
+        return None;
+    };
+    Some(start + len)
+}
+
+/// Removes the `--` and `/* */` comments of `source`, leaving quoted strings as they are.
+pub fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut start = 0;
+    while let Some(offset) = source[start..].find(['-', '/', '\'', '"']) {
+        let i = start + offset;
+        match literal_end(source, i) {
+            Some(end) => {
+                let literal = &source[i..end];
+                if literal.starts_with(['\'', '"']) {
+                    stripped.push_str(&source[start..end]);
+                } else {
+                    stripped.push_str(&source[start..i]);
+                }
+                start = end;
+            }
+            None => {
+                stripped.push_str(&source[start..=i]);
+                start = i + 1;
+            }
+        }
+    }
+    stripped.push_str(&source[start..]);
+    stripped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("example.sui"), "example.sui");
+        assert_eq!(unquote("'example.sui'"), "example.sui");
+        assert_eq!(unquote(r#""0x2::sui::SUI""#), "0x2::sui::SUI");
+        assert_eq!(unquote("'it''s'"), "it's");
+        assert_eq!(unquote(r"'it\'s'"), "it's");
+        assert_eq!(unquote(r#""say \"hi\"\n""#), "say \"hi\"\n");
+        assert_eq!(unquote(r"'C:\\sui'"), r"C:\sui");
+        assert_eq!(unquote("'"), "'");
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        assert_eq!(
+            strip_comments("gas_used /* total */ - 1 -- minus one\n"),
+            "gas_used  - 1 \n"
+        );
+        assert_eq!(
+            strip_comments("'-- kept' || \"/* kept */\""),
+            "'-- kept' || \"/* kept */\""
+        );
+    }
+}
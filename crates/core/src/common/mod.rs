@@ -1,4 +1,5 @@
 pub mod account;
+pub mod builder;
 pub mod chain;
 pub mod checkpoint;
 pub mod coin;
@@ -7,10 +8,18 @@ pub mod dump;
 pub mod entity;
 pub mod entity_id;
 pub mod event;
+pub mod expression;
 pub mod filters;
+pub mod join;
+pub mod literal;
+pub mod name;
 pub mod name_services;
 pub mod object;
+pub mod params;
 pub mod query_result;
+pub mod schema;
 pub mod serializer;
+pub mod subquery;
+pub mod timestamp;
 pub mod transaction;
 pub mod types;
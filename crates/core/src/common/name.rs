@@ -0,0 +1,310 @@
+use super::entity::QueryText;
+use super::expression::add_fields;
+use super::filters::{EqualityFilter, EqualityFilterError};
+use super::join::JoinEntity;
+use super::literal::unquote;
+use super::subquery::{
+    apply_in_ids, take_subquery_filters, InFilter, InFilterError, InFilterTarget, Subquery,
+};
+use crate::interpreter::frontend::parser::Rule;
+use eql_macros::EnumVariants;
+use pest::iterators::{Pair, Pairs};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+use sui_types::base_types::SuiAddress;
+
+#[derive(thiserror::Error, Debug)]
+pub enum NameError {
+    #[error("Unexpected token {0}")]
+    UnexpectedToken(String),
+
+    #[error(transparent)]
+    NameFieldError(#[from] NameFieldError),
+
+    #[error(transparent)]
+    NameFilterError(#[from] NameFilterError),
+
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
+}
+
+/// A SuiNS name, looked up either by name or by the address it belongs to.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Name {
+    id: Option<Vec<String>>,
+    filter: Option<Vec<NameFilter>>,
+    fields: Vec<NameField>,
+}
+
+impl Name {
+    pub fn new(
+        id: Option<Vec<String>>,
+        filter: Option<Vec<NameFilter>>,
+        fields: Vec<NameField>,
+    ) -> Self {
+        Self { id, filter, fields }
+    }
+
+    pub fn ids(&self) -> Option<&Vec<String>> {
+        self.id.as_ref()
+    }
+
+    pub fn filter(&self) -> Option<Vec<NameFilter>> {
+        self.filter.clone()
+    }
+
+    pub fn fields(&self) -> Vec<NameField> {
+        self.fields.clone()
+    }
+
+    /// Names selected with `name = ...` filters.
+    pub fn name_filters(&self) -> Vec<&EqualityFilter<String>> {
+        self.filter
+            .iter()
+            .flatten()
+            .filter_map(|f| match f {
+                NameFilter::Name(filter) => Some(filter),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Addresses selected with `address = ...` filters.
+    pub fn address_filters(&self) -> Vec<&EqualityFilter<SuiAddress>> {
+        self.filter
+            .iter()
+            .flatten()
+            .filter_map(|f| match f {
+                NameFilter::Address(filter) => Some(filter),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Name {
+    pub(crate) fn query_text(&self) -> QueryText {
+        QueryText::new(
+            JoinEntity::Name,
+            &self.fields,
+            NameField::all_variants(),
+            self.id.iter().flatten(),
+            self.filter.iter().flatten(),
+        )
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.query_text().fmt(f)
+    }
+}
+
+impl TryFrom<Pairs<'_, Rule>> for Name {
+    type Error = NameError;
+
+    fn try_from(pairs: Pairs<'_, Rule>) -> Result<Self, Self::Error> {
+        let mut fields: Vec<NameField> = vec![];
+        let mut id: Option<Vec<String>> = None;
+        let mut filter: Option<Vec<NameFilter>> = None;
+        let mut in_filters: Vec<InFilter> = vec![];
+
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::distinct => {}
+                Rule::name_fields => {
+                    let inner_pairs = pair.clone().into_inner();
+                    if let Some(pair) = inner_pairs.peek() {
+                        if pair.as_rule() == Rule::wildcard {
+                            fields = NameField::all_variants().to_vec();
+                            continue;
+                        }
+                    }
+                    add_fields(&mut fields, pair, |field| NameField::try_from(field))?;
+                }
+
+                Rule::name_id => {
+                    let val = unquote(pair.as_str().trim());
+
+                    if let Some(ref mut vec) = id {
+                        vec.push(val);
+                    } else {
+                        id = Some(vec![val]);
+                    }
+                }
+
+                Rule::name_filter => {
+                    let inner = pair.into_inner().next().unwrap();
+                    if inner.as_rule() == Rule::expr_filter {
+                        add_fields(&mut fields, inner, |field| NameField::try_from(field))?;
+                        continue;
+                    }
+                    if inner.as_rule() == Rule::in_filter {
+                        in_filters.push(InFilter::try_from(inner)?);
+                        continue;
+                    }
+                    filter
+                        .get_or_insert_with(Vec::new)
+                        .push(NameFilter::try_from(inner)?);
+                }
+
+                Rule::variable => {
+                    in_filters.push(InFilter::variable(Name::IN_COLUMNS[0], pair));
+                }
+
+                _ => return Err(NameError::UnexpectedToken(pair.as_str().to_string())),
+            }
+        }
+
+        let mut name = Name { id, filter, fields };
+        for in_filter in in_filters {
+            name.add_in_filter(in_filter)?;
+        }
+
+        Ok(name)
+    }
+}
+
+impl InFilterTarget for Name {
+    const IN_COLUMNS: &'static [&'static str] = &["name"];
+
+    fn apply_in(&mut self, _column: &str, values: &[String]) -> Result<(), InFilterError> {
+        apply_in_ids(&mut self.id, values.to_vec());
+        Ok(())
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        self.filter
+            .get_or_insert_with(Vec::new)
+            .push(NameFilter::Subquery(subquery));
+    }
+
+    fn take_subqueries(&mut self) -> Vec<Subquery> {
+        take_subquery_filters(&mut self.filter, |f| match f {
+            NameFilter::Subquery(subquery) => Some(subquery),
+            _ => None,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NameFilterError {
+    #[error("Unexpected token {0} for Name filter")]
+    UnexpectedToken(String),
+
+    #[error("Failed to parse address: {0}")]
+    AddressParseError(String),
+
+    #[error("Missing operator in filter")]
+    MissingOperator,
+
+    #[error(transparent)]
+    EqualityFilterError(#[from] EqualityFilterError),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum NameFilter {
+    Name(EqualityFilter<String>),
+    Address(EqualityFilter<SuiAddress>),
+    Subquery(Subquery),
+}
+
+impl Display for NameFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NameFilter::Name(filter) => write!(f, "name {filter}"),
+            NameFilter::Address(filter) => write!(f, "address {filter}"),
+            NameFilter::Subquery(subquery) => write!(f, "{subquery}"),
+        }
+    }
+}
+
+impl TryFrom<Pair<'_, Rule>> for NameFilter {
+    type Error = NameFilterError;
+
+    fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        let rule = pair.as_rule();
+        let mut inner_pair = pair.into_inner();
+        let operator = inner_pair.next().ok_or(NameFilterError::MissingOperator)?;
+        let value = inner_pair.as_str().trim();
+
+        match rule {
+            Rule::ns_filter_type => Ok(NameFilter::Name(EqualityFilter::try_from((
+                operator,
+                unquote(value),
+            ))?)),
+            Rule::address_filter_type => {
+                let address = SuiAddress::from_str(value)
+                    .map_err(|e| NameFilterError::AddressParseError(e.to_string()))?;
+                Ok(NameFilter::Address(EqualityFilter::try_from((
+                    operator, address,
+                ))?))
+            }
+            _ => Err(NameFilterError::UnexpectedToken(value.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, EnumVariants)]
+pub enum NameField {
+    Name,
+    Address,
+    Expiration,
+    Target,
+    Avatar,
+    Chain,
+}
+
+impl NameField {
+    /// Whether the field is read from the name record in the SuiNS registry.
+    pub fn is_record_field(&self) -> bool {
+        matches!(
+            self,
+            NameField::Expiration | NameField::Target | NameField::Avatar
+        )
+    }
+}
+
+impl Display for NameField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            NameField::Name => "name",
+            NameField::Address => "address",
+            NameField::Expiration => "expiration",
+            NameField::Target => "target",
+            NameField::Avatar => "avatar",
+            NameField::Chain => "chain",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NameFieldError {
+    #[error("Invalid field for entity Name: {0}")]
+    InvalidField(String),
+}
+
+impl<'a> TryFrom<Pair<'a, Rule>> for NameField {
+    type Error = NameFieldError;
+
+    fn try_from(pair: Pair<'a, Rule>) -> Result<Self, Self::Error> {
+        NameField::try_from(pair.as_str())
+    }
+}
+
+impl TryFrom<&str> for NameField {
+    type Error = NameFieldError;
+
+    fn try_from(value: &str) -> Result<Self, NameFieldError> {
+        match value {
+            "name" => Ok(NameField::Name),
+            "address" => Ok(NameField::Address),
+            "expiration" => Ok(NameField::Expiration),
+            "target" => Ok(NameField::Target),
+            "avatar" => Ok(NameField::Avatar),
+            "chain" => Ok(NameField::Chain),
+            _ => Err(NameFieldError::InvalidField(value.to_string())),
+        }
+    }
+}
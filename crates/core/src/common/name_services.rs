@@ -1,7 +1,17 @@
-use std::str::FromStr;
+use super::chain::Chain;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fmt::Display, str::FromStr};
 use sui_json_rpc_api::IndexerApiClient;
+use sui_json_rpc_types::{
+    SuiObjectDataFilter, SuiObjectDataOptions, SuiObjectResponseQuery, SuiParsedData,
+};
 use sui_sdk::SuiClient;
-use sui_types::base_types::SuiAddress;
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    dynamic_field::DynamicFieldName,
+    parse_sui_struct_tag, parse_sui_type_tag,
+};
 
 /// Error type for NS resolution.
 #[derive(Debug, thiserror::Error)]
@@ -15,10 +25,174 @@ pub enum NSError {
 
     #[error("Failed to resolve address to an name: {0}")]
     ResolveAddress(String),
+
+    /// Failed to read a name record from the SuiNS registry.
+    #[error("Failed to read SuiNS record: {0}")]
+    Record(String),
+}
+
+/// Where SuiNS is deployed on a chain. Records are read from the registry table, which
+/// stores a `NameRecord` under a dynamic field keyed by the `Domain` of each name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuiNsConfig {
+    /// Package that defines the `Domain` and `SuinsRegistration` types.
+    pub package: &'static str,
+    /// Table holding the name records of the registry.
+    pub registry_table: &'static str,
+}
+
+impl SuiNsConfig {
+    /// Returns the SuiNS deployment of a chain, if it has one.
+    pub fn for_chain(chain: &Chain) -> Option<Self> {
+        match chain {
+            Chain::Mainnet => Some(Self {
+                package: "0xd22b24490e0bae52676651b4f56660a5ff8022a2576e0089f79b3c88d44e08f0",
+                registry_table:
+                    "0xe64cd9db9f829c6cc405d9790bd71567ae07259855f4fba6f02c84f52298c106",
+            }),
+            Chain::Testnet => Some(Self {
+                package: "0x22fa05f21b1ad71442491220bb9338f7b7095fe35000ef88d5400d28523bdd93",
+                registry_table:
+                    "0xb120c0d55432630fce61f7854795a3463deb6e3b443cc4ae72e1282073ff56e4",
+            }),
+            Chain::Devnet | Chain::Custom(_) => None,
+        }
+    }
+}
+
+/// The parts of a SuiNS `NameRecord` exposed by queries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameRecord {
+    pub expiration_timestamp_ms: Option<u64>,
+    pub target_address: Option<SuiAddress>,
+    pub avatar: Option<String>,
+}
+
+/// Reads the record of a name from the SuiNS registry. Returns `None` if the name isn't registered.
+pub async fn get_name_record(
+    name: &str,
+    config: &SuiNsConfig,
+    provider: &SuiClient,
+) -> Result<Option<NameRecord>, NSError> {
+    let registry_table =
+        ObjectID::from_str(config.registry_table).map_err(|e| NSError::Record(e.to_string()))?;
+    let domain_type = parse_sui_type_tag(&format!("{}::domain::Domain", config.package))
+        .map_err(|e| NSError::Record(e.to_string()))?;
+    let domain = DynamicFieldName {
+        type_: domain_type,
+        value: serde_json::json!({ "labels": domain_labels(name) }),
+    };
+
+    let response = provider
+        .read_api()
+        .get_dynamic_field_object(registry_table, domain)
+        .await
+        .map_err(|e| NSError::Record(e.to_string()))?;
+
+    let fields = match response.data.and_then(|data| data.content) {
+        Some(SuiParsedData::MoveObject(object)) => object.fields.to_json_value(),
+        _ => return Ok(None),
+    };
+    let record = struct_fields(&fields["value"]);
+    let avatar = struct_fields(&record["data"])["contents"]
+        .as_array()
+        .and_then(|entries| {
+            entries
+                .iter()
+                .map(struct_fields)
+                .find(|entry| entry["key"] == "avatar")
+        })
+        .and_then(|entry| entry["value"].as_str())
+        .map(String::from);
+
+    Ok(Some(NameRecord {
+        expiration_timestamp_ms: record["expiration_timestamp_ms"]
+            .as_str()
+            .and_then(|value| value.parse().ok()),
+        target_address: record["target_address"]
+            .as_str()
+            .and_then(|value| SuiAddress::from_str(value).ok()),
+        avatar,
+    }))
+}
+
+/// Lists every name an address is known by: the names that reverse-resolve to it, followed by
+/// the names whose `SuinsRegistration` NFT it owns.
+pub async fn get_address_names(
+    address: SuiAddress,
+    config: Option<&SuiNsConfig>,
+    provider: &SuiClient,
+) -> Result<Vec<String>, NSError> {
+    let mut names = vec![];
+
+    let mut cursor = None;
+    loop {
+        let page = provider
+            .http()
+            .resolve_name_service_names(address, cursor, None)
+            .await
+            .map_err(|e| NSError::ResolveAddress(e.to_string()))?;
+        names.extend(page.data);
+        if !page.has_next_page {
+            break;
+        }
+        cursor = page.next_cursor;
+    }
+
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(names),
+    };
+    let registration_type = parse_sui_struct_tag(&format!(
+        "{}::suins_registration::SuinsRegistration",
+        config.package
+    ))
+    .map_err(|e| NSError::ResolveAddress(e.to_string()))?;
+    let query = SuiObjectResponseQuery::new(
+        Some(SuiObjectDataFilter::StructType(registration_type)),
+        Some(SuiObjectDataOptions::new().with_content()),
+    );
+
+    let mut cursor = None;
+    loop {
+        let page = provider
+            .read_api()
+            .get_owned_objects(address, Some(query.clone()), cursor, None)
+            .await
+            .map_err(|e| NSError::ResolveAddress(e.to_string()))?;
+        for object in page.data {
+            if let Some(SuiParsedData::MoveObject(registration)) =
+                object.data.and_then(|data| data.content)
+            {
+                let fields = registration.fields.to_json_value();
+                if let Some(name) = struct_fields(&fields)["domain_name"].as_str() {
+                    if !names.iter().any(|known| known == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        if !page.has_next_page {
+            break;
+        }
+        cursor = page.next_cursor;
+    }
+
+    Ok(names)
+}
+
+/// SuiNS stores domains as their labels in reverse order: `sub.example.sui` is `[sui, example, sub]`.
+fn domain_labels(name: &str) -> Vec<&str> {
+    name.split('.').rev().collect()
+}
+
+/// Nested structs may come with their type next to their fields.
+fn struct_fields(value: &Value) -> &Value {
+    value.get("fields").unwrap_or(value)
 }
 
 /// NS name or Ethereum Address.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub enum NameOrAddress {
     /// An Name Service (format does not get checked)
     Name(String),
@@ -81,6 +255,15 @@ impl NameOrAddress {
     }
 }
 
+impl Display for NameOrAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "{name}"),
+            Self::Address(address) => write!(f, "{address}"),
+        }
+    }
+}
+
 impl From<String> for NameOrAddress {
     fn from(name: String) -> Self {
         Self::Name(name)
@@ -173,6 +356,15 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_domain_labels() {
+        assert_eq!(domain_labels("test.sui"), vec!["sui", "test"]);
+        assert_eq!(
+            domain_labels("sub.my-name.sui"),
+            vec!["sui", "my-name", "sub"]
+        );
+    }
+
     #[tokio::test]
     async fn test_resolve_address_failed() {
         let provider = SuiClientBuilder::default().build_mainnet().await.unwrap();
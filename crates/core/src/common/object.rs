@@ -1,9 +1,17 @@
 // use super::ens::NameOrAddress;
+use super::entity::QueryText;
+use super::entity_id::unique_ids;
+use super::expression::add_fields;
+use super::join::JoinEntity;
+use super::subquery::{
+    apply_in_ids, parse_in_values, take_subquery_filters, InFilter, InFilterError, InFilterTarget,
+    Subquery,
+};
 use crate::interpreter::frontend::parser::Rule;
 use eql_macros::EnumVariants;
 use pest::iterators::{Pair, Pairs};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 use sui_types::base_types::{ObjectID, ObjectIDParseError};
 
 #[derive(thiserror::Error, Debug)]
@@ -19,9 +27,12 @@ pub enum ObjectError {
 
     #[error(transparent)]
     ObjectParseError(#[from] ObjectIDParseError),
+
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Object {
     id: Option<Vec<ObjectID>>,
     filter: Option<Vec<ObjectFilter>>,
@@ -48,6 +59,33 @@ impl Object {
     pub fn fields(&self) -> Vec<ObjectField> {
         self.fields.clone()
     }
+
+    /// The ids to fetch, from the id list and the `object = ...` filters.
+    pub fn object_ids(&self) -> Vec<ObjectID> {
+        let filter_ids = self.filter.iter().flatten().filter_map(|f| match f {
+            ObjectFilter::ObjectId(id) => Some(*id),
+            _ => None,
+        });
+        unique_ids(self.id.iter().flatten().copied().chain(filter_ids))
+    }
+}
+
+impl Object {
+    pub(crate) fn query_text(&self) -> QueryText {
+        QueryText::new(
+            JoinEntity::Object,
+            &self.fields,
+            ObjectField::all_variants(),
+            self.id.iter().flatten(),
+            self.filter.iter().flatten(),
+        )
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.query_text().fmt(f)
+    }
 }
 
 impl TryFrom<Pairs<'_, Rule>> for Object {
@@ -57,11 +95,13 @@ impl TryFrom<Pairs<'_, Rule>> for Object {
         let mut fields: Vec<ObjectField> = vec![];
         let mut id: Option<Vec<ObjectID>> = None;
         let mut filter: Option<Vec<ObjectFilter>> = None;
+        let mut in_filters: Vec<InFilter> = vec![];
 
         for pair in pairs {
             match pair.as_rule() {
+                Rule::distinct => {}
                 Rule::object_fields => {
-                    let inner_pairs = pair.into_inner();
+                    let inner_pairs = pair.clone().into_inner();
 
                     if let Some(pair) = inner_pairs.peek() {
                         if pair.as_rule() == Rule::wildcard {
@@ -70,9 +110,7 @@ impl TryFrom<Pairs<'_, Rule>> for Object {
                         }
                     }
 
-                    fields = inner_pairs
-                        .map(|pair| ObjectField::try_from(pair))
-                        .collect::<Result<Vec<ObjectField>, ObjectFieldError>>()?;
+                    add_fields(&mut fields, pair, |field| ObjectField::try_from(field))?;
                 }
                 Rule::object_id => {
                     if let Some(id) = id.as_mut() {
@@ -81,12 +119,22 @@ impl TryFrom<Pairs<'_, Rule>> for Object {
                         id = Some(vec![ObjectID::from_str(pair.as_str())?]);
                     }
                 }
-                Rule::object_filter_list => {
-                    filter = Some(
-                        pair.into_inner()
-                            .map(|pair| ObjectFilter::try_from(pair))
-                            .collect::<Result<Vec<ObjectFilter>, ObjectFilterError>>()?,
-                    );
+                Rule::object_filter => {
+                    let inner = pair.into_inner().next().unwrap();
+                    if inner.as_rule() == Rule::expr_filter {
+                        add_fields(&mut fields, inner, |field| ObjectField::try_from(field))?;
+                        continue;
+                    }
+                    if inner.as_rule() == Rule::in_filter {
+                        in_filters.push(InFilter::try_from(inner)?);
+                        continue;
+                    }
+                    filter
+                        .get_or_insert_with(Vec::new)
+                        .push(ObjectFilter::try_from(inner)?);
+                }
+                Rule::variable => {
+                    in_filters.push(InFilter::variable(Object::IN_COLUMNS[0], pair));
                 }
                 _ => {
                     return Err(ObjectError::UnexpectedToken(pair.as_str().to_string()));
@@ -94,7 +142,35 @@ impl TryFrom<Pairs<'_, Rule>> for Object {
             }
         }
 
-        Ok(Object { id, filter, fields })
+        let mut object = Object { id, filter, fields };
+        for in_filter in in_filters {
+            object.add_in_filter(in_filter)?;
+        }
+
+        Ok(object)
+    }
+}
+
+impl InFilterTarget for Object {
+    const IN_COLUMNS: &'static [&'static str] = &["object", "object_id"];
+
+    fn apply_in(&mut self, column: &str, values: &[String]) -> Result<(), InFilterError> {
+        let ids = parse_in_values(column, values, ObjectID::from_str)?;
+        apply_in_ids(&mut self.id, ids);
+        Ok(())
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        self.filter
+            .get_or_insert_with(Vec::new)
+            .push(ObjectFilter::Subquery(subquery));
+    }
+
+    fn take_subqueries(&mut self) -> Vec<Subquery> {
+        take_subquery_filters(&mut self.filter, |f| match f {
+            ObjectFilter::Subquery(subquery) => Some(subquery),
+            _ => None,
+        })
     }
 }
 
@@ -107,9 +183,19 @@ pub enum ObjectFilterError {
     ObjectParseError(#[from] ObjectIDParseError),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum ObjectFilter {
     ObjectId(ObjectID),
+    Subquery(Subquery),
+}
+
+impl Display for ObjectFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectFilter::ObjectId(id) => write!(f, "object = {id}"),
+            ObjectFilter::Subquery(subquery) => write!(f, "{subquery}"),
+        }
+    }
 }
 
 impl TryFrom<Pair<'_, Rule>> for ObjectFilter {
@@ -117,8 +203,9 @@ impl TryFrom<Pair<'_, Rule>> for ObjectFilter {
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
-            Rule::object_filter_template => {
-                let address = ObjectID::from_str(pair.as_str())?;
+            Rule::object_filter_type => {
+                let address = pair.into_inner().last().map_or("", |p| p.as_str());
+                let address = ObjectID::from_str(address.trim())?;
                 Ok(ObjectFilter::ObjectId(address))
             }
             _ => {
@@ -141,7 +228,7 @@ pub enum ObjectField {
     Chain,
 }
 
-impl std::fmt::Display for ObjectField {
+impl Display for ObjectField {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             ObjectField::ObjectId => "object_id",
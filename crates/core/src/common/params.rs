@@ -0,0 +1,419 @@
+use super::literal::literal_end;
+use crate::interpreter::frontend::{
+    diagnostic::Diagnostic,
+    parser::{Parser, Rule},
+};
+use pest::Parser as _;
+use std::{collections::HashMap, ops::Deref, str::FromStr};
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ParamsError {
+    #[error("Invalid parameter {0}, expected key=value")]
+    InvalidParam(String),
+
+    #[error("Missing value for parameter ${0}")]
+    MissingPositionalParam(String),
+}
+
+/// Values bound to the `$1` and `:name` placeholders of a program. Positional placeholders are
+/// bound by their index, `$1` is the key `1`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Params(HashMap<String, String>);
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.get(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Replaces the placeholders in `source` with their values. `$1` placeholders must be
+    /// bound, while `:name` is only replaced when `name` is bound, so move types and
+    /// checkpoint ranges are left as they are. `$name` is a `LET` variable and is kept.
+    /// Comments and quoted strings are copied without replacing anything.
+    ///
+    /// A value is always a single literal: numbers, addresses, names, tags, checkpoint ranges
+    /// and timestamps are pasted as they are, anything else is bound as a quoted string, so a
+    /// value can't add filters or statements to the program.
+    pub fn bind<'a>(&self, source: &'a str) -> Result<Bound<'a>, ParamsError> {
+        let mut bound = Bound {
+            source,
+            text: String::with_capacity(source.len()),
+            values: vec![],
+        };
+        let mut chars = source.char_indices().peekable();
+        let mut previous: Option<char> = None;
+
+        while let Some((i, c)) = chars.next() {
+            if let Some(end) = literal_end(source, i) {
+                bound.text.push_str(&source[i..end]);
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+                previous = source[..end].chars().last();
+                continue;
+            }
+
+            let placeholder = match c {
+                '$' => take_while(source, i + 1, |c| c.is_ascii_digit()),
+                ':' if previous.map_or(true, |p| p.is_whitespace() || "(,=".contains(p)) => {
+                    take_while(source, i + 1, |c| c.is_ascii_alphanumeric() || c == '_')
+                        .filter(|name| !name.starts_with(|c: char| c.is_ascii_digit()))
+                }
+                _ => None,
+            };
+
+            let value = match (c, placeholder) {
+                ('$', Some(index)) => Some(
+                    self.get(index)
+                        .ok_or_else(|| ParamsError::MissingPositionalParam(index.to_string()))?,
+                ),
+                (':', Some(name)) => self.get(name),
+                _ => None,
+            };
+
+            match (value, placeholder) {
+                (Some(value), Some(placeholder)) => {
+                    let literal = to_literal(value);
+                    bound.values.push(BoundValue {
+                        start: bound.text.len(),
+                        len: literal.len(),
+                        source_start: i,
+                        source_len: placeholder.len() + 1,
+                    });
+                    bound.text.push_str(&literal);
+                    for _ in 0..placeholder.len() {
+                        chars.next();
+                    }
+                    previous = literal.chars().last();
+                }
+                _ => {
+                    bound.text.push(c);
+                    previous = Some(c);
+                }
+            }
+        }
+
+        Ok(bound)
+    }
+}
+
+/// A program with its placeholders bound to their values. It remembers where every value
+/// was bound, so problems found in it point at the program that was written.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bound<'a> {
+    source: &'a str,
+    text: String,
+    values: Vec<BoundValue>,
+}
+
+/// A value bound at byte `start` of the bound program, in place of the placeholder at byte
+/// `source_start` of the original one.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct BoundValue {
+    start: usize,
+    len: usize,
+    source_start: usize,
+    source_len: usize,
+}
+
+impl Bound<'_> {
+    /// Points `diagnostic`, found in the bound program, at the original one. A problem in a
+    /// bound value points at its placeholder.
+    pub fn locate(&self, diagnostic: Diagnostic) -> Diagnostic {
+        if self.values.is_empty() {
+            return diagnostic;
+        }
+
+        let line_start = match diagnostic.line {
+            1 => 0,
+            line => self
+                .text
+                .match_indices('\n')
+                .nth(line - 2)
+                .map_or(self.text.len(), |(i, _)| i + 1),
+        };
+        let start = char_offset(&self.text, line_start, diagnostic.column - 1);
+        let end = char_offset(&self.text, start, diagnostic.length);
+        let source_start = self.source_offset(start, false);
+        let source_end = self.source_offset(end, true).max(source_start);
+
+        Diagnostic {
+            expected: diagnostic.expected,
+            suggestion: diagnostic.suggestion,
+            ..Diagnostic::new(
+                self.source,
+                source_start,
+                source_end - source_start,
+                diagnostic.message,
+            )
+        }
+    }
+
+    /// The byte of the original program matching byte `offset` of the bound one. Offsets
+    /// inside a value are moved to the start of its placeholder, or to its end with `end`.
+    fn source_offset(&self, offset: usize, end: bool) -> usize {
+        let mut shift = 0isize;
+        for value in &self.values {
+            if offset <= value.start {
+                break;
+            }
+            if offset < value.start + value.len {
+                return match end {
+                    true => value.source_start + value.source_len,
+                    false => value.source_start,
+                };
+            }
+            shift = (value.source_start + value.source_len) as isize
+                - (value.start + value.len) as isize;
+        }
+        offset.saturating_add_signed(shift)
+    }
+}
+
+impl Deref for Bound<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl PartialEq<&str> for Bound<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+impl PartialEq<String> for Bound<'_> {
+    fn eq(&self, other: &String) -> bool {
+        &self.text == other
+    }
+}
+
+/// The literal a value is bound as. A value that is a single value of the grammar is kept as
+/// it is, anything else is quoted.
+fn to_literal(value: &str) -> String {
+    match is_value(value) {
+        true => value.to_string(),
+        false => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''")),
+    }
+}
+
+/// Whether `value` is written as a single value: a number, negative ones included, an
+/// address, a name or a move tag, a checkpoint or checkpoint range like `latest-100:latest`,
+/// or a timestamp like `now() - 1h`.
+fn is_value(value: &str) -> bool {
+    let is_token = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.:".contains(c));
+    let number = value.strip_prefix('-').unwrap_or(value);
+
+    is_token
+        || parses_as(Rule::number_literal, number)
+        || parses_as(Rule::checkpoint_id, value)
+        || parses_as(Rule::relative_timestamp, value)
+}
+
+/// Whether the whole of `value` is a `rule` of the grammar.
+fn parses_as(rule: Rule, value: &str) -> bool {
+    Parser::parse(rule, value).is_ok_and(|pairs| {
+        pairs
+            .last()
+            .is_some_and(|pair| pair.as_span().end() == value.len())
+    })
+}
+
+/// The byte `chars` characters after byte `start` of `text`.
+fn char_offset(text: &str, start: usize, chars: usize) -> usize {
+    let start = start.min(text.len());
+    text[start..]
+        .char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(i, _)| start + i)
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Params {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Parses a `key=value` parameter, as given to `--param`.
+pub fn parse_param(param: &str) -> Result<(String, String), ParamsError> {
+    match param.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            let key = key.trim().trim_start_matches(['$', ':']);
+            Ok((key.to_string(), value.trim().to_string()))
+        }
+        _ => Err(ParamsError::InvalidParam(param.to_string())),
+    }
+}
+
+impl FromStr for Params {
+    type Err = ParamsError;
+
+    /// Parses a comma separated list of `key=value` parameters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|param| !param.trim().is_empty())
+            .map(parse_param)
+            .collect()
+    }
+}
+
+/// The non-empty run of characters matching `f` starting at byte `start`.
+fn take_while(source: &str, start: usize, f: impl Fn(char) -> bool) -> Option<&str> {
+    let rest = &source[start..];
+    let end = rest.find(|c| !f(c)).unwrap_or(rest.len());
+    (end > 0).then(|| &rest[..end])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bind_params() {
+        let params: Params = [("1", "0x2"), ("checkpoint", "500")].into_iter().collect();
+
+        assert_eq!(
+            params
+                .bind("SELECT * FROM account $1 ON sui_mainnet")
+                .unwrap(),
+            "SELECT * FROM account 0x2 ON sui_mainnet"
+        );
+        assert_eq!(
+            params
+                .bind("SELECT * FROM tx WHERE checkpoint = :checkpoint ON sui_mainnet")
+                .unwrap(),
+            "SELECT * FROM tx WHERE checkpoint = 500 ON sui_mainnet"
+        );
+        assert_eq!(
+            params
+                .bind("SELECT * FROM coin 0x2::sui::SUI, 0x2::coin::Coin ON http://localhost:9000")
+                .unwrap(),
+            "SELECT * FROM coin 0x2::sui::SUI, 0x2::coin::Coin ON http://localhost:9000"
+        );
+        assert_eq!(
+            params.bind("SELECT * FROM account $whales").unwrap(),
+            "SELECT * FROM account $whales"
+        );
+        assert_eq!(
+            params.bind("SELECT * FROM account $2"),
+            Err(ParamsError::MissingPositionalParam("2".to_string()))
+        );
+
+        let source = "-- costs $2\nSELECT 'a :checkpoint', \"b \\\" $2\" FROM tx /* $2 */ \
+                      WHERE checkpoint = :checkpoint";
+        assert_eq!(
+            params.bind(source).unwrap(),
+            source.replace("= :checkpoint", "= 500")
+        );
+    }
+
+    #[test]
+    fn test_bind_values_as_literals() {
+        let params: Params = [
+            ("1", "0x2 OR sender = 0x3"),
+            ("2", "it's \\ done"),
+            ("checkpoint", "1:100"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            params
+                .bind("SELECT * FROM tx WHERE sender = $1; SELECT * FROM name $2")
+                .unwrap(),
+            "SELECT * FROM tx WHERE sender = '0x2 OR sender = 0x3'; \
+             SELECT * FROM name 'it''s \\\\ done'"
+        );
+        assert_eq!(
+            params.bind("SELECT * FROM checkpoint :checkpoint").unwrap(),
+            "SELECT * FROM checkpoint 1:100"
+        );
+    }
+
+    #[test]
+    fn test_bind_ranges_timestamps_and_negative_numbers() {
+        let params: Params = [
+            ("range", "latest-100:latest"),
+            ("since", "now() - 1h"),
+            ("min", "-5"),
+            ("ratio", "-1.5"),
+            ("day", "2024-01-01T00:00:00Z"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            params.bind("SELECT * FROM checkpoint :range").unwrap(),
+            "SELECT * FROM checkpoint latest-100:latest"
+        );
+        assert_eq!(
+            params
+                .bind("SELECT * FROM tx WHERE timestamp > :since, timestamp < :day")
+                .unwrap(),
+            "SELECT * FROM tx WHERE timestamp > now() - 1h, timestamp < '2024-01-01T00:00:00Z'"
+        );
+        assert_eq!(
+            params
+                .bind("SELECT * FROM tx WHERE checkpoint = 1, gas_used > :min * :ratio")
+                .unwrap(),
+            "SELECT * FROM tx WHERE checkpoint = 1, gas_used > -5 * -1.5"
+        );
+
+        // Values that only start like one are still quoted.
+        for value in [
+            "now() - 1h, sender = 0x3",
+            "latest-100:latest OR 1",
+            "-5 OR 1",
+        ] {
+            assert_eq!(to_literal(value), format!("'{value}'"));
+        }
+    }
+
+    #[test]
+    fn test_locate_diagnostics() {
+        let params: Params = [("1", "a b"), ("epoch", "10")].into_iter().collect();
+        let source = "SELECT * FROM tx\nWHERE epoch = :epoch AND sender = $1 AND gas = 1";
+        let bound = params.bind(source).unwrap();
+
+        let at = |word: &str| {
+            let start = bound.find(word).unwrap();
+            Diagnostic::new(&bound, start, word.len(), "problem")
+        };
+
+        let gas = bound.locate(at("gas"));
+        assert_eq!((gas.line, gas.column, gas.length), (2, 42, 3));
+        assert_eq!(gas.snippet, source.lines().nth(1).unwrap());
+
+        let value = bound.locate(at("'a b'"));
+        assert_eq!((value.line, value.column, value.length), (2, 35, 2));
+
+        let where_ = bound.locate(at("WHERE"));
+        assert_eq!((where_.line, where_.column, where_.length), (2, 1, 5));
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let params = Params::from_str("addr=0x2, :epoch = 10").unwrap();
+        assert_eq!(params.get("addr"), Some(&"0x2".to_string()));
+        assert_eq!(params.get("epoch"), Some(&"10".to_string()));
+        assert!(parse_param("addr").is_err());
+    }
+}
@@ -2,8 +2,8 @@ use std::any::TypeId;
 
 use crate::common::chain::Chain;
 //use alloy::primitives::{Address, Bloom, Bytes, FixedBytes, B256, U256};
-use serde::ser::SerializeStruct;
-use serde::{Deserialize, Serialize, Serializer};
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sui_types::base_types::{ObjectType, SequenceNumber, SuiAddress};
 use sui_types::digests::TransactionDigest;
 
@@ -30,10 +30,15 @@ pub enum ExpressionResult {
     Coin(Vec<CoinQueryRes>),
     #[serde(rename = "object")]
     Object(Vec<ObjectQueryRes>),
+    #[serde(rename = "name")]
+    Name(Vec<NameQueryRes>),
+    #[serde(rename = "join")]
+    Join(Vec<RowQueryRes>),
+    #[serde(rename = "rows")]
+    Rows(Vec<RowQueryRes>),
 }
 
 // TODO: should this be replaced with Alloy's Block?
-#[serde_with::skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct CheckpointQueryRes {
     pub chain: Option<Chain>,
@@ -49,6 +54,21 @@ pub struct CheckpointQueryRes {
     pub non_refundable_storage_fee: Option<u64>,
     pub network_total_transactions: Option<u64>,
     pub validator_signature: Option<String>,
+    #[serde(
+        serialize_with = "serialize_list",
+        deserialize_with = "deserialize_list",
+        default
+    )]
+    pub transaction_digests: Option<Vec<String>>,
+    pub transaction_digest: Option<String>,
+    pub end_of_epoch: Option<bool>,
+    pub next_epoch_protocol_version: Option<u64>,
+    #[serde(
+        serialize_with = "serialize_list",
+        deserialize_with = "deserialize_list",
+        default
+    )]
+    pub checkpoint_commitments: Option<Vec<String>>,
 }
 
 impl Default for CheckpointQueryRes {
@@ -67,11 +87,41 @@ impl Default for CheckpointQueryRes {
             previous_digest: None,
             network_total_transactions: None,
             validator_signature: None,
+            transaction_digests: None,
+            transaction_digest: None,
+            end_of_epoch: None,
+            next_epoch_protocol_version: None,
+            checkpoint_commitments: None,
         }
     }
 }
 
-#[serde_with::skip_serializing_none]
+/// List columns are written as a single comma-separated cell, so that they fit in tables, CSV
+/// and Parquet dumps like any other column. Subqueries split the cell back into its items, so
+/// items hold no commas outside of generic type arguments.
+fn serialize_list<S>(list: &Option<Vec<String>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match list {
+        Some(list) => serializer.serialize_str(&list.join(",")),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let list = Option::<String>::deserialize(deserializer)?;
+    Ok(list.map(|list| {
+        list.split(',')
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+    }))
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct AccountQueryRes {
     pub chain: Option<Chain>,
@@ -95,7 +145,6 @@ impl Default for AccountQueryRes {
     }
 }
 
-#[serde_with::skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct CoinQueryRes {
     pub chain: Option<Chain>,
@@ -105,6 +154,8 @@ pub struct CoinQueryRes {
     pub description: Option<String>, // "Stable coin."
     pub decimals: Option<u8>,        // 9
     pub icon_url: Option<String>,    // Might be null or Some(url)
+    pub total_supply: Option<u64>,
+    pub treasury_cap: Option<String>, // TreasuryCap<T> object id
 }
 
 impl Default for CoinQueryRes {
@@ -117,11 +168,86 @@ impl Default for CoinQueryRes {
             decimals: None,
             icon_url: None,
             chain: None,
+            total_supply: None,
+            treasury_cap: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct NameQueryRes {
+    pub chain: Option<Chain>,
+    pub name: Option<String>,        // "example.sui"
+    pub address: Option<SuiAddress>, // Address the name resolves to
+    pub expiration: Option<u64>,     // Expiration timestamp in ms
+    pub target: Option<SuiAddress>,  // Target address of the name record
+    pub avatar: Option<String>,      // Avatar NFT id
+}
+
+impl Default for NameQueryRes {
+    fn default() -> Self {
+        Self {
+            chain: None,
+            name: None,
+            address: None,
+            expiration: None,
+            target: None,
+            avatar: None,
+        }
+    }
+}
+
+/// A row of a join or of a query with computed columns, holding the selected columns in the
+/// order they were selected. Columns without a value are kept as `null`, so every row has the
+/// same columns.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RowQueryRes {
+    pub columns: Vec<(String, serde_json::Value)>,
+}
+
+impl RowQueryRes {
+    pub fn header(&self) -> Vec<&str> {
+        self.columns.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// The column values as they are displayed in tables and CSV files.
+    pub fn values(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|(_, value)| match value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl Serialize for RowQueryRes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_map(Some(self.columns.len()))?;
+        for (name, value) in &self.columns {
+            state.serialize_entry(name, value)?;
         }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RowQueryRes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let columns = serde_json::Map::deserialize(deserializer)?;
+        Ok(Self {
+            columns: columns.into_iter().collect(),
+        })
     }
 }
 
-#[serde_with::skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct EventQueryRes {
     pub chain: Option<Chain>,
@@ -151,7 +277,6 @@ impl Default for EventQueryRes {
     }
 }
 
-#[serde_with::skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct ObjectQueryRes {
     pub chain: Option<Chain>,
@@ -177,7 +302,6 @@ impl Default for ObjectQueryRes {
     }
 }
 
-#[serde_with::skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Deserialize, Clone)]
 pub struct TransactionQueryRes {
     pub chain: Option<Chain>,
@@ -197,6 +321,7 @@ pub struct TransactionQueryRes {
     pub status: Option<bool>,
     pub timestamp_ms: Option<u64>,
     pub total_events: Option<usize>,
+    pub changed_objects: Option<Vec<String>>,
 }
 
 impl Default for TransactionQueryRes {
@@ -219,6 +344,7 @@ impl Default for TransactionQueryRes {
             total_events: None,
             executed_epoch: None,
             recepient: None,
+            changed_objects: None,
         }
     }
 }
@@ -242,65 +368,57 @@ impl TransactionQueryRes {
             || self.total_events.is_some()
             || self.recepient.is_some()
             || self.executed_epoch.is_some()
+            || self.changed_objects.is_some()
     }
 
+    /// The values of the fields, written as strings. Fields without a value are skipped.
     pub fn get_field_values(&self) -> Vec<(&'static str, String)> {
-        let mut fields = Vec::new();
-        if let Some(chain) = &self.chain {
-            fields.push(("chain", chain.to_string()));
-        }
-        if let Some(r#kind) = &self.r#kind {
-            fields.push(("kind", r#kind.to_string()));
-        }
-        if let Some(digest) = &self.digest {
-            fields.push(("digest", format!("{digest:?}")));
-        }
-        if let Some(sender) = &self.sender {
-            fields.push(("sender", sender.to_string()));
-        }
-        if let Some(to) = &self.to {
-            fields.push(("to", to.to_string()));
-        }
-        if let Some(computation_cost) = self.computation_cost {
-            fields.push(("computation_cost", computation_cost.to_string()));
-        }
-        if let Some(storage_cost) = self.storage_cost {
-            fields.push(("storage_cost", storage_cost.to_string()));
-        }
-        if let Some(storage_rebate) = self.storage_rebate {
-            fields.push(("storage_rebate", storage_rebate.to_string()));
-        }
-        if let Some(gas_price) = self.gas_price {
-            fields.push(("gas_price", gas_price.to_string()));
-        }
-        if let Some(gas_budget) = self.gas_budget {
-            fields.push(("gas_budget", gas_budget.to_string()));
-        }
-        if let Some(gas_used) = self.gas_used {
-            fields.push(("gas_used", gas_used.to_string()));
-        }
-        if let Some(checkpoint) = self.checkpoint {
-            fields.push(("checkpoint", checkpoint.to_string()));
-        }
-        if let Some(status) = self.status {
-            fields.push(("status", status.to_string()));
-        }
-        if let Some(timestamp_ms) = self.timestamp_ms {
-            fields.push(("timestamp_ms", timestamp_ms.to_string()));
-        }
-        if let Some(total_events) = self.total_events {
-            fields.push(("total_events", total_events.to_string()));
+        self.fields()
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect()
+    }
+
+    /// Every field the resolver fetches, with its value written as a string, if it has one.
+    fn fields(&self) -> Vec<(&'static str, Option<String>)> {
+        fn string<T: ToString>(value: &Option<T>) -> Option<String> {
+            value.as_ref().map(ToString::to_string)
         }
-        fields
+
+        vec![
+            ("chain", string(&self.chain)),
+            ("kind", string(&self.r#kind)),
+            ("digest", string(&self.digest)),
+            ("sender", string(&self.sender)),
+            ("to", string(&self.to)),
+            ("executed_epoch", string(&self.executed_epoch)),
+            ("computation_cost", string(&self.computation_cost)),
+            ("storage_cost", string(&self.storage_cost)),
+            ("storage_rebate", string(&self.storage_rebate)),
+            ("gas_price", string(&self.gas_price)),
+            ("gas_budget", string(&self.gas_budget)),
+            ("gas_used", string(&self.gas_used)),
+            ("checkpoint", string(&self.checkpoint)),
+            ("status", string(&self.status)),
+            ("timestamp_ms", string(&self.timestamp_ms)),
+            ("total_events", string(&self.total_events)),
+            (
+                "changed_objects",
+                self.changed_objects
+                    .as_ref()
+                    .map(|objects| objects.join(",")),
+            ),
+        ]
     }
 }
 
+/// Fields without a value are written as `null`, so every transaction has the same fields.
 impl Serialize for TransactionQueryRes {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let fields = self.get_field_values();
+        let fields = self.fields();
         let mut state = serializer.serialize_struct("TransactionQueryRes", fields.len())?;
         for (field_name, value) in fields {
             state.serialize_field(field_name, &value)?;
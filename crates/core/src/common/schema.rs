@@ -0,0 +1,547 @@
+use super::{
+    account::AccountField, chain::Chain, checkpoint::CheckpointField, coin::CoinField,
+    config::Config, join::JoinEntity, name::NameField, object::ObjectField,
+    query_result::RowQueryRes, transaction::TransactionField,
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::fmt::Display;
+
+/// The type of the values of a field.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FieldType {
+    Number,
+    Boolean,
+    Text,
+    List,
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::Text => "text",
+            FieldType::List => "list",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The operators a filter accepts, besides `IN`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FilterOperators {
+    /// `=`
+    Eq,
+    /// `=` and `!=`
+    Equality,
+    /// `=`, `>`, `>=`, `<` and `<=`
+    Range,
+    /// `=`, `!=`, `>`, `>=`, `<` and `<=`
+    All,
+}
+
+impl FilterOperators {
+    pub fn symbols(&self) -> &'static [&'static str] {
+        match self {
+            FilterOperators::Eq => &["="],
+            FilterOperators::Equality => &["=", "!="],
+            FilterOperators::Range => &["=", ">", ">=", "<", "<="],
+            FilterOperators::All => &["=", "!=", ">", ">=", "<", "<="],
+        }
+    }
+}
+
+/// A filter of the `WHERE` clause of an entity, as the grammar defines it. Fields without
+/// one can still be filtered with expressions, which are evaluated on the fetched rows.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FilterDefinition {
+    /// The names the filter is written with, e.g. `timestamp_ms` or `timestamp`.
+    pub names: &'static [&'static str],
+    /// The field the filter checks, `None` when the field can't be selected.
+    pub field: Option<&'static str>,
+    pub operators: FilterOperators,
+    /// The values the filter takes, in plain words.
+    pub value: &'static str,
+}
+
+/// The filters of a selectable field, see [`JoinEntity::field_filters`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldFilters<'a> {
+    pub names: Vec<&'a str>,
+    pub operators: Vec<&'static str>,
+    pub value: Option<&'static str>,
+}
+
+const fn filter(
+    names: &'static [&'static str],
+    field: Option<&'static str>,
+    operators: FilterOperators,
+    value: &'static str,
+) -> FilterDefinition {
+    FilterDefinition {
+        names,
+        field,
+        operators,
+        value,
+    }
+}
+
+const ACCOUNT_FILTERS: &[FilterDefinition] = &[filter(
+    &["address"],
+    Some("address"),
+    FilterOperators::Equality,
+    "address",
+)];
+
+const CHECKPOINT_FILTERS: &[FilterDefinition] = &[
+    filter(
+        &["checkpoint"],
+        Some("number"),
+        FilterOperators::Range,
+        "checkpoint number, range or tag",
+    ),
+    filter(
+        &["timestamp", "timestamp_ms"],
+        Some("timestamp"),
+        FilterOperators::All,
+        "timestamp",
+    ),
+    filter(&["epoch"], Some("epoch"), FilterOperators::All, "integer"),
+    filter(
+        &["transactions"],
+        Some("transactions"),
+        FilterOperators::All,
+        "integer",
+    ),
+    filter(
+        &["computation_cost"],
+        Some("computation_cost"),
+        FilterOperators::All,
+        "integer",
+    ),
+    filter(
+        &["storage_cost"],
+        Some("storage_cost"),
+        FilterOperators::All,
+        "integer",
+    ),
+    filter(
+        &["storage_rebate"],
+        Some("storage_rebate"),
+        FilterOperators::All,
+        "integer",
+    ),
+    filter(
+        &["non_refundable_storage_fee"],
+        Some("non_refundable_storage_fee"),
+        FilterOperators::All,
+        "integer",
+    ),
+    filter(
+        &["network_total_transactions"],
+        Some("network_total_transactions"),
+        FilterOperators::All,
+        "integer",
+    ),
+];
+
+const TRANSACTION_FILTERS: &[FilterDefinition] = &[
+    filter(&["type"], Some("type"), FilterOperators::Equality, "number"),
+    filter(
+        &["sender"],
+        Some("sender"),
+        FilterOperators::Equality,
+        "address",
+    ),
+    filter(
+        &["recipient"],
+        Some("recipient"),
+        FilterOperators::Equality,
+        "address",
+    ),
+    filter(
+        &["data"],
+        Some("data"),
+        FilterOperators::Equality,
+        "hex string",
+    ),
+    filter(
+        &["gas_budget"],
+        Some("gas_budget"),
+        FilterOperators::All,
+        "number",
+    ),
+    filter(
+        &["gas_price"],
+        Some("gas_price"),
+        FilterOperators::All,
+        "number",
+    ),
+    filter(
+        &["gas_used"],
+        Some("gas_used"),
+        FilterOperators::All,
+        "number",
+    ),
+    filter(
+        &["status"],
+        Some("status"),
+        FilterOperators::Equality,
+        "boolean",
+    ),
+    filter(
+        &["executed_epoch"],
+        Some("executed_epoch"),
+        FilterOperators::All,
+        "number",
+    ),
+    filter(
+        &["checkpoint"],
+        Some("checkpoint"),
+        FilterOperators::Range,
+        "checkpoint number, range or tag",
+    ),
+    filter(
+        &["timestamp_ms", "timestamp"],
+        Some("timestamp_ms"),
+        FilterOperators::All,
+        "timestamp",
+    ),
+];
+
+const COIN_FILTERS: &[FilterDefinition] = &[
+    filter(
+        &["coin_type", "type"],
+        Some("id"),
+        FilterOperators::Equality,
+        "coin type",
+    ),
+    filter(&["owner"], None, FilterOperators::Eq, "address"),
+    filter(
+        &["decimals"],
+        Some("decimals"),
+        FilterOperators::All,
+        "integer",
+    ),
+];
+
+const OBJECT_FILTERS: &[FilterDefinition] = &[filter(
+    &["object"],
+    Some("object_id"),
+    FilterOperators::Equality,
+    "object id",
+)];
+
+const NAME_FILTERS: &[FilterDefinition] = &[
+    filter(
+        &["address"],
+        Some("address"),
+        FilterOperators::Equality,
+        "address",
+    ),
+    filter(
+        &["name"],
+        Some("name"),
+        FilterOperators::Equality,
+        "SuiNS name",
+    ),
+];
+
+impl JoinEntity {
+    pub const ALL: [JoinEntity; 6] = [
+        JoinEntity::Account,
+        JoinEntity::Checkpoint,
+        JoinEntity::Transaction,
+        JoinEntity::Coin,
+        JoinEntity::Object,
+        JoinEntity::Name,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            JoinEntity::Account => "Balances and stakes of addresses or SuiNS names",
+            JoinEntity::Checkpoint => "Checkpoints by number, range, epoch or timestamp",
+            JoinEntity::Transaction => "Transactions by digest, checkpoint or timestamp",
+            JoinEntity::Coin => "Coin metadata by coin type or owner",
+            JoinEntity::Object => "Objects by id",
+            JoinEntity::Name => "SuiNS names by name or address",
+        }
+    }
+
+    /// The fields that can be selected, in the order `DESCRIBE` lists them.
+    pub fn selectable_fields(&self) -> Vec<String> {
+        fn names<T: ToString>(fields: &[T]) -> Vec<String> {
+            fields.iter().map(ToString::to_string).collect()
+        }
+
+        match self {
+            JoinEntity::Account => names(AccountField::all_variants()),
+            JoinEntity::Checkpoint => names(CheckpointField::all_variants()),
+            JoinEntity::Transaction => names(&TransactionField::wildcard_variants()),
+            JoinEntity::Coin => names(CoinField::all_variants()),
+            JoinEntity::Object => names(ObjectField::all_variants()),
+            JoinEntity::Name => names(NameField::all_variants()),
+        }
+    }
+
+    /// The filters of the `WHERE` clause of the entity, besides `IN` and expressions.
+    pub fn filters(&self) -> &'static [FilterDefinition] {
+        match self {
+            JoinEntity::Account => ACCOUNT_FILTERS,
+            JoinEntity::Checkpoint => CHECKPOINT_FILTERS,
+            JoinEntity::Transaction => TRANSACTION_FILTERS,
+            JoinEntity::Coin => COIN_FILTERS,
+            JoinEntity::Object => OBJECT_FILTERS,
+            JoinEntity::Name => NAME_FILTERS,
+        }
+    }
+
+    /// How a selectable field can be filtered: the names of its filters, their operators,
+    /// `IN` included, and the values they take.
+    pub fn field_filters<'a>(&self, field: &'a str) -> FieldFilters<'a> {
+        let filters = self
+            .filters()
+            .iter()
+            .filter(|filter| filter.field == Some(field))
+            .collect::<Vec<_>>();
+        let mut names = filters
+            .iter()
+            .flat_map(|filter| filter.names.iter().copied())
+            .collect::<Vec<_>>();
+        let mut operators = filters
+            .iter()
+            .flat_map(|filter| filter.operators.symbols().iter().copied())
+            .collect::<Vec<_>>();
+        let has_in = std::iter::once(field)
+            .chain(names.iter().copied())
+            .any(|name| self.in_columns().contains(&name));
+        if has_in {
+            if names.is_empty() {
+                names.push(field);
+            }
+            operators.push("IN");
+        }
+
+        FieldFilters {
+            names,
+            operators,
+            value: filters.first().map(|filter| filter.value),
+        }
+    }
+
+    pub fn field_type(&self, field: &str) -> FieldType {
+        match (self, field) {
+            (JoinEntity::Transaction, "status") | (JoinEntity::Checkpoint, "end_of_epoch") => {
+                FieldType::Boolean
+            }
+            (
+                JoinEntity::Transaction,
+                "gas_budget" | "gas_price" | "gas_used" | "executed_epoch" | "checkpoint"
+                | "timestamp_ms" | "total_events",
+            )
+            | (
+                JoinEntity::Checkpoint,
+                "epoch"
+                | "number"
+                | "timestamp"
+                | "transactions"
+                | "network_total_transactions"
+                | "computation_cost"
+                | "storage_cost"
+                | "storage_rebate"
+                | "non_refundable_storage_fee"
+                | "next_epoch_protocol_version",
+            )
+            | (
+                JoinEntity::Account,
+                "sui_balance" | "coin_owned" | "stake_amount" | "active_delegations",
+            )
+            | (JoinEntity::Coin, "decimals" | "total_supply")
+            | (JoinEntity::Name, "expiration")
+            | (JoinEntity::Object, "storage_rebate") => FieldType::Number,
+            (JoinEntity::Checkpoint, "transaction_digests" | "checkpoint_commitments")
+            | (JoinEntity::Transaction, "changed_objects" | "event_digests") => FieldType::List,
+            _ => FieldType::Text,
+        }
+    }
+}
+
+/// `SHOW ENTITIES`: the entities that can be queried.
+pub fn entity_rows() -> Vec<RowQueryRes> {
+    JoinEntity::ALL
+        .iter()
+        .map(|entity| RowQueryRes {
+            columns: vec![
+                ("entity".to_string(), Value::from(entity.to_string())),
+                ("id".to_string(), Value::from(entity.id_field())),
+                (
+                    "fields".to_string(),
+                    Value::from(entity.selectable_fields().len()),
+                ),
+                ("description".to_string(), Value::from(entity.description())),
+            ],
+        })
+        .collect()
+}
+
+/// `DESCRIBE entity`: the fields of an entity, their type and the filters they have, followed
+/// by the filters on values that can't be selected.
+pub fn describe_rows(entity: JoinEntity) -> Vec<RowQueryRes> {
+    let fields = entity.selectable_fields();
+    let mut rows = vec![];
+
+    for field in &fields {
+        let filters = entity.field_filters(field);
+        rows.push(describe_row(
+            field,
+            Some(entity.field_type(field)),
+            true,
+            &filters.names,
+            &filters.operators,
+            filters.value,
+        ));
+    }
+
+    for filter in entity.filters() {
+        if filter.field.is_none() {
+            rows.push(describe_row(
+                filter.names[0],
+                None,
+                false,
+                filter.names,
+                filter.operators.symbols(),
+                Some(filter.value),
+            ));
+        }
+    }
+
+    rows
+}
+
+fn describe_row(
+    field: &str,
+    field_type: Option<FieldType>,
+    selectable: bool,
+    filters: &[&str],
+    operators: &[&str],
+    value: Option<&str>,
+) -> RowQueryRes {
+    let list = |items: &[&str]| match items.is_empty() {
+        true => Value::Null,
+        false => Value::from(items.join(", ")),
+    };
+
+    RowQueryRes {
+        columns: vec![
+            ("field".to_string(), Value::from(field)),
+            (
+                "type".to_string(),
+                field_type.map_or(Value::Null, |field_type| {
+                    Value::from(field_type.to_string())
+                }),
+            ),
+            ("selectable".to_string(), Value::from(selectable)),
+            ("filter".to_string(), list(filters)),
+            ("operators".to_string(), list(operators)),
+            (
+                "filter_value".to_string(),
+                value.map_or(Value::Null, Value::from),
+            ),
+        ],
+    }
+}
+
+/// `SHOW CHAINS`: the public networks and the networks named in the config file, with the
+/// RPCs of the config file, or the public RPC when the config doesn't set one.
+pub fn chain_rows() -> Result<Vec<RowQueryRes>> {
+    let config = Config::load();
+    let networks = config.get_networks()?.into_iter().map(Chain::Custom);
+    let mut rows = vec![];
+
+    for chain in Chain::public_chains().iter().cloned().chain(networks) {
+        let rpcs = config.get_chain_rpcs(&chain)?.unwrap_or_default();
+        let is_configured = config.get_chain_default_rpc(&chain)?.is_some();
+
+        rows.push(RowQueryRes {
+            columns: vec![
+                ("chain".to_string(), Value::from(chain.to_string())),
+                ("rpc".to_string(), Value::from(chain.rpc_url()?.to_string())),
+                (
+                    "rpcs".to_string(),
+                    Value::from(rpcs.iter().map(ToString::to_string).collect::<Vec<_>>()),
+                ),
+                (
+                    "source".to_string(),
+                    Value::from(match is_configured {
+                        true => "config",
+                        false => "default",
+                    }),
+                ),
+            ],
+        });
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interpreter::frontend::parser::{parse_program, Rule};
+
+    fn sample(value: &str) -> &'static str {
+        match value {
+            "address" | "object id" => {
+                "0x0000000000000000000000000000000000000000000000000000000000000005"
+            }
+            "coin type" => "'0x2::sui::SUI'",
+            "SuiNS name" => "'example.sui'",
+            "hex string" => "0x00",
+            "boolean" => "true",
+            _ => "10",
+        }
+    }
+
+    #[test]
+    fn test_filters_match_the_grammar() {
+        for entity in JoinEntity::ALL {
+            for filter in entity.filters() {
+                for name in filter.names {
+                    for operator in filter.operators.symbols() {
+                        let source = format!(
+                            "SELECT * FROM {entity} WHERE {name} {operator} {} ON sui_mainnet",
+                            sample(filter.value)
+                        );
+                        let pairs = parse_program(&source).unwrap();
+                        let is_typed = pairs.flatten().all(|pair| {
+                            !matches!(pair.as_rule(), Rule::expr_filter | Rule::in_filter)
+                        });
+                        assert!(is_typed, "{source}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_describe_rows() {
+        let rows = describe_rows(JoinEntity::Coin);
+        let row = |field: &str| {
+            rows.iter()
+                .find(|row| row.columns[0].1 == field)
+                .unwrap()
+                .columns
+                .clone()
+        };
+
+        assert_eq!(row("decimals")[1].1, Value::from("number"));
+        assert_eq!(row("decimals")[4].1, Value::from("=, !=, >, >=, <, <="));
+        assert_eq!(row("owner")[2].1, Value::from(false));
+        assert_eq!(row("owner")[4].1, Value::from("="));
+        assert_eq!(
+            rows.len(),
+            entity_rows()[3].columns[2].1.as_u64().unwrap() as usize + 1
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use super::{
     dump::{Dump, DumpFormat},
-    query_result::ExpressionResult,
+    query_result::{ExpressionResult, RowQueryRes},
 };
 use arrow::array::{ArrayRef, StringArray};
 use arrow::datatypes::{DataType, Field, Schema};
@@ -14,30 +14,35 @@ use serde::Serialize;
 use csv::WriterBuilder;
 
 pub(crate) fn dump_results(result: &ExpressionResult, dump: &Dump) -> Result<(), Box<dyn Error>> {
-    match dump.format {
-        DumpFormat::Json => {
-            let content = serialize_json(result)?;
-            std::fs::write(dump.path(), content)?;
-        }
-        DumpFormat::Csv => {
-            let content = match result {
-                ExpressionResult::Account(accounts) => serialize_csv(accounts)?,
-                ExpressionResult::Checkpoint(blocks) => serialize_csv(blocks)?,
-                ExpressionResult::Transaction(txs) => serialize_csv(txs)?,
-                ExpressionResult::Coin(coins) => serialize_csv(coins)?,
-                ExpressionResult::Object(object) => serialize_csv(object)?,
-            };
-
-            std::fs::write(dump.path(), content)?;
-        }
-        DumpFormat::Parquet => {
-            let content = serialize_parquet(result)?;
-            std::fs::write(dump.path(), content)?;
-        }
-    }
+    let content = serialize_result(result, &dump.format)?;
+    std::fs::write(dump.path(), content)?;
     Ok(())
 }
 
+/// The content of a result written in `format`, as `>>` dumps it.
+pub fn serialize_result(
+    result: &ExpressionResult,
+    format: &DumpFormat,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let content = match format {
+        DumpFormat::Json => serialize_json(result)?.into_bytes(),
+        DumpFormat::Csv => match result {
+            ExpressionResult::Account(accounts) => serialize_csv(accounts)?,
+            ExpressionResult::Checkpoint(blocks) => serialize_csv(blocks)?,
+            ExpressionResult::Transaction(txs) => serialize_csv(txs)?,
+            ExpressionResult::Coin(coins) => serialize_csv(coins)?,
+            ExpressionResult::Object(object) => serialize_csv(object)?,
+            ExpressionResult::Name(names) => serialize_csv(names)?,
+            ExpressionResult::Join(rows) | ExpressionResult::Rows(rows) => {
+                serialize_rows_csv(rows)?
+            }
+        }
+        .into_bytes(),
+        DumpFormat::Parquet => serialize_parquet(result)?,
+    };
+    Ok(content)
+}
+
 fn serialize_json<T: Serialize>(result: &T) -> Result<String, Box<dyn Error>> {
     Ok(serde_json::to_string_pretty(result)?)
 }
@@ -52,6 +57,20 @@ fn serialize_csv<T: Serialize>(results: &Vec<T>) -> Result<String, Box<dyn Error
     Ok(String::from_utf8(writer.into_inner()?)?)
 }
 
+/// Join and computed rows have dynamic columns, which the CSV serializer can't derive from the row type.
+fn serialize_rows_csv(rows: &[RowQueryRes]) -> Result<String, Box<dyn Error>> {
+    let mut writer = WriterBuilder::new().from_writer(vec![]);
+
+    if let Some(first_row) = rows.first() {
+        writer.write_record(first_row.header())?;
+    }
+    for row in rows {
+        writer.write_record(row.values())?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
 fn serialize_parquet(result: &ExpressionResult) -> Result<Vec<u8>, Box<dyn Error>> {
     let (schema, data) = match result {
         ExpressionResult::Account(accounts) => create_parquet_schema_and_data(accounts)?,
@@ -61,6 +80,10 @@ fn serialize_parquet(result: &ExpressionResult) -> Result<Vec<u8>, Box<dyn Error
         }
         ExpressionResult::Coin(coins) => create_parquet_schema_and_data(coins)?,
         ExpressionResult::Object(objects) => create_parquet_schema_and_data(objects)?,
+        ExpressionResult::Name(names) => create_parquet_schema_and_data(names)?,
+        ExpressionResult::Join(rows) | ExpressionResult::Rows(rows) => {
+            create_rows_parquet_schema_and_data(rows)
+        }
     };
 
     let batch = RecordBatch::try_new(Arc::new(schema), data)?;
@@ -108,10 +131,40 @@ fn create_parquet_schema_and_data<T: Serialize>(
     Ok((schema, data))
 }
 
+fn create_rows_parquet_schema_and_data(rows: &[RowQueryRes]) -> (Schema, Vec<ArrayRef>) {
+    let header = rows.first().map(|row| row.header()).unwrap_or_default();
+    let fields = header
+        .iter()
+        .map(|name| Field::new(*name, DataType::Utf8, true))
+        .collect::<Vec<_>>();
+    let values = rows.iter().map(|row| row.values()).collect::<Vec<_>>();
+    let data = (0..header.len())
+        .map(|index| {
+            let column_data: Vec<Option<String>> = rows
+                .iter()
+                .zip(&values)
+                .map(|(row, values)| match row.columns[index].1 {
+                    serde_json::Value::Null => None,
+                    _ => Some(values[index].clone()),
+                })
+                .collect();
+            Arc::new(StringArray::from(column_data)) as ArrayRef
+        })
+        .collect();
+
+    (Schema::new(fields), data)
+}
+
 #[cfg(test)]
 mod test {
-    use super::{serialize_csv, serialize_json, serialize_parquet};
-    use crate::common::query_result::{AccountQueryRes, ExpressionResult};
+    use super::{serialize_csv, serialize_json, serialize_parquet, serialize_result};
+    use crate::common::{
+        dump::DumpFormat,
+        expression::Projection,
+        join::JoinEntity,
+        query_result::{AccountQueryRes, ExpressionResult},
+    };
+    use serde_json::Value;
 
     #[test]
     fn test_serialize_json() {
@@ -126,7 +179,12 @@ mod test {
         let result = ExpressionResult::Account(vec![res]);
         let content = serialize_json(&result).unwrap();
 
-        assert_eq!(content, "{\n  \"account\": [\n    {\n      \"nonce\": 0,\n      \"balance\": \"100\"\n    }\n  ]\n}");
+        assert_eq!(
+            content,
+            "{\n  \"account\": [\n    {\n      \"chain\": null,\n      \"sui_balance\": 1000,\n      \
+             \"coin_owned\": null,\n      \"staked_amount\": 0,\n      \"address\": null,\n      \
+             \"active_delegations\": null\n    }\n  ]\n}"
+        );
     }
 
     #[test]
@@ -151,7 +209,43 @@ mod test {
         ];
         let content = serialize_csv(&res).unwrap();
 
-        assert_eq!(content, "sui_balance,stake_amount\n1000,0\n1000,0\n");
+        assert_eq!(
+            content,
+            "chain,sui_balance,coin_owned,staked_amount,address,active_delegations\n\
+             ,1000,,0,,\n,1000,,0,,\n"
+        );
+    }
+
+    #[test]
+    fn test_serialize_rows_csv_with_nulls() {
+        let accounts = [
+            AccountQueryRes {
+                sui_balance: Some(1000),
+                staked_amount: Some(0),
+                ..Default::default()
+            },
+            AccountQueryRes {
+                active_delegations: Some(2),
+                ..Default::default()
+            },
+        ];
+        let rows = accounts
+            .iter()
+            .map(|account| match serde_json::to_value(account).unwrap() {
+                Value::Object(row) => row,
+                _ => unreachable!(),
+            })
+            .collect();
+        let rows = Projection::new(JoinEntity::Account, None, vec![])
+            .apply(rows)
+            .unwrap();
+        let content = serialize_result(&ExpressionResult::Rows(rows), &DumpFormat::Csv).unwrap();
+
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "address,sui_balance,coin_owned,stake_amount,active_delegations,chain\n\
+             ,1000,,0,,\n,,,,2,\n"
+        );
     }
 
     #[test]
@@ -0,0 +1,294 @@
+use super::{
+    entity::{Entity, EntityError},
+    expression::{ExpressionError, Projection},
+    literal::unquote,
+};
+use crate::interpreter::frontend::parser::Rule;
+use pest::iterators::Pair;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+#[derive(thiserror::Error, Debug)]
+pub enum InFilterError {
+    #[error("Column {0} can't be used in an IN filter")]
+    UnsupportedColumn(String),
+
+    #[error("Invalid value {1} for column {0} in IN filter")]
+    InvalidValue(String, String),
+
+    #[error("Subqueries should select exactly one column, found {0}")]
+    SubqueryColumns(usize),
+
+    #[error("Missing values in IN filter")]
+    MissingValues,
+
+    #[error("Unknown variable ${0}")]
+    UnknownVariable(String),
+
+    #[error(transparent)]
+    EntityError(Box<EntityError>),
+
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+}
+
+/// The values of an `IN` filter.
+#[derive(Debug, PartialEq, Clone)]
+pub enum InValues {
+    List(Vec<String>),
+    Subquery(Box<Entity>),
+    Variable(String),
+}
+
+/// A `column IN (...)` filter, before it is applied to an entity.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InFilter {
+    pub column: String,
+    pub values: InValues,
+}
+
+impl InFilter {
+    /// A filter on the values of a `LET` variable, as in `SELECT * FROM account $whales`.
+    pub fn variable(column: &str, variable: Pair<'_, Rule>) -> Self {
+        InFilter {
+            column: column.to_string(),
+            values: InValues::Variable(variable_name(variable)),
+        }
+    }
+}
+
+/// The name of a `$variable`, without the `$`.
+fn variable_name(variable: Pair<'_, Rule>) -> String {
+    variable.as_str().trim().trim_start_matches('$').to_string()
+}
+
+impl TryFrom<Pair<'_, Rule>> for InFilter {
+    type Error = InFilterError;
+
+    fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        let mut inner = pair.into_inner();
+        let column = inner
+            .next()
+            .ok_or(InFilterError::MissingValues)?
+            .as_str()
+            .trim()
+            .to_string();
+        let values = inner.next().ok_or(InFilterError::MissingValues)?;
+
+        let values = match values.as_rule() {
+            Rule::in_list => InValues::List(
+                values
+                    .into_inner()
+                    .map(|value| unquote(value.as_str().trim()))
+                    .collect(),
+            ),
+            Rule::subquery => {
+                let entity = values
+                    .into_inner()
+                    .next()
+                    .ok_or(InFilterError::MissingValues)?;
+                Projection::reject(&entity, "subqueries")?;
+                let entity = Entity::try_from(entity.into_inner())
+                    .map_err(|e| InFilterError::EntityError(Box::new(e)))?;
+                let columns = entity.field_count();
+                if columns != 1 {
+                    return Err(InFilterError::SubqueryColumns(columns));
+                }
+                InValues::Subquery(Box::new(entity))
+            }
+            Rule::variable => InValues::Variable(variable_name(values)),
+            _ => return Err(InFilterError::MissingValues),
+        };
+
+        Ok(InFilter { column, values })
+    }
+}
+
+/// A `column IN (SELECT ...)` filter. Subqueries are executed before the query they belong to,
+/// and their values are then applied like an `IN (list)` filter.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Subquery {
+    pub column: String,
+    pub source: SubquerySource,
+}
+
+impl Eq for Subquery {}
+
+/// The query of a subquery: written inline, or bound to a `LET` variable. Variables are
+/// replaced by their query when the program is parsed.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum SubquerySource {
+    Query(Box<Entity>),
+    Variable(String),
+}
+
+impl Subquery {
+    pub fn entity(&self) -> Result<&Entity, InFilterError> {
+        match &self.source {
+            SubquerySource::Query(entity) => Ok(entity),
+            SubquerySource::Variable(name) => Err(InFilterError::UnknownVariable(name.clone())),
+        }
+    }
+}
+
+impl Display for Subquery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            SubquerySource::Query(entity) => write!(f, "{} IN ({})", self.column, entity),
+            SubquerySource::Variable(name) => write!(f, "{} IN (${})", self.column, name),
+        }
+    }
+}
+
+/// Entities that support `column IN (...)` filters.
+pub trait InFilterTarget {
+    /// The columns that can be used in `IN` filters, usually the ones the entity is fetched by.
+    const IN_COLUMNS: &'static [&'static str];
+
+    /// Narrows the entity down to the given values of `column`.
+    fn apply_in(&mut self, column: &str, values: &[String]) -> Result<(), InFilterError>;
+
+    /// Adds a subquery to the entity filters, to be executed before the entity is resolved.
+    fn push_subquery(&mut self, subquery: Subquery);
+
+    /// Removes the subqueries from the entity filters, so they can be executed.
+    fn take_subqueries(&mut self) -> Vec<Subquery>;
+
+    /// Applies an `IN` filter: lists right away, subqueries once they have been executed.
+    fn add_in_filter(&mut self, filter: InFilter) -> Result<(), InFilterError> {
+        if !Self::IN_COLUMNS.contains(&filter.column.as_str()) {
+            return Err(InFilterError::UnsupportedColumn(filter.column));
+        }
+
+        match filter.values {
+            InValues::List(values) => self.apply_in(&filter.column, &values),
+            InValues::Subquery(entity) => {
+                self.push_subquery(Subquery {
+                    column: filter.column,
+                    source: SubquerySource::Query(entity),
+                });
+                Ok(())
+            }
+            InValues::Variable(name) => {
+                self.push_subquery(Subquery {
+                    column: filter.column,
+                    source: SubquerySource::Variable(name),
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Narrows an id list down to `values`. The first `IN` filter sets the ids, the next ones
+/// keep the ids that are in all of them.
+pub fn apply_in_ids<T: PartialEq>(ids: &mut Option<Vec<T>>, values: Vec<T>) {
+    match ids {
+        Some(ids) => ids.retain(|id| values.contains(id)),
+        None => *ids = Some(values),
+    }
+}
+
+/// Removes the subqueries from a filter list, returning them.
+pub fn take_subquery_filters<F>(
+    filters: &mut Option<Vec<F>>,
+    as_subquery: impl Fn(&F) -> Option<&Subquery>,
+) -> Vec<Subquery> {
+    let Some(filters) = filters.as_mut() else {
+        return vec![];
+    };
+    let mut subqueries = vec![];
+    filters.retain(|f| match as_subquery(f) {
+        Some(subquery) => {
+            subqueries.push(subquery.clone());
+            false
+        }
+        None => true,
+    });
+    subqueries
+}
+
+/// Parses the values of an `IN` filter on `column`.
+pub fn parse_in_values<T, E>(
+    column: &str,
+    values: &[String],
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Vec<T>, InFilterError> {
+    values
+        .iter()
+        .map(|value| {
+            parse(value).map_err(|_| InFilterError::InvalidValue(column.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{common::types::Expression, interpreter::frontend::parser::Parser as SuiQLParser};
+
+    fn parse_entity(source: &str) -> anyhow::Result<Entity> {
+        let expressions = SuiQLParser::new(source).parse_expressions()?;
+        match expressions.into_iter().next() {
+            Some(Expression::Get(get)) => Ok(get.entity),
+            Some(expression) => anyhow::bail!("Expected a query, got {:?}", expression),
+            None => anyhow::bail!("Missing expression"),
+        }
+    }
+
+    #[test]
+    fn test_in_list_sets_ids() {
+        let digest = "5PqUjTz5YAcWuVoF9a6iGUnyKmrHKzuS7DTxDEB9V8Qn";
+        let source =
+            format!("SELECT sender FROM tx WHERE digest IN ({digest}, {digest}) ON sui_mainnet");
+
+        match parse_entity(&source).unwrap() {
+            Entity::Transaction(tx) => {
+                assert_eq!(tx.ids().map(Vec::len), Some(2));
+                assert_eq!(tx.filters(), None);
+            }
+            entity => panic!("Expected a transaction, got {:?}", entity),
+        }
+    }
+
+    #[test]
+    fn test_in_subquery_is_deferred() {
+        let source = "SELECT * FROM object WHERE object IN (SELECT changed_objects FROM tx WHERE checkpoint = 500) ON sui_mainnet";
+        let mut entity = parse_entity(source).unwrap();
+
+        let subqueries = entity.take_subqueries();
+        assert_eq!(subqueries.len(), 1);
+        assert_eq!(subqueries[0].column, "object");
+        assert!(matches!(subqueries[0].entity(), Ok(Entity::Transaction(_))));
+        assert!(entity.take_subqueries().is_empty());
+
+        let id = "0x0000000000000000000000000000000000000000000000000000000000000005";
+        entity.apply_in("object", &[id.to_string()]).unwrap();
+        match entity {
+            Entity::Object(object) => assert_eq!(object.ids().map(Vec::len), Some(1)),
+            entity => panic!("Expected an object, got {:?}", entity),
+        }
+    }
+
+    #[test]
+    fn test_in_subquery_selects_one_column() {
+        let source = "SELECT * FROM object WHERE object IN (SELECT changed_objects, sender FROM tx WHERE checkpoint = 500) ON sui_mainnet";
+        assert!(parse_entity(source).is_err());
+        assert!(
+            parse_entity("SELECT sender FROM tx WHERE status IN (true) ON sui_mainnet").is_err()
+        );
+    }
+
+    #[test]
+    fn test_apply_in_ids() {
+        let mut ids = None;
+        apply_in_ids(&mut ids, vec![1, 2, 3]);
+        assert_eq!(ids, Some(vec![1, 2, 3]));
+
+        apply_in_ids(&mut ids, vec![3, 2, 5]);
+        assert_eq!(ids, Some(vec![2, 3]));
+
+        apply_in_ids(&mut ids, vec![]);
+        assert_eq!(ids, Some(vec![]));
+    }
+}
@@ -0,0 +1,263 @@
+use super::filters::{FilterError, FilterType};
+use crate::interpreter::frontend::parser::Rule;
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeZone, Utc};
+use pest::iterators::Pair;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+const SECOND_MS: i64 = 1_000;
+const MINUTE_MS: i64 = 60 * SECOND_MS;
+const HOUR_MS: i64 = 60 * MINUTE_MS;
+const DAY_MS: i64 = 24 * HOUR_MS;
+const WEEK_MS: i64 = 7 * DAY_MS;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TimestampError {
+    #[error("Invalid timestamp {0}")]
+    InvalidTimestamp(String),
+    #[error("Invalid duration {0}")]
+    InvalidDuration(String),
+    #[error("Timestamp {0} is before the unix epoch")]
+    BeforeUnixEpoch(String),
+    #[error("Missing operator in timestamp filter")]
+    MissingOperator,
+    #[error(transparent)]
+    FilterError(#[from] FilterError),
+}
+
+/// A point in time used by `timestamp_ms` filters.
+///
+/// Relative timestamps (`now() - 1h`) are kept unresolved so that they are evaluated when
+/// the query runs, not when it is parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Timestamp {
+    /// Raw unix timestamp in milliseconds, e.g. `1735689600000`
+    Millis(u64),
+    /// ISO-8601 literal, e.g. `'2025-01-01T00:00:00Z'`, stored in milliseconds
+    DateTime(u64),
+    /// Offset in milliseconds from the moment the query is executed, e.g. `now() - 1h`
+    Now(i64),
+}
+
+impl Timestamp {
+    /// Returns the unix timestamp in milliseconds this timestamp points to, or an error when a
+    /// relative timestamp points before the unix epoch.
+    pub fn resolve(&self) -> Result<u64, TimestampError> {
+        match self {
+            Timestamp::Millis(ms) | Timestamp::DateTime(ms) => Ok(*ms),
+            Timestamp::Now(offset) => {
+                let now = Utc::now().timestamp_millis();
+                u64::try_from(now.saturating_add(*offset))
+                    .map_err(|_| TimestampError::BeforeUnixEpoch(self.to_string()))
+            }
+        }
+    }
+
+    fn parse_iso(value: &str) -> Result<u64, TimestampError> {
+        let millis = match DateTime::parse_from_rfc3339(value) {
+            Ok(datetime) => datetime.timestamp_millis(),
+            Err(_) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|datetime| Utc.from_utc_datetime(&datetime).timestamp_millis())
+                .ok_or_else(|| TimestampError::InvalidTimestamp(value.to_string()))?,
+        };
+
+        u64::try_from(millis).map_err(|_| TimestampError::BeforeUnixEpoch(format!("'{value}'")))
+    }
+
+    fn parse_relative(value: &str) -> Result<i64, TimestampError> {
+        let offset = value.trim_start_matches("now()").trim();
+        if offset.is_empty() {
+            return Ok(0);
+        }
+
+        let (sign, duration) = match offset.split_at(1) {
+            ("-", duration) => (-1, duration),
+            ("+", duration) => (1, duration),
+            _ => return Err(TimestampError::InvalidTimestamp(value.to_string())),
+        };
+
+        Ok(sign * parse_duration(duration.trim())?)
+    }
+}
+
+/// Parses durations such as `500ms`, `30s`, `15m`, `1h`, `7d` or `2w` into milliseconds.
+pub fn parse_duration(duration: &str) -> Result<i64, TimestampError> {
+    let split_at = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| TimestampError::InvalidDuration(duration.to_string()))?;
+    let (amount, unit) = duration.split_at(split_at);
+    let amount = amount
+        .parse::<i64>()
+        .map_err(|_| TimestampError::InvalidDuration(duration.to_string()))?;
+
+    let unit_ms = match unit {
+        "ms" => 1,
+        "s" => SECOND_MS,
+        "m" => MINUTE_MS,
+        "h" => HOUR_MS,
+        "d" => DAY_MS,
+        "w" => WEEK_MS,
+        _ => return Err(TimestampError::InvalidDuration(duration.to_string())),
+    };
+
+    amount
+        .checked_mul(unit_ms)
+        .ok_or_else(|| TimestampError::InvalidDuration(duration.to_string()))
+}
+
+fn format_duration(duration_ms: i64) -> String {
+    for (unit, unit_ms) in [
+        ("w", WEEK_MS),
+        ("d", DAY_MS),
+        ("h", HOUR_MS),
+        ("m", MINUTE_MS),
+        ("s", SECOND_MS),
+    ] {
+        if duration_ms % unit_ms == 0 {
+            return format!("{}{}", duration_ms / unit_ms, unit);
+        }
+    }
+    format!("{}ms", duration_ms)
+}
+
+impl FromStr for Timestamp {
+    type Err = TimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.starts_with("now()") {
+            return Ok(Timestamp::Now(Timestamp::parse_relative(s)?));
+        }
+
+        if let Some(iso) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+            return Ok(Timestamp::DateTime(Timestamp::parse_iso(iso)?));
+        }
+
+        s.parse::<u64>()
+            .map(Timestamp::Millis)
+            .map_err(|_| TimestampError::InvalidTimestamp(s.to_string()))
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Timestamp::Millis(ms) => write!(f, "{}", ms),
+            Timestamp::DateTime(ms) => match Utc.timestamp_millis_opt(*ms as i64).single() {
+                Some(datetime) => write!(
+                    f,
+                    "'{}'",
+                    datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                ),
+                None => write!(f, "{}", ms),
+            },
+            Timestamp::Now(0) => write!(f, "now()"),
+            Timestamp::Now(offset) if *offset < 0 => {
+                write!(f, "now() - {}", format_duration(-offset))
+            }
+            Timestamp::Now(offset) => write!(f, "now() + {}", format_duration(*offset)),
+        }
+    }
+}
+
+impl From<u64> for Timestamp {
+    fn from(ms: u64) -> Self {
+        Timestamp::Millis(ms)
+    }
+}
+
+impl FilterType<Timestamp> {
+    /// Resolves the timestamp of the filter to unix milliseconds, keeping its operator.
+    pub fn resolve(&self) -> Result<FilterType<u64>, TimestampError> {
+        let millis = self.value().resolve()?;
+        Ok(self.map(|_| millis))
+    }
+}
+
+impl TryFrom<Pair<'_, Rule>> for FilterType<Timestamp> {
+    type Error = TimestampError;
+
+    /// Builds a timestamp filter from a `timestamp_ms_filter_type` pair.
+    fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        let mut inner_pair = pair.into_inner();
+        let operator = inner_pair.next().ok_or(TimestampError::MissingOperator)?;
+        let timestamp = inner_pair.as_str().parse::<Timestamp>()?;
+
+        Ok(FilterType::try_from((operator, timestamp))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp_literals() {
+        assert_eq!(
+            "1735689600000".parse::<Timestamp>().unwrap(),
+            Timestamp::Millis(1735689600000)
+        );
+        assert_eq!(
+            "'2025-01-01T00:00:00Z'".parse::<Timestamp>().unwrap(),
+            Timestamp::DateTime(1735689600000)
+        );
+        assert_eq!(
+            "'2025-01-01'".parse::<Timestamp>().unwrap(),
+            Timestamp::DateTime(1735689600000)
+        );
+        assert_eq!("now()".parse::<Timestamp>().unwrap(), Timestamp::Now(0));
+        assert_eq!(
+            "now() - 1h".parse::<Timestamp>().unwrap(),
+            Timestamp::Now(-3_600_000)
+        );
+        assert_eq!(
+            "now() + 30s".parse::<Timestamp>().unwrap(),
+            Timestamp::Now(30_000)
+        );
+        assert!("'yesterday'".parse::<Timestamp>().is_err());
+        assert!("now() - 1y".parse::<Timestamp>().is_err());
+        assert!(matches!(
+            "'1969-12-31T23:59:59Z'".parse::<Timestamp>(),
+            Err(TimestampError::BeforeUnixEpoch(_))
+        ));
+        assert!(matches!(
+            "'1900-01-01'".parse::<Timestamp>(),
+            Err(TimestampError::BeforeUnixEpoch(_))
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_display_round_trip() {
+        for source in [
+            "1735689600000",
+            "'2025-01-01T00:00:00Z'",
+            "now()",
+            "now() - 2d",
+            "now() - 90m",
+            "now() + 1500ms",
+        ] {
+            let timestamp = source.parse::<Timestamp>().unwrap();
+            assert_eq!(
+                timestamp.to_string().parse::<Timestamp>().unwrap(),
+                timestamp
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_relative_timestamp() {
+        let one_hour_ago = Timestamp::Now(-HOUR_MS).resolve().unwrap();
+        let now = Timestamp::Now(0).resolve().unwrap();
+        assert!(now - one_hour_ago >= HOUR_MS as u64);
+        assert!(matches!(
+            Timestamp::Now(-100_000 * WEEK_MS).resolve(),
+            Err(TimestampError::BeforeUnixEpoch(_))
+        ));
+    }
+}
@@ -1,20 +1,32 @@
 use super::{
-    checkpoint::{CheckpointId, CheckpointRange},
-    entity_id::{parse_checkpoint_number_or_tag, EntityIdError},
+    checkpoint::{CheckpointFilter, CheckpointFilterError, CheckpointId, CheckpointNumberOrTag},
+    entity::QueryText,
+    entity_id::EntityIdError,
+    expression::add_fields,
     filters::{
-        ComparisonFilterError, EqualityFilter, EqualityFilterError, Filter, FilterError, FilterType,
+        inclusive_bounds, ComparisonFilter, ComparisonFilterError, EqualityFilter,
+        EqualityFilterError, Filter, FilterError, FilterType,
     },
+    join::JoinEntity,
     query_result::TransactionQueryRes,
+    subquery::{
+        apply_in_ids, parse_in_values, take_subquery_filters, InFilter, InFilterError,
+        InFilterTarget, Subquery,
+    },
+    timestamp::{Timestamp, TimestampError},
 };
 use crate::interpreter::frontend::parser::Rule;
 use eql_macros::EnumVariants;
 use pest::iterators::{Pair, Pairs};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 use sui_types::base_types::SuiAddress;
 use sui_types::digests::TransactionDigest;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     ids: Option<Vec<TransactionDigest>>,
     filters: Option<Vec<TransactionFilter>>,
@@ -46,6 +58,16 @@ impl Transaction {
         self.filters.as_ref()
     }
 
+    pub(crate) fn query_text(&self) -> QueryText {
+        QueryText::new(
+            JoinEntity::Transaction,
+            &self.fields,
+            &TransactionField::wildcard_variants(),
+            self.ids.iter().flatten(),
+            self.filters.iter().flatten(),
+        )
+    }
+
     pub fn get_checkpoint_id_filter(&self) -> Result<&CheckpointId, TransactionFilterError> {
         self.filters
             .as_ref()
@@ -58,35 +80,163 @@ impl Transaction {
             .ok_or(TransactionFilterError::InvalidCheckpointIdFilter)
     }
 
+    /// Checks a transaction against the filters, `tx` holding at least the `filter_fields`.
+    /// Transactions without a value for a filtered field don't match.
     pub fn filter(&self, tx: &TransactionQueryRes) -> bool {
         if let Some(filters) = &self.filters {
             filters.iter().all(|filter| match filter {
-                TransactionFilter::Kind(t) => t.compare(&tx.kind.clone().unwrap()),
+                TransactionFilter::Kind(t) => tx.kind.as_ref().is_some_and(|kind| t.compare(kind)),
                 TransactionFilter::CheckpointId(_) => true,
-                TransactionFilter::Sender(k) => k.compare(&tx.sender.unwrap()),
-                TransactionFilter::Recipient(l) => l.compare(&tx.recepient.unwrap()),
-                TransactionFilter::GasBudget(m) => m.compare(&tx.gas_budget.unwrap()),
-                TransactionFilter::GasPrice(n) => n.compare(&tx.gas_price.unwrap()),
-                TransactionFilter::GasUsed(o) => o.compare(&tx.gas_used.unwrap()),
-                TransactionFilter::Status(p) => p.compare(&tx.status.unwrap()),
-                TransactionFilter::ExecutedEpoch(q) => q.compare(&tx.executed_epoch.unwrap()),
-                TransactionFilter::Checkpoint(r) => r.compare(&tx.checkpoint.unwrap()),
-                TransactionFilter::TimestampMs(s) => s.compare(&tx.timestamp_ms.unwrap()),
-                TransactionFilter::EventTotal(u) => u.compare(&tx.total_events.unwrap()),
+                TransactionFilter::Sender(k) => tx.sender.is_some_and(|sender| k.compare(&sender)),
+                TransactionFilter::Recipient(l) => {
+                    tx.recepient.is_some_and(|recipient| l.compare(&recipient))
+                }
+                TransactionFilter::GasBudget(m) => tx.gas_budget.is_some_and(|gas| m.compare(&gas)),
+                TransactionFilter::GasPrice(n) => tx.gas_price.is_some_and(|gas| n.compare(&gas)),
+                TransactionFilter::GasUsed(o) => tx.gas_used.is_some_and(|gas| o.compare(&gas)),
+                TransactionFilter::Status(p) => tx.status.is_some_and(|status| p.compare(&status)),
+                TransactionFilter::ExecutedEpoch(q) => {
+                    tx.executed_epoch.is_some_and(|epoch| q.compare(&epoch))
+                }
+                // Checkpoint comparisons are folded into the checkpoint range that is fetched.
+                TransactionFilter::Checkpoint(_) => true,
+                // Timestamp filters are evaluated against the RPC response by the resolver.
+                TransactionFilter::TimestampMs(_) => true,
+                TransactionFilter::EventTotal(u) => {
+                    tx.total_events.is_some_and(|events| u.compare(&events))
+                }
+                // Checked by the resolver against the RPC response, like timestamps.
+                TransactionFilter::SenderIn(_) => true,
+                TransactionFilter::Subquery(_) => true,
             })
         } else {
             true
         }
     }
 
-    pub fn has_checkpoint_filter(&self) -> bool {
+    /// The fields `filter` compares, which are fetched to check the filters whether they are
+    /// selected or not.
+    pub fn filter_fields(&self) -> Vec<TransactionField> {
+        let mut fields = vec![];
+        for filter in self.filters.iter().flatten() {
+            let field = match filter {
+                TransactionFilter::Kind(_) => TransactionField::Type,
+                TransactionFilter::Sender(_) => TransactionField::Sender,
+                TransactionFilter::GasBudget(_) => TransactionField::GasBudget,
+                TransactionFilter::GasPrice(_) => TransactionField::GasPrice,
+                TransactionFilter::GasUsed(_) => TransactionField::GasUsed,
+                TransactionFilter::Status(_) => TransactionField::Status,
+                TransactionFilter::ExecutedEpoch(_) => TransactionField::ExecutedEpoch,
+                TransactionFilter::EventTotal(_) => TransactionField::TotalEvents,
+                // `recipient` isn't fetched yet, so its filters match nothing.
+                _ => continue,
+            };
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+        fields
+    }
+
+    /// Fetches the `filter_fields` along with the selected ones. The rows of a query are
+    /// projected on its select list, so the added fields aren't returned.
+    pub(crate) fn fetch_filter_fields(&mut self) {
+        for field in self.filter_fields() {
+            if !self.fields.contains(&field) {
+                self.fields.push(field);
+            }
+        }
+    }
+
+    /// Returns the timestamp filters resolved to unix milliseconds. Relative timestamps are
+    /// resolved against the current time, so this is called once per query.
+    pub fn timestamp_filters(&self) -> Result<Vec<FilterType<u64>>, TimestampError> {
+        self.filters()
+            .into_iter()
+            .flatten()
+            .filter_map(|f| match f {
+                TransactionFilter::TimestampMs(t) => Some(t.resolve()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the inclusive `(start, end)` time bounds, in milliseconds, set by the timestamp filters.
+    pub fn timestamp_bounds(&self) -> Result<(Option<u64>, Option<u64>), TimestampError> {
+        Ok(inclusive_bounds(&self.timestamp_filters()?))
+    }
+
+    pub fn has_timestamp_filter(&self) -> bool {
         match self.filters() {
             Some(filters) => filters
                 .iter()
-                .any(|f| matches!(f, TransactionFilter::CheckpointId(CheckpointId::Range(_)))),
+                .any(|f| matches!(f, TransactionFilter::TimestampMs(_))),
             None => false,
         }
     }
+
+    /// Checks a transaction timestamp against the filters returned by `timestamp_filters`.
+    pub fn matches_timestamp(
+        timestamp_filters: &[FilterType<u64>],
+        timestamp_ms: Option<u64>,
+    ) -> bool {
+        match timestamp_ms {
+            Some(timestamp_ms) => timestamp_filters.iter().all(|f| f.compare(&timestamp_ms)),
+            None => timestamp_filters.is_empty(),
+        }
+    }
+
+    /// Checks a transaction sender against the `sender IN (...)` filters.
+    pub fn matches_sender(&self, sender: Option<SuiAddress>) -> bool {
+        let Some(filters) = self.filters() else {
+            return true;
+        };
+        filters.iter().all(|f| match f {
+            TransactionFilter::SenderIn(senders) => {
+                sender.is_some_and(|sender| senders.contains(&sender))
+            }
+            _ => true,
+        })
+    }
+
+    pub fn has_checkpoint_filter(&self) -> bool {
+        match self.filters() {
+            Some(filters) => filters.iter().any(|f| {
+                matches!(
+                    f,
+                    TransactionFilter::CheckpointId(_) | TransactionFilter::Checkpoint(_)
+                )
+            }),
+            None => false,
+        }
+    }
+
+    /// Returns the `checkpoint` ranges and comparisons as a flat list of bounds.
+    pub fn checkpoint_filters(&self) -> Vec<FilterType<CheckpointNumberOrTag>> {
+        self.filters()
+            .map(|filters| {
+                filters
+                    .iter()
+                    .flat_map(|f| match f {
+                        TransactionFilter::CheckpointId(CheckpointId::Range(range)) => {
+                            range.to_filters()
+                        }
+                        TransactionFilter::CheckpointId(CheckpointId::Number(number)) => {
+                            vec![FilterType::Equality(EqualityFilter::Eq(*number))]
+                        }
+                        TransactionFilter::Checkpoint(c) => vec![FilterType::Comparison(c.clone())],
+                        _ => vec![],
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Display for Transaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.query_text().fmt(f)
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -99,6 +249,8 @@ pub enum TransactionError {
     TransactionFieldError(#[from] TransactionFieldError),
     #[error(transparent)]
     TransactionFilterError(#[from] TransactionFilterError),
+    #[error(transparent)]
+    InFilterError(#[from] InFilterError),
     #[error("Unknown transaction error: {0}")]
     Other(#[from] anyhow::Error),
 }
@@ -110,6 +262,7 @@ impl TryFrom<Pairs<'_, Rule>> for Transaction {
         let mut ids: Option<Vec<TransactionDigest>> = None;
         let mut filter: Option<Vec<TransactionFilter>> = None;
         let mut fields: Vec<TransactionField> = vec![];
+        let mut in_filters: Vec<InFilter> = vec![];
 
         for pair in pairs {
             match pair.as_rule() {
@@ -122,24 +275,38 @@ impl TryFrom<Pairs<'_, Rule>> for Transaction {
                 }
                 Rule::tx_filter => {
                     let next_filter = pair.into_inner().next().unwrap();
+                    if next_filter.as_rule() == Rule::expr_filter {
+                        // Applied to the result rows, so the columns it uses are fetched.
+                        add_fields(&mut fields, next_filter, |field| {
+                            TransactionField::try_from(field)
+                        })?;
+                        continue;
+                    }
+                    if next_filter.as_rule() == Rule::in_filter {
+                        in_filters.push(InFilter::try_from(next_filter)?);
+                        continue;
+                    }
                     if let Some(filter) = filter.as_mut() {
                         filter.push(TransactionFilter::try_from(next_filter)?);
                     } else {
                         filter = Some(vec![TransactionFilter::try_from(next_filter)?]);
                     }
                 }
+                // DISTINCT is applied by the engine to the projected rows.
+                Rule::distinct => {}
                 Rule::tx_fields => {
-                    let inner_pairs = pair.into_inner();
+                    let inner_pairs = pair.clone().into_inner();
 
                     if let Some(pair) = inner_pairs.peek() {
                         if pair.as_rule() == Rule::wildcard {
-                            fields = TransactionField::all_variants().to_vec();
+                            fields = TransactionField::wildcard_variants();
                             continue;
                         }
                     }
-                    fields = inner_pairs
-                        .map(|pair| TransactionField::try_from(pair.as_str()))
-                        .collect::<Result<Vec<TransactionField>, TransactionFieldError>>()?;
+                    add_fields(&mut fields, pair, |field| TransactionField::try_from(field))?;
+                }
+                Rule::variable => {
+                    in_filters.push(InFilter::variable(Transaction::IN_COLUMNS[0], pair));
                 }
                 _ => {
                     return Err(TransactionError::UnexpectedToken(pair.as_str().to_string()));
@@ -147,10 +314,56 @@ impl TryFrom<Pairs<'_, Rule>> for Transaction {
             }
         }
 
-        Ok(Transaction {
+        let mut transaction = Transaction {
             ids,
             filters: filter,
             fields,
+        };
+        for in_filter in in_filters {
+            transaction.add_in_filter(in_filter)?;
+        }
+
+        Ok(transaction)
+    }
+}
+
+impl InFilterTarget for Transaction {
+    const IN_COLUMNS: &'static [&'static str] = &["digest", "sender"];
+
+    fn apply_in(&mut self, column: &str, values: &[String]) -> Result<(), InFilterError> {
+        match column {
+            "digest" => {
+                let digests = parse_in_values(column, values, TransactionDigest::from_str)?;
+                apply_in_ids(&mut self.ids, digests);
+            }
+            "sender" => {
+                let senders = parse_in_values(column, values, SuiAddress::from_str)?;
+                let filters = self.filters.get_or_insert_with(Vec::new);
+                match filters
+                    .iter_mut()
+                    .find(|f| matches!(f, TransactionFilter::SenderIn(_)))
+                {
+                    Some(TransactionFilter::SenderIn(existing)) => {
+                        existing.retain(|sender| senders.contains(sender))
+                    }
+                    _ => filters.push(TransactionFilter::SenderIn(senders)),
+                }
+            }
+            column => return Err(InFilterError::UnsupportedColumn(column.to_string())),
+        }
+        Ok(())
+    }
+
+    fn push_subquery(&mut self, subquery: Subquery) {
+        self.filters
+            .get_or_insert_with(Vec::new)
+            .push(TransactionFilter::Subquery(subquery));
+    }
+
+    fn take_subqueries(&mut self) -> Vec<Subquery> {
+        take_subquery_filters(&mut self.filters, |f| match f {
+            TransactionFilter::Subquery(subquery) => Some(subquery),
+            _ => None,
         })
     }
 }
@@ -178,9 +391,38 @@ pub enum TransactionField {
     SignatureScheme,
     PublicKey,
     Signature,
+    ChangedObjects,
     Chain,
 }
 
+impl TransactionField {
+    /// Fields that `*` expands to, the ones the resolver fetches.
+    pub fn wildcard_variants() -> Vec<TransactionField> {
+        TransactionField::all_variants()
+            .iter()
+            .filter(|field| field.is_implemented())
+            .copied()
+            .collect()
+    }
+
+    /// Whether the resolver fetches the field. The others are parsed but have no value yet.
+    pub fn is_implemented(&self) -> bool {
+        !matches!(
+            self,
+            TransactionField::Recipient
+                | TransactionField::Data
+                | TransactionField::EventDigests
+                | TransactionField::RawTransaction
+                | TransactionField::TotalObjectChanges
+                | TransactionField::TransactionKind
+                | TransactionField::Version
+                | TransactionField::SignatureScheme
+                | TransactionField::PublicKey
+                | TransactionField::Signature
+        )
+    }
+}
+
 impl std::fmt::Display for TransactionField {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -206,6 +448,7 @@ impl std::fmt::Display for TransactionField {
             TransactionField::Signature => write!(f, "signature"),
             TransactionField::SignatureScheme => write!(f, "signature_scheme"),
             TransactionField::PublicKey => write!(f, "public_key"),
+            TransactionField::ChangedObjects => write!(f, "changed_objects"),
         }
     }
 }
@@ -243,6 +486,7 @@ impl TryFrom<&str> for TransactionField {
             "signature_scheme" => Ok(TransactionField::SignatureScheme),
             "public_key" => Ok(TransactionField::PublicKey),
             "signature" => Ok(TransactionField::Signature),
+            "changed_objects" => Ok(TransactionField::ChangedObjects),
             invalid_field => Err(TransactionFieldError::InvalidTransactionField(
                 invalid_field.to_string(),
             )),
@@ -264,9 +508,13 @@ pub enum TransactionFilterError {
     ComparisonFilterError(#[from] ComparisonFilterError),
     #[error(transparent)]
     FilterError(#[from] FilterError),
+    #[error(transparent)]
+    TimestampError(#[from] TimestampError),
+    #[error(transparent)]
+    CheckpointFilterError(#[from] CheckpointFilterError),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TransactionFilter {
     Kind(EqualityFilter<String>),
     Sender(FilterType<SuiAddress>),
@@ -276,10 +524,36 @@ pub enum TransactionFilter {
     GasUsed(FilterType<u64>),
     Status(EqualityFilter<bool>),
     ExecutedEpoch(FilterType<u64>),
-    Checkpoint(FilterType<u64>),
-    TimestampMs(FilterType<u64>),
+    Checkpoint(ComparisonFilter<CheckpointNumberOrTag>),
+    TimestampMs(FilterType<Timestamp>),
     EventTotal(FilterType<usize>), // Number of events emitted
     CheckpointId(CheckpointId),
+    SenderIn(Vec<SuiAddress>),
+    Subquery(Subquery),
+}
+
+impl Display for TransactionFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionFilter::Kind(filter) => write!(f, "type {filter}"),
+            TransactionFilter::Sender(filter) => write!(f, "sender {filter}"),
+            TransactionFilter::Recipient(filter) => write!(f, "recipient {filter}"),
+            TransactionFilter::GasBudget(filter) => write!(f, "gas_budget {filter}"),
+            TransactionFilter::GasPrice(filter) => write!(f, "gas_price {filter}"),
+            TransactionFilter::GasUsed(filter) => write!(f, "gas_used {filter}"),
+            TransactionFilter::Status(filter) => write!(f, "status {filter}"),
+            TransactionFilter::ExecutedEpoch(filter) => write!(f, "executed_epoch {filter}"),
+            TransactionFilter::Checkpoint(filter) => write!(f, "checkpoint {filter}"),
+            TransactionFilter::TimestampMs(filter) => write!(f, "timestamp_ms {filter}"),
+            TransactionFilter::EventTotal(filter) => write!(f, "total_events {filter}"),
+            TransactionFilter::CheckpointId(id) => write!(f, "checkpoint = {id}"),
+            TransactionFilter::SenderIn(senders) => {
+                let senders = senders.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "sender IN ({})", senders.join(", "))
+            }
+            TransactionFilter::Subquery(subquery) => write!(f, "{subquery}"),
+        }
+    }
 }
 
 impl TransactionFilter {
@@ -338,26 +612,17 @@ impl TransactionFilter {
     }
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, TransactionFilterError> {
         match pair.as_rule() {
-            Rule::checkpointrange_filter => {
-                let range = pair
-                    .as_str()
-                    .trim_start_matches("checkpoint")
-                    .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
-                    .trim();
-
-                let (start, end) = match range.split_once(':') {
-                    // if ":" is present, we have a start and an end
-                    Some((start, end)) => (
-                        parse_checkpoint_number_or_tag(start)?,
-                        Some(parse_checkpoint_number_or_tag(end)?),
-                    ),
-                    // else we only have start
-                    None => (parse_checkpoint_number_or_tag(range)?, None),
-                };
-                Ok(TransactionFilter::CheckpointId(CheckpointId::Range(
-                    CheckpointRange::new(start, end),
-                )))
-            }
+            Rule::checkpointrange_filter => match CheckpointFilter::try_from(pair)? {
+                CheckpointFilter::Range(range) => {
+                    Ok(TransactionFilter::CheckpointId(CheckpointId::Range(range)))
+                }
+                CheckpointFilter::Comparison(comparison) => {
+                    Ok(TransactionFilter::Checkpoint(comparison))
+                }
+                filter => Err(TransactionFilterError::InvalidTransactionFilterProperty(
+                    format!("{:?}", filter),
+                )),
+            },
             Rule::type_filter_type => Self::parse_equality_filter(
                 pair,
                 |s| s.parse::<String>().unwrap(),
@@ -402,11 +667,11 @@ impl TransactionFilter {
                     EqualityFilter::try_from((operator, value == "true")).unwrap(),
                 ))
             }
-            Rule::timestamp_ms_filter_type => Self::parse_filter(
-                pair,
-                |s| s.parse::<u64>().unwrap(),
-                TransactionFilter::TimestampMs,
-            ),
+            Rule::timestamp_ms_filter_type => {
+                Ok(TransactionFilter::TimestampMs(
+                    FilterType::<Timestamp>::try_from(pair)?,
+                ))
+            }
             _ => {
                 println!("pair f: {:?}", pair);
                 return Err(TransactionFilterError::InvalidTransactionFilterProperty(
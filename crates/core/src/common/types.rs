@@ -2,32 +2,108 @@ use super::{
     chain::{Chain, ChainError, ChainOrRpc},
     dump::{Dump, DumpError},
     entity::{Entity, EntityError},
+    expression::{Distinct, Expr, ExpressionError, Projection},
+    join::{JoinColumn, JoinEntity},
 };
 use crate::interpreter::frontend::parser::Rule;
-use pest::iterators::Pairs;
+use pest::iterators::{Pair, Pairs};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
 use url::Url;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Expression {
     Get(GetExpression),
+    /// `EXPLAIN SELECT ...`, the plan of a query instead of its results.
+    Explain(GetExpression),
+    /// `SHOW ENTITIES`, the entities that can be queried.
+    ShowEntities,
+    /// `SHOW CHAINS`, the chains that can be queried and their RPCs.
+    ShowChains,
+    /// `DESCRIBE entity`, the fields of an entity and how they can be filtered.
+    Describe(JoinEntity),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct GetExpression {
     pub entity: Entity,
     pub chains: Vec<ChainOrRpc>,
     pub dump: Option<Dump>,
+    /// Computed columns and expression filters, applied to the rows of the entity.
+    pub projection: Option<Projection>,
+    /// `DISTINCT` or `DISTINCT ON (columns)`, applied to the projected rows.
+    pub distinct: Option<Distinct>,
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Get(get) => write!(f, "{get}"),
+            Expression::Explain(get) => write!(f, "EXPLAIN {get}"),
+            Expression::ShowEntities => write!(f, "SHOW ENTITIES"),
+            Expression::ShowChains => write!(f, "SHOW CHAINS"),
+            Expression::Describe(entity) => write!(f, "DESCRIBE {entity}"),
+        }
+    }
+}
+
+impl From<GetExpression> for Expression {
+    fn from(get: GetExpression) -> Self {
+        Expression::Get(get)
+    }
 }
 
 impl GetExpression {
-    fn new(entity: Entity, chains: Vec<ChainOrRpc>, dump: Option<Dump>) -> Self {
+    pub(crate) fn new(
+        entity: Entity,
+        chains: Vec<ChainOrRpc>,
+        dump: Option<Dump>,
+        projection: Option<Projection>,
+        distinct: Option<Distinct>,
+    ) -> Self {
+        let mut entity = entity;
+        // Built and parsed queries fetch the fields their filters compare alike.
+        if let Entity::Transaction(transaction) = &mut entity {
+            transaction.fetch_filter_fields();
+        }
+
         Self {
             entity,
             chains,
             dump,
+            projection,
+            distinct,
         }
     }
 }
+
+/// Writes the query back as query text, which parses to the same expression.
+impl Display for GetExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut query = self.entity.query_text();
+        if let Some(projection) = &self.projection {
+            query.columns = projection
+                .columns()
+                .map(|items| items.iter().map(ToString::to_string).collect());
+            query
+                .filters
+                .extend(projection.filters().iter().map(Expr::to_filter_string));
+        }
+        query.distinct = self.distinct.clone();
+
+        let chains = self
+            .chains
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{query} ON {}", chains.join(", "))?;
+        if let Some(dump) = &self.dump {
+            write!(f, " >> {}", dump.path())?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetExpressionError {
     #[error("Unexpected token: {0}")]
@@ -44,29 +120,43 @@ pub enum GetExpressionError {
     ChainError(#[from] ChainError),
     #[error(transparent)]
     DumpError(#[from] DumpError),
+    #[error("Missing variable name")]
+    MissingVariableName,
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
 }
 
 impl TryFrom<Pairs<'_, Rule>> for GetExpression {
     type Error = GetExpressionError;
 
     fn try_from(pairs: Pairs<'_, Rule>) -> Result<Self, Self::Error> {
+        GetExpression::parse(pairs, None, &[])
+    }
+}
+
+impl GetExpression {
+    /// Parses a query, running it on `default_chains` when it has no `ON` clause. `networks`
+    /// are the networks of the config file its `ON` clause can name.
+    pub fn parse(
+        pairs: Pairs<'_, Rule>,
+        default_chains: Option<&Vec<ChainOrRpc>>,
+        networks: &[String],
+    ) -> Result<Self, GetExpressionError> {
         let mut entity: Option<Entity> = None;
         let mut chains: Option<Vec<ChainOrRpc>> = None;
         let mut dump: Option<Dump> = None;
+        let mut projection: Option<Projection> = None;
+        let mut distinct: Option<Distinct> = None;
 
         for pair in pairs {
             match pair.as_rule() {
                 Rule::entity => {
+                    projection = Projection::from_entity(&pair)?;
+                    distinct = Distinct::from_entity(&pair);
                     entity = Some(Entity::try_from(pair.into_inner())?);
                 }
-                Rule::chain_selector => {
-                    let selector = pair.as_str();
-                    chains = Some(Chain::from_selector(selector)?);
-                }
-                Rule::rpc_url => {
-                    let url = Url::parse(pair.as_str())
-                        .map_err(|e| GetExpressionError::UrlParseError(e.to_string()))?;
-                    chains = Some(vec![ChainOrRpc::Rpc(url)]);
+                Rule::chain_selector | Rule::rpc_url => {
+                    chains = Some(parse_chains(pair, networks)?);
                 }
                 Rule::dump => {
                     dump = Some(Dump::try_from(pair.into_inner())?);
@@ -79,10 +169,286 @@ impl TryFrom<Pairs<'_, Rule>> for GetExpression {
             }
         }
 
+        let entity = entity.ok_or(GetExpressionError::MissingEntity)?;
+        if let Some(distinct) = &distinct {
+            let columns = match (&projection, &entity) {
+                (Some(projection), _) => projection.column_names(),
+                (None, Entity::Join(join)) => join.columns().iter().map(JoinColumn::name).collect(),
+                _ => vec![],
+            };
+            distinct.validate(&columns)?;
+        }
+
         Ok(GetExpression::new(
-            entity.ok_or(GetExpressionError::MissingEntity)?,
-            chains.ok_or(GetExpressionError::MissingChainOrRpc)?,
+            entity,
+            chains
+                .or_else(|| default_chains.cloned())
+                .ok_or(GetExpressionError::MissingChainOrRpc)?,
             dump,
+            projection,
+            distinct,
         ))
     }
 }
+
+/// `LET name = SELECT ...` binds a query to `$name`. The query runs as a subquery wherever
+/// the variable is used.
+#[derive(Debug, PartialEq)]
+pub struct LetStatement {
+    pub name: String,
+    pub entity: Entity,
+}
+
+impl TryFrom<Pairs<'_, Rule>> for LetStatement {
+    type Error = GetExpressionError;
+
+    fn try_from(pairs: Pairs<'_, Rule>) -> Result<Self, Self::Error> {
+        let mut name: Option<String> = None;
+        let mut entity: Option<Entity> = None;
+
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::variable_name => name = Some(pair.as_str().trim().to_string()),
+                Rule::entity => {
+                    Projection::reject(&pair, "LET statements")?;
+                    entity = Some(Entity::try_from(pair.into_inner())?);
+                }
+                _ => {
+                    return Err(GetExpressionError::UnexpectedToken(
+                        pair.as_str().to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(LetStatement {
+            name: name.ok_or(GetExpressionError::MissingVariableName)?,
+            entity: entity.ok_or(GetExpressionError::MissingEntity)?,
+        })
+    }
+}
+
+/// `SET chain = ...` sets the chains of the following queries that have no `ON` clause.
+#[derive(Debug, PartialEq)]
+pub struct SetStatement {
+    pub chains: Vec<ChainOrRpc>,
+}
+
+impl SetStatement {
+    /// Parses a `SET chain`, which can name the networks of the config file in `networks`.
+    pub fn parse(
+        mut pairs: Pairs<'_, Rule>,
+        networks: &[String],
+    ) -> Result<Self, GetExpressionError> {
+        let pair = pairs.next().ok_or(GetExpressionError::MissingChainOrRpc)?;
+        Ok(SetStatement {
+            chains: parse_chains(pair, networks)?,
+        })
+    }
+}
+
+fn parse_chains(
+    pair: Pair<'_, Rule>,
+    networks: &[String],
+) -> Result<Vec<ChainOrRpc>, GetExpressionError> {
+    match pair.as_rule() {
+        Rule::chain_selector => Ok(Chain::from_selector(pair.as_str(), networks)?),
+        Rule::rpc_url => {
+            let url = Url::parse(pair.as_str())
+                .map_err(|e| GetExpressionError::UrlParseError(e.to_string()))?;
+            Ok(vec![ChainOrRpc::Rpc(url)])
+        }
+        _ => Err(GetExpressionError::UnexpectedToken(
+            pair.as_str().to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        common::{params::Params, subquery::SubquerySource, transaction::TransactionField},
+        interpreter::frontend::{analyzer::Analyzer, parser::Parser},
+    };
+
+    #[test]
+    fn test_let_and_set_statements() {
+        let source = "
+            SET chain = sui_testnet;
+            LET names = SELECT address FROM name WHERE name = :name;
+            SELECT * FROM account $names;
+            SELECT * FROM account $1 ON sui_mainnet
+        ";
+        let params: Params = [("name", "example.sui"), ("1", "example.sui")]
+            .into_iter()
+            .collect();
+        let expressions = Parser::new(source)
+            .parse_expressions_with_params(&params)
+            .unwrap();
+        assert_eq!(expressions.len(), 2);
+
+        let Expression::Get(first) = &expressions[0] else {
+            panic!("expected a query");
+        };
+        assert_eq!(first.chains, vec![ChainOrRpc::Chain(Chain::Testnet)]);
+        let mut entity = first.entity.clone();
+        let subqueries = entity.take_subqueries();
+        assert_eq!(subqueries.len(), 1);
+        assert_eq!(subqueries[0].column, "address");
+        assert!(matches!(
+            &subqueries[0].source,
+            SubquerySource::Query(entity) if matches!(**entity, Entity::Name(_))
+        ));
+
+        let Expression::Get(second) = &expressions[1] else {
+            panic!("expected a query");
+        };
+        assert_eq!(second.chains, vec![ChainOrRpc::Chain(Chain::Mainnet)]);
+    }
+
+    #[test]
+    fn test_queries_without_on() {
+        let source = "SELECT * FROM tx WHERE checkpoint = 1; SELECT * FROM account $1";
+        let params: Params = [("1", "example.sui")].into_iter().collect();
+        let expressions = Parser::new(source)
+            .with_default_chains(Some(vec![ChainOrRpc::Chain(Chain::Devnet)]))
+            .parse_expressions_with_params(&params)
+            .unwrap();
+
+        for expression in expressions {
+            let Expression::Get(get) = expression else {
+                panic!("expected a query");
+            };
+            assert_eq!(get.chains, vec![ChainOrRpc::Chain(Chain::Devnet)]);
+        }
+        assert!(Parser::new("SELECT * FROM tx WHERE checkpoint = 1")
+            .parse_expressions()
+            .is_err());
+    }
+
+    #[test]
+    fn test_config_networks() {
+        let source =
+            "SET chain = localnet; SELECT * FROM tx WHERE checkpoint = 1 ON sui_testnet, localnet";
+        let expressions = Parser::new(source)
+            .with_networks(vec!["localnet".to_string()])
+            .parse_expressions()
+            .unwrap();
+        let Expression::Get(get) = &expressions[0] else {
+            panic!("expected a query");
+        };
+        assert_eq!(
+            get.chains,
+            vec![
+                ChainOrRpc::Chain(Chain::Testnet),
+                ChainOrRpc::Chain(Chain::Custom("localnet".to_string()))
+            ]
+        );
+        assert!(Parser::new(source).parse_expressions().is_err());
+        assert!(Parser::new("SET chain = localnet")
+            .parse_expressions()
+            .is_err());
+    }
+
+    #[test]
+    fn test_unknown_variable() {
+        let source = "SELECT * FROM account $whales ON sui_mainnet";
+        assert!(Parser::new(source).parse_expressions().is_err());
+        assert!(Parser::new("SELECT * FROM account $1 ON sui_mainnet")
+            .parse_expressions()
+            .is_err());
+    }
+
+    #[test]
+    fn test_computed_columns() {
+        let source = "SELECT digest, gas_budget - gas_used AS unused_gas FROM tx \
+                      WHERE checkpoint = 10, gas_price * 2 > 1000 ON sui_mainnet";
+        let expressions = Parser::new(source).parse_expressions().unwrap();
+
+        let Expression::Get(get) = &expressions[0] else {
+            panic!("expected a query");
+        };
+        assert!(get.projection.is_some());
+        let Entity::Transaction(tx) = &get.entity else {
+            panic!("expected a transaction query");
+        };
+        assert_eq!(
+            tx.fields(),
+            &vec![
+                TransactionField::Digest,
+                TransactionField::GasBudget,
+                TransactionField::GasUsed,
+                TransactionField::GasPrice,
+            ]
+        );
+
+        let source = "LET gas = SELECT gas_used * 2 FROM tx WHERE checkpoint = 10;
+                      SELECT * FROM tx $gas ON sui_mainnet";
+        assert!(Parser::new(source).parse_expressions().is_err());
+    }
+
+    #[test]
+    fn test_comments_strings_and_keywords() {
+        let source = "
+            /* Coins by type */
+            select * from coin '0x2::sui::SUI', \"0x2::coin::Coin<0x2::sui::SUI>\" -- quoted
+            on sui_mainnet;
+            Select name From name Where name = 'example.sui' On sui_mainnet; -- by name
+            SELECT * FROM checkpoint 10 -- a single checkpoint
+            ON sui_mainnet, sui_testnet -- both chains
+        ";
+        let expressions = Parser::new(source).parse_expressions().unwrap();
+        assert_eq!(expressions.len(), 3);
+
+        let Expression::Get(coins) = &expressions[0] else {
+            panic!("expected a query");
+        };
+        assert_eq!(coins.chains, vec![ChainOrRpc::Chain(Chain::Mainnet)]);
+        let Entity::Coin(coin) = &coins.entity else {
+            panic!("expected a coin query");
+        };
+        assert_eq!(
+            coin.ids(),
+            Some(&vec![
+                "0x2::sui::SUI".to_string(),
+                "0x2::coin::Coin<0x2::sui::SUI>".to_string()
+            ])
+        );
+
+        let Expression::Get(names) = &expressions[1] else {
+            panic!("expected a query");
+        };
+        let Entity::Name(name) = &names.entity else {
+            panic!("expected a name query");
+        };
+        assert_eq!(name.name_filters()[0].value(), "example.sui");
+
+        let Expression::Get(checkpoints) = &expressions[2] else {
+            panic!("expected a query");
+        };
+        assert_eq!(checkpoints.chains.len(), 2);
+    }
+
+    #[test]
+    fn test_readme_and_examples_parse() {
+        let params: Params = [("name", "example.sui"), ("1", "500")]
+            .into_iter()
+            .collect();
+        let readme = include_str!("../../../../README.md");
+        let blocks = readme
+            .split("```sql")
+            .skip(1)
+            .map(|block| block.split("```").next().unwrap_or_default().to_string());
+        let examples = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples"))
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap());
+
+        for source in blocks.chain(examples) {
+            let expressions = Parser::new(&source).parse_expressions_with_params(&params);
+            assert!(expressions.is_ok(), "{source}");
+            let analyzed = Analyzer::new(&params.bind(&source).unwrap()).analyze();
+            assert_eq!(analyzed, Ok(()), "{source}");
+        }
+    }
+}
@@ -1,13 +1,24 @@
 use super::{
-    resolve_account::resolve_account_query, resolve_checkpoint::resolve_checkpoint_query,
-    resolve_coin::resolve_coin_query, resolve_object::resolve_object_query,
+    explain::explain_query,
+    resolve_account::resolve_account_query,
+    resolve_checkpoint::resolve_checkpoint_query,
+    resolve_coin::resolve_coin_query,
+    resolve_join::resolve_join_query,
+    resolve_name::resolve_name_query,
+    resolve_object::resolve_object_query,
+    resolve_subquery::{resolve_subqueries, to_rows, Row},
     resolve_transaction::resolve_transaction_query,
 };
-use crate::common::{
-    entity::Entity,
-    query_result::{ExpressionResult, QueryResult},
-    serializer::dump_results,
-    types::{Expression, GetExpression},
+use crate::{
+    common::{
+        chain::{Chain, ChainOrRpc},
+        entity::Entity,
+        query_result::{ExpressionResult, QueryResult, RowQueryRes},
+        schema::{chain_rows, describe_rows, entity_rows},
+        serializer::dump_results,
+        types::{Expression, GetExpression},
+    },
+    interpreter::frontend::analyzer::Analyzer,
 };
 use anyhow::Result;
 
@@ -35,35 +46,122 @@ impl ExecutionEngine {
                     let result = self.run_get_expr(&get_expr).await?;
                     query_results.push(QueryResult::new(result));
                 }
+                Expression::Explain(get_expr) => {
+                    let plan = explain_query(&get_expr).await?;
+                    let rows = plan.into_iter().map(RowQueryRes::from).collect();
+                    query_results.push(QueryResult::new(ExpressionResult::Rows(rows)));
+                }
+                Expression::ShowEntities => {
+                    query_results.push(QueryResult::new(ExpressionResult::Rows(entity_rows())));
+                }
+                Expression::ShowChains => {
+                    query_results.push(QueryResult::new(ExpressionResult::Rows(chain_rows()?)));
+                }
+                Expression::Describe(entity) => {
+                    let rows = describe_rows(entity);
+                    query_results.push(QueryResult::new(ExpressionResult::Rows(rows)));
+                }
             }
         }
 
         Ok(query_results)
     }
 
+    /// Runs a single query, such as one built with [`Query`](crate::common::builder::Query).
+    /// The query goes through the checks of a parsed program first.
+    pub async fn run_query(&self, query: GetExpression) -> Result<QueryResult> {
+        // The networks of the query were named when it was built, so they are known.
+        let networks = query
+            .chains
+            .iter()
+            .filter_map(|chain| match chain {
+                ChainOrRpc::Chain(Chain::Custom(network)) => Some(network.clone()),
+                _ => None,
+            })
+            .collect();
+        Analyzer::new(&query.to_string())
+            .with_networks(networks)
+            .analyze()?;
+
+        let result = self.run_get_expr(&query).await?;
+        Ok(QueryResult::new(result))
+    }
+
     async fn run_get_expr(&self, expr: &GetExpression) -> Result<ExpressionResult> {
-        let result = match &expr.entity {
-            Entity::Checkpoint(checkpoint) => ExpressionResult::Checkpoint(
-                resolve_checkpoint_query(checkpoint, &expr.chains).await?,
-            ),
+        // Subqueries run first, their values narrow down the ids of the outer query.
+        let mut result = match resolve_subqueries(&expr.entity, &expr.chains).await? {
+            Some(entity) => self.resolve_entity(&entity, &expr.chains).await?,
+            None => empty_result(&expr.entity),
+        };
+
+        if let Some(projection) = &expr.projection {
+            result = ExpressionResult::Rows(projection.apply(result_rows(result)?)?);
+        }
+
+        if let Some(distinct) = &expr.distinct {
+            result = match result {
+                ExpressionResult::Rows(rows) => ExpressionResult::Rows(distinct.apply(rows)),
+                ExpressionResult::Join(rows) => ExpressionResult::Join(distinct.apply(rows)),
+                result => result,
+            };
+        }
+
+        if let Some(dump) = &expr.dump {
+            let _ = dump_results(&result, dump);
+        }
+
+        Ok(result)
+    }
+
+    async fn resolve_entity(
+        &self,
+        entity: &Entity,
+        chains: &[ChainOrRpc],
+    ) -> Result<ExpressionResult> {
+        let result = match entity {
+            Entity::Checkpoint(checkpoint) => {
+                ExpressionResult::Checkpoint(resolve_checkpoint_query(checkpoint, chains).await?)
+            }
             Entity::Account(account) => {
-                ExpressionResult::Account(resolve_account_query(account, &expr.chains).await?)
+                ExpressionResult::Account(resolve_account_query(account, chains).await?)
             }
-            Entity::Transaction(transaction) => ExpressionResult::Transaction(
-                resolve_transaction_query(transaction, &expr.chains).await?,
-            ),
-            Entity::Coin(coin) => {
-                ExpressionResult::Coin(resolve_coin_query(coin, &expr.chains).await?)
+            Entity::Transaction(transaction) => {
+                ExpressionResult::Transaction(resolve_transaction_query(transaction, chains).await?)
             }
+            Entity::Coin(coin) => ExpressionResult::Coin(resolve_coin_query(coin, chains).await?),
             Entity::Object(object) => {
-                ExpressionResult::Object(resolve_object_query(object, &expr.chains).await?)
+                ExpressionResult::Object(resolve_object_query(object, chains).await?)
             }
+            Entity::Name(name) => ExpressionResult::Name(resolve_name_query(name, chains).await?),
+            Entity::Join(join) => ExpressionResult::Join(resolve_join_query(join, chains).await?),
         };
 
-        if let Some(dump) = &expr.dump {
-            let _ = dump_results(&result, dump);
-        }
-
         Ok(result)
     }
 }
+
+/// The result of a query whose subqueries didn't return any values.
+fn empty_result(entity: &Entity) -> ExpressionResult {
+    match entity {
+        Entity::Account(_) => ExpressionResult::Account(vec![]),
+        Entity::Checkpoint(_) => ExpressionResult::Checkpoint(vec![]),
+        Entity::Transaction(_) => ExpressionResult::Transaction(vec![]),
+        Entity::Coin(_) => ExpressionResult::Coin(vec![]),
+        Entity::Object(_) => ExpressionResult::Object(vec![]),
+        Entity::Name(_) => ExpressionResult::Name(vec![]),
+        Entity::Join(_) => ExpressionResult::Join(vec![]),
+    }
+}
+
+/// The rows of a result, keyed by the serialized field names.
+fn result_rows(result: ExpressionResult) -> Result<Vec<Row>> {
+    match result {
+        ExpressionResult::Account(accounts) => to_rows(accounts),
+        ExpressionResult::Checkpoint(checkpoints) => to_rows(checkpoints),
+        ExpressionResult::Transaction(transactions) => to_rows(transactions),
+        ExpressionResult::Coin(coins) => to_rows(coins),
+        ExpressionResult::Object(objects) => to_rows(objects),
+        ExpressionResult::Name(names) => to_rows(names),
+        ExpressionResult::Join(rows) | ExpressionResult::Rows(rows) => to_rows(rows),
+    }
+}
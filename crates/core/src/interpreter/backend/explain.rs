@@ -0,0 +1,846 @@
+use super::resolve_checkpoint::{CHECKPOINT_PAGE_SIZE, MAX_CONCURRENT_REQUESTS};
+use crate::common::{
+    account::{Account, AccountField},
+    chain::ChainOrRpc,
+    checkpoint::{
+        checkpoint_bounds, get_checkpoint_number_from_tag, Checkpoint, CheckpointFilter,
+        CheckpointId, CheckpointNumberOrTag, CheckpointTag,
+    },
+    coin::{Coin, CoinField, CoinFilter},
+    entity::Entity,
+    entity_id::unique_ids,
+    expression::Distinct,
+    filters::{EqualityFilter, FilterType},
+    join::Join,
+    name::{Name, NameField},
+    name_services::{NameOrAddress, SuiNsConfig},
+    object::Object,
+    query_result::RowQueryRes,
+    transaction::{Transaction, TransactionFilter},
+    types::GetExpression,
+};
+use anyhow::Result;
+use futures::future::{BoxFuture, FutureExt};
+use serde_json::Value;
+use sui_sdk::SuiClientBuilder;
+use url::Url;
+
+/// A step of a query plan: requests sent to a chain, or work done on the fetched rows.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlanStep {
+    /// The chain the requests are sent to, `None` for the steps run locally.
+    pub chain: Option<String>,
+    pub endpoint: Option<Url>,
+    pub operation: String,
+    pub rpc_method: Option<&'static str>,
+    /// The number of requests, when it is known before the query runs.
+    pub requests: Option<u64>,
+    pub detail: String,
+}
+
+impl From<PlanStep> for RowQueryRes {
+    fn from(step: PlanStep) -> Self {
+        let text = |value: Option<String>| value.map_or(Value::Null, Value::String);
+        RowQueryRes {
+            columns: vec![
+                ("chain".to_string(), text(step.chain)),
+                (
+                    "endpoint".to_string(),
+                    text(step.endpoint.map(String::from)),
+                ),
+                ("operation".to_string(), Value::String(step.operation)),
+                (
+                    "rpc_method".to_string(),
+                    text(step.rpc_method.map(String::from)),
+                ),
+                (
+                    "requests".to_string(),
+                    step.requests.map_or(Value::Null, Value::from),
+                ),
+                ("detail".to_string(), Value::String(step.detail)),
+            ],
+        }
+    }
+}
+
+/// Plans a query without running it: which chains and endpoints are queried, which RPC methods
+/// are called and how many times, and which filters narrow down what is fetched versus the ones
+/// evaluated on the fetched rows. The only requests sent are the ones resolving `latest` tags.
+pub async fn explain_query(expr: &GetExpression) -> Result<Vec<PlanStep>> {
+    let mut planner = Planner::default();
+
+    for chain in &expr.chains {
+        planner.start_chain(chain)?;
+        planner.plan_entity(&expr.entity).await?;
+    }
+
+    planner.chain = None;
+    planner.endpoint = None;
+    if let Some(projection) = &expr.projection {
+        let detail = match projection.filter_count() {
+            0 => format!("columns {}", projection.column_names().join(", ")),
+            filters => format!(
+                "columns {}, after {filters} expression filters",
+                projection.column_names().join(", ")
+            ),
+        };
+        planner.local("project rows", detail);
+    }
+    if let Some(distinct) = &expr.distinct {
+        let detail = match distinct {
+            Distinct::All => "rows repeating every column".to_string(),
+            Distinct::On(columns) => format!("the first row of every {}", columns.join(", ")),
+        };
+        planner.local("drop repeated rows", detail);
+    }
+    if let Some(dump) = &expr.dump {
+        planner.local(
+            "write file",
+            format!("{}, not written by EXPLAIN", dump.path()),
+        );
+    }
+
+    Ok(planner.steps)
+}
+
+/// The range of checkpoints a query fetches, as far as it is known before the query runs.
+enum PlannedRange {
+    Checkpoints(u64, u64),
+    Empty,
+    /// Depends on epochs or timestamps, only resolved when the query runs.
+    Unknown,
+}
+
+#[derive(Default)]
+struct Planner {
+    steps: Vec<PlanStep>,
+    chain: Option<ChainOrRpc>,
+    endpoint: Option<Url>,
+    latest: Option<u64>,
+    /// Prefixes the operations of the subqueries being planned.
+    scope: Vec<String>,
+}
+
+impl Planner {
+    fn start_chain(&mut self, chain: &ChainOrRpc) -> Result<()> {
+        self.endpoint = Some(chain.rpc_url()?);
+        self.chain = Some(chain.clone());
+        self.latest = None;
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        operation: &str,
+        rpc_method: Option<&'static str>,
+        requests: Option<u64>,
+        detail: impl Into<String>,
+    ) {
+        let chain = self.chain.as_ref().map(|chain| match chain {
+            ChainOrRpc::Chain(chain) => chain.to_string(),
+            ChainOrRpc::Rpc(url) => url.to_string(),
+        });
+        let operation = self
+            .scope
+            .iter()
+            .map(String::as_str)
+            .chain([operation])
+            .collect::<Vec<_>>()
+            .join(": ");
+
+        self.steps.push(PlanStep {
+            chain,
+            endpoint: self.endpoint.clone(),
+            operation,
+            rpc_method,
+            requests,
+            detail: detail.into(),
+        });
+    }
+
+    fn call(
+        &mut self,
+        operation: &str,
+        rpc_method: &'static str,
+        requests: Option<u64>,
+        detail: impl Into<String>,
+    ) {
+        self.push(operation, Some(rpc_method), requests, detail);
+    }
+
+    fn local(&mut self, operation: &str, detail: impl Into<String>) {
+        self.push(operation, None, None, detail);
+    }
+
+    /// Lists the filters that narrow down what is fetched and the ones checked on the results.
+    fn filters(&mut self, pushed_down: &[&str], local: &[&str]) {
+        if !pushed_down.is_empty() {
+            self.local(
+                "push down filters",
+                unique_ids(pushed_down.iter().copied()).join(", "),
+            );
+        }
+        if !local.is_empty() {
+            self.local(
+                "filter locally",
+                unique_ids(local.iter().copied()).join(", "),
+            );
+        }
+    }
+
+    fn plan_entity<'a>(&'a mut self, entity: &'a Entity) -> BoxFuture<'a, Result<()>> {
+        async move {
+            let mut entity = entity.clone();
+            for subquery in entity.take_subqueries() {
+                self.local(
+                    "run subquery",
+                    format!(
+                        "`{} IN (SELECT ...)` runs first, its values are fetched like ids",
+                        subquery.column
+                    ),
+                );
+                self.scope.push(format!("subquery `{}`", subquery.column));
+                self.plan_entity(subquery.entity()?).await?;
+                self.scope.pop();
+            }
+
+            match &entity {
+                Entity::Account(account) => self.plan_account(account),
+                Entity::Checkpoint(checkpoint) => self.plan_checkpoint(checkpoint).await?,
+                Entity::Transaction(transaction) => self.plan_transaction(transaction).await?,
+                Entity::Coin(coin) => self.plan_coin(coin),
+                Entity::Object(object) => self.plan_object(object),
+                Entity::Name(name) => self.plan_name(name),
+                Entity::Join(join) => self.plan_join(join).await?,
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+
+    async fn plan_transaction(&mut self, transaction: &Transaction) -> Result<()> {
+        let mut pushed_down = vec![];
+        let mut local = vec![];
+
+        match transaction.ids() {
+            Some(ids) => {
+                pushed_down.push("digest");
+                self.call(
+                    "fetch transactions",
+                    "sui_getTransactionBlock",
+                    Some(unique_ids(ids).len() as u64),
+                    "one request per digest, sent concurrently",
+                );
+            }
+            None => {
+                let timestamp_bounds = transaction
+                    .has_timestamp_filter()
+                    .then(|| transaction.timestamp_bounds())
+                    .transpose()?;
+                let range = self
+                    .checkpoint_range(&transaction.checkpoint_filters(), &[], timestamp_bounds)
+                    .await?;
+                self.fetch_checkpoints(&range, "for their transaction digests");
+                if !matches!(range, PlannedRange::Empty) {
+                    self.call(
+                        "fetch transactions",
+                        "sui_multiGetTransactionBlocks",
+                        Some(1),
+                        "every transaction of the fetched checkpoints, in a single request",
+                    );
+                }
+            }
+        }
+
+        for filter in transaction.filters().into_iter().flatten() {
+            match filter {
+                TransactionFilter::CheckpointId(_) | TransactionFilter::Checkpoint(_) => {
+                    pushed_down.push("checkpoint")
+                }
+                TransactionFilter::TimestampMs(_) => {
+                    pushed_down.push("timestamp_ms");
+                    local.push("timestamp_ms");
+                }
+                TransactionFilter::Kind(_) => local.push("type"),
+                TransactionFilter::Sender(_) => local.push("sender"),
+                TransactionFilter::SenderIn(_) => local.push("sender IN"),
+                TransactionFilter::Recipient(_) => local.push("recipient"),
+                TransactionFilter::GasBudget(_) => local.push("gas_budget"),
+                TransactionFilter::GasPrice(_) => local.push("gas_price"),
+                TransactionFilter::GasUsed(_) => local.push("gas_used"),
+                TransactionFilter::Status(_) => local.push("status"),
+                TransactionFilter::ExecutedEpoch(_) => local.push("executed_epoch"),
+                TransactionFilter::EventTotal(_) => local.push("total_events"),
+                TransactionFilter::Subquery(_) => {}
+            }
+        }
+        self.filters(&pushed_down, &local);
+
+        Ok(())
+    }
+
+    async fn plan_checkpoint(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        let mut pushed_down = vec![];
+        let mut local = vec![];
+
+        let timestamp_bounds = checkpoint
+            .has_timestamp_filter()
+            .then(|| checkpoint.timestamp_bounds())
+            .transpose()?;
+        let epoch_filters = checkpoint.epoch_filters();
+        let bounds = match checkpoint.has_range_filter() {
+            true => Some(
+                self.checkpoint_range(
+                    &checkpoint.checkpoint_filters(),
+                    &epoch_filters,
+                    timestamp_bounds,
+                )
+                .await?,
+            ),
+            false => None,
+        };
+
+        let ids = checkpoint.ids().cloned().unwrap_or_default();
+        let ranges = match (ids.is_empty(), bounds) {
+            (true, Some(bounds)) => vec![bounds],
+            (_, bounds) => {
+                let mut ranges = vec![];
+                for id in unique_ids(&ids) {
+                    let range = match id {
+                        CheckpointId::Number(number) => {
+                            let number = self.checkpoint_number(number).await?;
+                            (number, number)
+                        }
+                        CheckpointId::Range(range) => {
+                            let start = self.checkpoint_number(&range.start()).await?;
+                            let end = match range.end() {
+                                Some(end) => self.checkpoint_number(&end).await?,
+                                None => start,
+                            };
+                            (start, end)
+                        }
+                    };
+                    ranges.push(match range {
+                        (Some(start), Some(end)) => PlannedRange::Checkpoints(start, end),
+                        _ => PlannedRange::Unknown,
+                    });
+                }
+                // Explicit ids are narrowed down to the checkpoints the filters allow.
+                if let Some(PlannedRange::Checkpoints(low, high)) = bounds {
+                    ranges = ranges
+                        .into_iter()
+                        .map(|range| match range {
+                            PlannedRange::Checkpoints(start, end) => {
+                                match (start.max(low), end.min(high)) {
+                                    (start, end) if start <= end => {
+                                        PlannedRange::Checkpoints(start, end)
+                                    }
+                                    _ => PlannedRange::Empty,
+                                }
+                            }
+                            range => range,
+                        })
+                        .collect();
+                }
+                ranges
+            }
+        };
+
+        let range = ranges
+            .iter()
+            .fold(PlannedRange::Empty, |total, range| match (total, range) {
+                (PlannedRange::Unknown, _) | (_, PlannedRange::Unknown) => PlannedRange::Unknown,
+                (total, PlannedRange::Empty) => total,
+                (PlannedRange::Empty, PlannedRange::Checkpoints(start, end)) => {
+                    PlannedRange::Checkpoints(*start, *end)
+                }
+                (PlannedRange::Checkpoints(a, b), PlannedRange::Checkpoints(start, end)) => {
+                    PlannedRange::Checkpoints(a.min(*start), b.max(*end))
+                }
+            });
+        let count = ranges
+            .iter()
+            .map(|range| match range {
+                PlannedRange::Checkpoints(start, end) => Some(end - start + 1),
+                PlannedRange::Empty => Some(0),
+                PlannedRange::Unknown => None,
+            })
+            .sum::<Option<u64>>();
+        match (count, &range) {
+            (Some(0), _) | (_, PlannedRange::Empty) => {
+                self.fetch_checkpoints(&PlannedRange::Empty, "")
+            }
+            (Some(count), PlannedRange::Checkpoints(start, end)) if ranges.len() > 1 => self.call(
+                "fetch checkpoints",
+                "sui_getCheckpoint",
+                Some(count),
+                format!(
+                    "{count} checkpoints between {start} and {end}, \
+                     {MAX_CONCURRENT_REQUESTS} at a time"
+                ),
+            ),
+            _ => self.fetch_checkpoints(&range, ""),
+        }
+
+        if checkpoint.uses_gas_costs() && !matches!(range, PlannedRange::Empty) {
+            // The costs of a checkpoint are the difference with the previous one, which is
+            // fetched too when it isn't part of the page of checkpoints being fetched.
+            let previous = ranges
+                .iter()
+                .map(|range| match range {
+                    PlannedRange::Checkpoints(start, end) => {
+                        Some((end - start) / CHECKPOINT_PAGE_SIZE + 1 - u64::from(*start == 0))
+                    }
+                    PlannedRange::Empty => Some(0),
+                    PlannedRange::Unknown => None,
+                })
+                .sum::<Option<u64>>();
+            self.call(
+                "fetch previous checkpoints",
+                "sui_getCheckpoint",
+                previous,
+                "for the gas costs of the first checkpoint of every page",
+            );
+        }
+
+        if !ids.is_empty() {
+            pushed_down.push("number");
+        }
+        for filter in checkpoint.filters().into_iter().flatten() {
+            match filter {
+                CheckpointFilter::Range(_) | CheckpointFilter::Comparison(_) => {
+                    pushed_down.push("checkpoint")
+                }
+                CheckpointFilter::Timestamp(_) => {
+                    pushed_down.push("timestamp");
+                    local.push("timestamp");
+                }
+                CheckpointFilter::Epoch(_) => {
+                    pushed_down.push("epoch");
+                    local.push("epoch");
+                }
+                CheckpointFilter::Transactions(_) => local.push("transactions"),
+                CheckpointFilter::ComputationCost(_) => local.push("computation_cost"),
+                CheckpointFilter::StorageCost(_) => local.push("storage_cost"),
+                CheckpointFilter::StorageRebate(_) => local.push("storage_rebate"),
+                CheckpointFilter::NonRefundableStorageFee(_) => {
+                    local.push("non_refundable_storage_fee")
+                }
+                CheckpointFilter::NetworkTotalTransactions(_) => {
+                    local.push("network_total_transactions")
+                }
+                CheckpointFilter::Subquery(_) => {}
+            }
+        }
+        self.filters(&pushed_down, &local);
+
+        Ok(())
+    }
+
+    fn plan_account(&mut self, account: &Account) {
+        let addresses = account.addresses();
+        let count = addresses.len() as u64;
+        let names = addresses
+            .iter()
+            .filter(|address| matches!(address, NameOrAddress::Name(_)))
+            .count() as u64;
+
+        if names > 0 {
+            self.call(
+                "resolve names",
+                "suix_resolveNameServiceAddress",
+                Some(names),
+                "SuiNS names to addresses, sent concurrently",
+            );
+        }
+        self.call(
+            "fetch stakes",
+            "suix_getStakes",
+            Some(count),
+            "one request per account, sent concurrently",
+        );
+        self.call(
+            "fetch balances",
+            "suix_getAllBalances",
+            Some(count),
+            "one request per account, sent concurrently",
+        );
+        if account.fields().contains(&AccountField::SuiBalance) {
+            self.call(
+                "fetch SUI balances",
+                "suix_getBalance",
+                Some(count),
+                "one request per account, sent concurrently",
+            );
+        }
+        self.filters(&["address"], &[]);
+    }
+
+    fn plan_coin(&mut self, coin: &Coin) {
+        let owners = coin.owners();
+        let mut pushed_down = vec![];
+        let mut local = vec![];
+
+        let coin_types = match owners.is_empty() {
+            true => {
+                let mut coin_types = coin.ids().cloned().unwrap_or_default();
+                coin_types.extend(coin.coin_type_filters().into_iter().filter_map(|filter| {
+                    match filter {
+                        EqualityFilter::Eq(coin_type) => Some(coin_type.clone()),
+                        EqualityFilter::Neq(_) => None,
+                    }
+                }));
+                Some(unique_ids(&coin_types).len() as u64)
+            }
+            false => {
+                self.call(
+                    "list coin types",
+                    "suix_getAllBalances",
+                    Some(owners.len() as u64),
+                    "the coin types held by every owner, one owner after the other",
+                );
+                None
+            }
+        };
+
+        self.call(
+            "fetch coin metadata",
+            "suix_getCoinMetadata",
+            coin_types,
+            "one request per coin type, sent concurrently",
+        );
+        let fields = coin.fields();
+        if fields.contains(&CoinField::TotalSupply) {
+            self.call(
+                "fetch total supplies",
+                "suix_getTotalSupply",
+                coin_types,
+                "one request per coin type",
+            );
+        }
+        if fields.contains(&CoinField::TreasuryCap) {
+            self.call(
+                "fetch coin packages",
+                "sui_getObject",
+                coin_types,
+                "one request per coin type, for the transaction that published it",
+            );
+            self.call(
+                "fetch publish transactions",
+                "sui_getTransactionBlock",
+                coin_types,
+                "one request per coin type, for the treasury cap it created",
+            );
+        }
+
+        if coin.ids().is_some() {
+            pushed_down.push("id");
+        }
+        for filter in coin.filter().into_iter().flatten() {
+            match filter {
+                CoinFilter::CoinType(EqualityFilter::Eq(_)) => pushed_down.push("type"),
+                CoinFilter::CoinType(EqualityFilter::Neq(_)) => local.push("type"),
+                CoinFilter::Owner(_) => pushed_down.push("owner"),
+                CoinFilter::Decimals(_) => local.push("decimals"),
+                CoinFilter::Subquery(_) => {}
+            }
+        }
+        self.filters(&pushed_down, &local);
+    }
+
+    fn plan_object(&mut self, object: &Object) {
+        let count = object.object_ids().len();
+        self.call(
+            "fetch objects",
+            "sui_multiGetObjects",
+            Some(1),
+            format!("{count} objects in a single request"),
+        );
+        self.filters(&["object_id"], &[]);
+    }
+
+    fn plan_name(&mut self, name: &Name) {
+        let mut pushed_down = vec![];
+        let mut local = vec![];
+
+        let mut names = name.ids().cloned().unwrap_or_default();
+        names.extend(
+            name.name_filters()
+                .into_iter()
+                .filter_map(|filter| match filter {
+                    EqualityFilter::Eq(name) => Some(name.clone()),
+                    EqualityFilter::Neq(_) => None,
+                }),
+        );
+        let addresses = name
+            .address_filters()
+            .into_iter()
+            .filter(|filter| matches!(filter, EqualityFilter::Eq(_)))
+            .count() as u64;
+
+        let count = match addresses {
+            0 => Some(unique_ids(&names).len() as u64),
+            addresses => {
+                self.call(
+                    "list names",
+                    "suix_resolveNameServiceNames",
+                    None,
+                    format!("the names of {addresses} addresses, one page after the other"),
+                );
+                let config = match &self.chain {
+                    Some(ChainOrRpc::Chain(chain)) => SuiNsConfig::for_chain(chain),
+                    _ => None,
+                };
+                if config.is_some() {
+                    self.call(
+                        "list registrations",
+                        "suix_getOwnedObjects",
+                        None,
+                        format!(
+                            "the SuiNS registrations of {addresses} addresses, \
+                             one page after the other"
+                        ),
+                    );
+                }
+                None
+            }
+        };
+
+        self.call(
+            "resolve names",
+            "suix_resolveNameServiceAddress",
+            count,
+            "one request per name, sent concurrently",
+        );
+        if name.fields().iter().any(NameField::is_record_field) {
+            self.call(
+                "fetch name records",
+                "suix_getDynamicFieldObject",
+                count,
+                "one request per name, from the SuiNS registry",
+            );
+        }
+
+        if name.ids().is_some() {
+            pushed_down.push("name");
+        }
+        for filter in name.name_filters() {
+            match filter {
+                EqualityFilter::Eq(_) => pushed_down.push("name"),
+                EqualityFilter::Neq(_) => local.push("name"),
+            }
+        }
+        for filter in name.address_filters() {
+            match filter {
+                EqualityFilter::Eq(_) => pushed_down.push("address"),
+                EqualityFilter::Neq(_) => local.push("address"),
+            }
+        }
+        self.filters(&pushed_down, &local);
+    }
+
+    async fn plan_join(&mut self, join: &Join) -> Result<()> {
+        self.plan_entity(join.source()).await?;
+
+        let target = join.target();
+        self.local(
+            "join",
+            format!(
+                "fetches {target} by `{}` once for every distinct `{}.{}` of the source rows, \
+                 like a {target} query by id",
+                target.id_field(),
+                join.source_entity(),
+                join.source_key(),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Plans the range of checkpoints the filters select, like `resolve_checkpoint_bounds`.
+    async fn checkpoint_range(
+        &mut self,
+        filters: &[FilterType<CheckpointNumberOrTag>],
+        epoch_filters: &[FilterType<u64>],
+        timestamp_bounds: Option<(Option<u64>, Option<u64>)>,
+    ) -> Result<PlannedRange> {
+        let mut resolved = vec![];
+        let mut is_known = true;
+        for filter in filters {
+            match self.checkpoint_number(filter.value()).await? {
+                Some(number) => resolved.push(filter.map(|_| number)),
+                None => is_known = false,
+            }
+        }
+
+        for filter in epoch_filters {
+            if matches!(filter, FilterType::Equality(EqualityFilter::Neq(_))) {
+                continue;
+            }
+            is_known = false;
+            self.call(
+                "resolve epoch",
+                "suix_getEpochs",
+                Some(1),
+                format!("the checkpoints of epoch {}", filter.value()),
+            );
+        }
+
+        if let Some((start, end)) = timestamp_bounds {
+            is_known = false;
+            let latest = self.latest().await?;
+            // Each bound is a binary search between genesis and the latest checkpoint.
+            let searches = u64::from(start.is_some()) + u64::from(end.is_some());
+            let steps = u64::from(u64::BITS - latest.leading_zeros());
+            self.call(
+                "find checkpoints by timestamp",
+                "sui_getCheckpoint",
+                Some(1 + searches * steps),
+                "at most this many, binary searching checkpoint timestamps one after the other",
+            );
+        }
+
+        if !is_known {
+            return Ok(PlannedRange::Unknown);
+        }
+
+        let latest = self.latest().await?;
+        Ok(match checkpoint_bounds(&resolved, latest) {
+            Some((start, end)) => PlannedRange::Checkpoints(start, end),
+            None => PlannedRange::Empty,
+        })
+    }
+
+    fn fetch_checkpoints(&mut self, range: &PlannedRange, purpose: &str) {
+        let purpose = match purpose {
+            "" => String::new(),
+            purpose => format!(", {purpose}"),
+        };
+        match range {
+            PlannedRange::Checkpoints(start, end) => self.call(
+                "fetch checkpoints",
+                "sui_getCheckpoint",
+                Some(end - start + 1),
+                format!("checkpoints {start}:{end}, {MAX_CONCURRENT_REQUESTS} at a time{purpose}"),
+            ),
+            PlannedRange::Empty => self.local(
+                "fetch checkpoints",
+                "the filters don't select any checkpoint, nothing is fetched",
+            ),
+            PlannedRange::Unknown => self.call(
+                "fetch checkpoints",
+                "sui_getCheckpoint",
+                None,
+                format!(
+                    "one request per checkpoint of the resolved range, \
+                     {MAX_CONCURRENT_REQUESTS} at a time{purpose}"
+                ),
+            ),
+        }
+    }
+
+    /// Resolves a checkpoint tag, like `get_checkpoint_number_from_tag`. Only `latest` is looked
+    /// up, epoch starts are left to the query.
+    async fn checkpoint_number(&mut self, number: &CheckpointNumberOrTag) -> Result<Option<u64>> {
+        let (tag, offset) = match *number {
+            CheckpointNumberOrTag::Number(number) => return Ok(Some(number)),
+            CheckpointNumberOrTag::Latest => (CheckpointTag::Latest, 0),
+            CheckpointNumberOrTag::Earliest => (CheckpointTag::Earliest, 0),
+            CheckpointNumberOrTag::EpochStart(epoch) => (CheckpointTag::EpochStart(epoch), 0),
+            CheckpointNumberOrTag::Relative(tag, offset) => (tag, offset),
+        };
+
+        let number = match tag {
+            CheckpointTag::Earliest => 0,
+            CheckpointTag::Latest => self.latest().await?,
+            CheckpointTag::EpochStart(epoch) => {
+                self.call(
+                    "resolve epoch start",
+                    "suix_getEpochs",
+                    Some(1),
+                    format!("the first checkpoint of epoch {epoch}"),
+                );
+                return Ok(None);
+            }
+        };
+        Ok(Some(number.saturating_add_signed(offset)))
+    }
+
+    /// The latest checkpoint of the chain, fetched once while planning.
+    async fn latest(&mut self) -> Result<u64> {
+        if let Some(latest) = self.latest {
+            return Ok(latest);
+        }
+
+        let chain = self.chain.as_ref().ok_or(ExplainError::MissingChain)?;
+        let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
+        let latest =
+            get_checkpoint_number_from_tag(&provider, &CheckpointNumberOrTag::Latest).await?;
+        self.latest = Some(latest);
+        self.call(
+            "resolve latest checkpoint",
+            "sui_getLatestCheckpointSequenceNumber",
+            Some(1),
+            format!("`latest` is checkpoint {latest}, sent while explaining"),
+        );
+        Ok(latest)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExplainError {
+    #[error("Checkpoint tags can only be resolved on a chain")]
+    MissingChain,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{common::types::Expression, interpreter::frontend::parser::Parser};
+
+    async fn explain(source: &str) -> Vec<PlanStep> {
+        let expressions = Parser::new(source).parse_expressions().unwrap();
+        let Some(Expression::Explain(get)) = expressions.first() else {
+            panic!("expected an EXPLAIN statement");
+        };
+        explain_query(get).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_explain_without_latest() {
+        let plan = explain(
+            "EXPLAIN SELECT address, sui_balance FROM account \
+             0xac5bceec1b789ff840d7d4e6ce4ce61c90d190a7f8c4f4ddf0bff6ee2413c33c, test.sui \
+             ON http://localhost:9000",
+        )
+        .await;
+        let calls = plan
+            .iter()
+            .map(|step| (step.rpc_method, step.requests))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            vec![
+                (Some("suix_resolveNameServiceAddress"), Some(1)),
+                (Some("suix_getStakes"), Some(2)),
+                (Some("suix_getAllBalances"), Some(2)),
+                (Some("suix_getBalance"), Some(2)),
+                (None, None),
+                (None, None),
+            ]
+        );
+        assert_eq!(plan[0].chain.as_deref(), Some("http://localhost:9000/"));
+
+        let plan = explain(
+            "EXPLAIN SELECT DISTINCT epoch FROM checkpoint 10:19, 15:24, 100 \
+             ON http://localhost:9000",
+        )
+        .await;
+        assert_eq!(plan[0].rpc_method, Some("sui_getCheckpoint"));
+        assert_eq!(plan[0].requests, Some(21));
+        assert_eq!(plan[1].detail, "number");
+        assert!(plan
+            .iter()
+            .all(|step| step.rpc_method.is_some() || step.requests.is_none()));
+        assert_eq!(plan.last().unwrap().operation, "drop repeated rows");
+    }
+}
@@ -1,7 +1,11 @@
 pub mod execution_engine;
+pub mod explain;
 mod resolve_account;
-mod resolve_checkpoint;
-mod resolve_coin;
+pub(crate) mod resolve_checkpoint;
+pub(crate) mod resolve_coin;
 mod resolve_event;
+mod resolve_join;
+pub(crate) mod resolve_name;
 mod resolve_object;
-mod resolve_transaction;
+mod resolve_subquery;
+pub(crate) mod resolve_transaction;
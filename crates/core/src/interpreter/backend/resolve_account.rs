@@ -1,6 +1,6 @@
 use crate::common::{
     account::{Account, AccountField},
-    chain::ChainOrRpc,
+    chain::{Chain, ChainOrRpc},
     name_services::NameOrAddress,
     query_result::AccountQueryRes,
 };
@@ -27,21 +27,21 @@ pub async fn resolve_account_query(
 
     for chain in chains {
         let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
+        let chain = chain.to_chain().await?;
 
-        // TODO: Handle filter
-        // TODO: Remove unwrap
-        for account_id in account.ids().unwrap() {
+        for account_id in account.addresses() {
             let fields = account.fields().clone();
             let provider = provider.clone();
+            let chain = chain.clone();
 
             let account_future = async move {
                 match account_id {
                     NameOrAddress::Address(address) => {
-                        get_account(address, fields, &provider, chain).await
+                        get_account(&address, fields, &provider, &chain).await
                     }
                     NameOrAddress::Name(name) => {
-                        let address = to_address(name).await?;
-                        get_account(&address, fields, &provider, chain).await
+                        let address = to_address(&name, &provider).await?;
+                        get_account(&address, fields, &provider, &chain).await
                     }
                 }
             };
@@ -58,10 +58,9 @@ async fn get_account(
     address: &SuiAddress,
     fields: Vec<AccountField>,
     provider: &SuiClient,
-    chain: &ChainOrRpc,
+    chain: &Chain,
 ) -> Result<AccountQueryRes> {
     let mut account = AccountQueryRes::default();
-    let chain = chain.to_chain().await?;
     let stakes = provider
         .governance_api()
         .get_stakes(*address)
@@ -111,8 +110,8 @@ async fn get_account(
     Ok(account)
 }
 
-async fn to_address(name: &String) -> Result<SuiAddress> {
-    let provider = SuiClientBuilder::default().build_mainnet().await.unwrap();
-    let address = NameOrAddress::Name(name.clone()).resolve(&provider).await?;
+/// Resolves a SuiNS name on the chain being queried.
+async fn to_address(name: &String, provider: &SuiClient) -> Result<SuiAddress> {
+    let address = NameOrAddress::Name(name.clone()).resolve(provider).await?;
     Ok(address)
 }
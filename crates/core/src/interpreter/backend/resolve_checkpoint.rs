@@ -1,17 +1,30 @@
 use crate::common::{
     chain::{Chain, ChainOrRpc},
     checkpoint::{
-        get_checkpoint_number_from_tag, Checkpoint, CheckpointField, CheckpointId,
-        CheckpointNumberOrTag,
+        get_checkpoint_number_from_tag, resolve_epoch_filters, Checkpoint, CheckpointField,
+        CheckpointId, CheckpointNumberOrTag, CheckpointRange,
     },
+    entity_id::unique_ids,
+    filters::{inclusive_bounds, FilterType},
     query_result::CheckpointQueryRes,
 };
 use anyhow::Result;
-use futures::future::try_join_all;
+use futures::{future::try_join_all, stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use sui_json_rpc_types::Checkpoint as RpcCheckpoint;
+use std::{collections::HashMap, ops::RangeInclusive};
+use sui_json_rpc_types::{Checkpoint as RpcCheckpoint, CheckpointId as SuiCheckpointId};
 use sui_sdk::{SuiClient, SuiClientBuilder};
-use sui_types::digests::CheckpointDigest;
+use sui_types::{
+    digests::{CheckpointDigest, TransactionDigest},
+    gas::GasCostSummary,
+    messages_checkpoint::CheckpointCommitment,
+};
+
+/// The most checkpoint requests sent to an RPC at once.
+pub const MAX_CONCURRENT_REQUESTS: usize = 50;
+
+/// The most checkpoints fetched before the ones already fetched are filtered.
+pub const CHECKPOINT_PAGE_SIZE: u64 = 1_000;
 
 #[derive(Debug, Serialize, Deserialize, thiserror::Error)]
 pub enum CheckpointResolverErrors {
@@ -19,11 +32,17 @@ pub enum CheckpointResolverErrors {
     UnableToFetchCheckpointNumber(CheckpointNumberOrTag),
     #[error("Mismatch between Entity and EntityId, {0} can't be resolved as a block id")]
     MismatchEntityAndEntityId(String),
-    #[error("Missing block ids")]
-    IdsNotSet,
+    #[error(
+        "Query should either provide checkpoint ids or a `checkpoint`, `epoch` or `timestamp_ms` \
+         filter, filters on other fields only narrow the fetched checkpoints down"
+    )]
+    MissingCheckpointIdsOrRange,
 }
 
-async fn resolve_checkpoint_id(id: &CheckpointId, provider: &SuiClient) -> Result<Vec<u64>> {
+async fn resolve_checkpoint_id(
+    id: &CheckpointId,
+    provider: &SuiClient,
+) -> Result<RangeInclusive<u64>> {
     let block_numbers = match id {
         CheckpointId::Range(checkpoint_range) => {
             checkpoint_range
@@ -31,7 +50,8 @@ async fn resolve_checkpoint_id(id: &CheckpointId, provider: &SuiClient) -> Resul
                 .await?
         }
         CheckpointId::Number(checkpoint_number) => {
-            resolve_cheeckpoint_numbers(&[checkpoint_number.clone()], provider).await?
+            let number = get_checkpoint_number_from_tag(provider, checkpoint_number).await?;
+            number..=number
         }
     };
 
@@ -44,28 +64,74 @@ pub async fn resolve_checkpoint_query(
 ) -> Result<Vec<CheckpointQueryRes>> {
     let mut all_chain_futures = Vec::new();
 
-    let ids = match checkpoints.ids() {
-        Some(ids) => ids,
-        None => return Err(CheckpointResolverErrors::IdsNotSet.into()),
-    };
+    let ids = unique_ids(checkpoints.ids().cloned().unwrap_or_default());
+    if ids.is_empty() && !checkpoints.has_range_filter() {
+        return Err(CheckpointResolverErrors::MissingCheckpointIdsOrRange.into());
+    }
+    let ids = &ids;
+    let timestamp_filters = &checkpoints.timestamp_filters()?;
 
     for chain in chains {
-        let fields = checkpoints.fields().clone();
-
         let chain_future = async move {
             let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
             let chain = chain.to_chain().await?;
             let mut all_checkpoint_futures = Vec::new();
+            let mut ids = ids.clone();
+
+            let mut checkpoint_filters = checkpoints.checkpoint_filters();
+            checkpoint_filters
+                .extend(resolve_epoch_filters(&checkpoints.epoch_filters(), &provider).await?);
+            let timestamp_bounds = checkpoints
+                .has_timestamp_filter()
+                .then(|| inclusive_bounds(timestamp_filters));
+            let bounds = if checkpoint_filters.is_empty() && timestamp_bounds.is_none() {
+                None
+            } else {
+                match resolve_checkpoint_bounds(checkpoint_filters, timestamp_bounds, &provider)
+                    .await?
+                {
+                    Some(range) => Some(range),
+                    None => return Ok(vec![]),
+                }
+            };
+
+            if ids.is_empty() {
+                if let Some(range) = &bounds {
+                    ids.push(CheckpointId::Range(range.clone()));
+                }
+            }
 
             for id in ids {
                 let chain_clone = chain.clone();
                 let provider_clone = provider.clone();
-                let fields = fields.clone();
 
-                let checkpoint_id = resolve_checkpoint_id(&id, &provider_clone).await?;
+                let mut checkpoint_id = resolve_checkpoint_id(&id, &provider_clone).await?;
+                // Explicit ids are narrowed down to the checkpoints the filters allow.
+                if let Some(range) = &bounds {
+                    let (start, end) = range.range();
+                    let start = start.as_number().unwrap_or(0);
+                    let end = end.and_then(|end| end.as_number()).unwrap_or(u64::MAX);
+                    checkpoint_id =
+                        (*checkpoint_id.start()).max(start)..=(*checkpoint_id.end()).min(end);
+                }
+                // Filters bounded from one side only, like `checkpoint >= 1000` or
+                // `timestamp > now() - 30d`, can span millions of checkpoints up to `latest`.
+                // They are fetched and filtered a page at a time, keeping only the matches.
                 let checkpoint_future = async move {
-                    get_filtered_checkpoints(checkpoint_id, fields, &provider_clone, &chain_clone)
-                        .await
+                    let mut rows = Vec::new();
+                    for page in checkpoint_pages(checkpoint_id, CHECKPOINT_PAGE_SIZE) {
+                        rows.extend(
+                            get_filtered_checkpoints(
+                                page,
+                                checkpoints,
+                                timestamp_filters,
+                                &provider_clone,
+                                &chain_clone,
+                            )
+                            .await?,
+                        );
+                    }
+                    Ok::<Vec<CheckpointQueryRes>, anyhow::Error>(rows)
                 };
                 all_checkpoint_futures.push(checkpoint_future);
             }
@@ -82,60 +148,212 @@ pub async fn resolve_checkpoint_query(
 }
 
 async fn get_filtered_checkpoints(
-    checkpoint_numbers: Vec<u64>,
-    fields: Vec<CheckpointField>,
+    checkpoint_numbers: RangeInclusive<u64>,
+    checkpoints: &Checkpoint,
+    timestamp_filters: &[FilterType<u64>],
     provider: &SuiClient,
     chain: &Chain,
 ) -> Result<Vec<CheckpointQueryRes>> {
     let checkpoint = batch_get_checkpoints(checkpoint_numbers, provider).await?;
+    let gas_costs = if checkpoints.uses_gas_costs() {
+        get_checkpoint_gas_costs(&checkpoint, provider).await?
+    } else {
+        HashMap::new()
+    };
+
     Ok(checkpoint
         .into_iter()
-        .map(|checkpoint| filter_fields(checkpoint, &fields, &chain))
+        .filter_map(|checkpoint| {
+            let gas_cost = gas_costs
+                .get(&checkpoint.sequence_number)
+                .cloned()
+                .unwrap_or_default();
+            checkpoints
+                .filter(&checkpoint, &gas_cost, timestamp_filters)
+                .then(|| filter_fields(checkpoint, &gas_cost, checkpoints.fields(), &chain))
+        })
+        .flatten()
         .collect())
 }
 
-// TODO: this method only exists here because it wasn't implemented on the BlockId struct yet.
-// BlockRange has a similar implementation and should be unified.
-async fn resolve_cheeckpoint_numbers(
-    checkpoint_numbers: &[CheckpointNumberOrTag],
+/// Computes the gas costs of the transactions in each checkpoint. Checkpoints only carry the
+/// rolling totals of their epoch, so a checkpoint's own costs are the difference with the
+/// previous checkpoint, unless it is the first checkpoint of its epoch.
+async fn get_checkpoint_gas_costs(
+    checkpoints: &[RpcCheckpoint],
     provider: &SuiClient,
-) -> Result<Vec<u64>> {
-    let mut checkpoint_number_futures = Vec::new();
+) -> Result<HashMap<u64, GasCostSummary>> {
+    let mut rolling_gas_costs: HashMap<u64, (u64, GasCostSummary)> = checkpoints
+        .iter()
+        .map(|c| {
+            (
+                c.sequence_number,
+                (c.epoch, c.epoch_rolling_gas_cost_summary.clone()),
+            )
+        })
+        .collect();
 
-    for checkpoint_number in checkpoint_numbers {
-        let block_number_future =
-            async move { get_checkpoint_number_from_tag(provider, checkpoint_number).await };
-        checkpoint_number_futures.push(block_number_future);
+    // Previous checkpoints that are not part of the query are fetched separately.
+    let missing_checkpoints: Vec<u64> = checkpoints
+        .iter()
+        .filter_map(|c| c.sequence_number.checked_sub(1))
+        .filter(|number| !rolling_gas_costs.contains_key(number))
+        .collect();
+    for c in batch_get_checkpoints(missing_checkpoints, provider).await? {
+        rolling_gas_costs.insert(
+            c.sequence_number,
+            (c.epoch, c.epoch_rolling_gas_cost_summary),
+        );
     }
 
-    let chepoint_numbers = try_join_all(checkpoint_number_futures).await?;
-    Ok(chepoint_numbers)
+    Ok(checkpoints
+        .iter()
+        .map(|checkpoint| {
+            let current = &checkpoint.epoch_rolling_gas_cost_summary;
+            let previous = checkpoint
+                .sequence_number
+                .checked_sub(1)
+                .and_then(|number| rolling_gas_costs.get(&number))
+                .filter(|(epoch, _)| *epoch == checkpoint.epoch);
+
+            let gas_cost = checkpoint_gas_cost(current, previous.map(|(_, previous)| previous));
+            (checkpoint.sequence_number, gas_cost)
+        })
+        .collect())
 }
 
-pub async fn batch_get_checkpoints(
-    checkpoint_numbers: Vec<u64>,
+/// Computes the gas costs of a checkpoint's own transactions from the rolling totals of its
+/// epoch, `previous` being the totals at the previous checkpoint of the same epoch.
+fn checkpoint_gas_cost(
+    current: &GasCostSummary,
+    previous: Option<&GasCostSummary>,
+) -> GasCostSummary {
+    match previous {
+        Some(previous) => GasCostSummary::new(
+            current
+                .computation_cost
+                .saturating_sub(previous.computation_cost),
+            current.storage_cost.saturating_sub(previous.storage_cost),
+            current
+                .storage_rebate
+                .saturating_sub(previous.storage_rebate),
+            current
+                .non_refundable_storage_fee
+                .saturating_sub(previous.non_refundable_storage_fee),
+        ),
+        None => current.clone(),
+    }
+}
+
+/// Combines `checkpoint` filters and, when set, the inclusive timestamp bounds into the single
+/// range of checkpoints a query has to fetch. Returns `None` when the filters don't overlap.
+pub async fn resolve_checkpoint_bounds(
+    mut checkpoint_filters: Vec<FilterType<CheckpointNumberOrTag>>,
+    timestamp_bounds: Option<(Option<u64>, Option<u64>)>,
     provider: &SuiClient,
-) -> Result<Vec<RpcCheckpoint>> {
-    let mut checkpoin_futures = Vec::new();
+) -> Result<Option<CheckpointRange>> {
+    if let Some((start_ms, end_ms)) = timestamp_bounds {
+        match resolve_timestamp_range(start_ms, end_ms, provider).await? {
+            Some(range) => checkpoint_filters.extend(range.to_filters()),
+            None => return Ok(None),
+        }
+    }
 
-    for checkpoint_number in checkpoint_numbers {
-        let checkpoint_future = async move {
-            get_checkpoint(CheckpointNumberOrTag::Number(checkpoint_number), provider).await
-        };
-        checkpoin_futures.push(checkpoint_future);
+    CheckpointRange::from_filters(&checkpoint_filters, provider).await
+}
+
+/// Resolves inclusive timestamp bounds, in milliseconds, into the range of checkpoints created
+/// within them. Checkpoint timestamps only grow, so each bound is found by binary-searching
+/// checkpoint timestamps between genesis and the latest checkpoint instead of scanning.
+/// Returns `None` when no checkpoint falls within the bounds.
+pub async fn resolve_timestamp_range(
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+    provider: &SuiClient,
+) -> Result<Option<CheckpointRange>> {
+    let latest = get_checkpoint_number_from_tag(provider, &CheckpointNumberOrTag::Latest).await?;
+    let latest_timestamp = get_checkpoint(latest.into(), provider).await?.timestamp_ms;
+
+    let start = match start_ms {
+        Some(start_ms) if start_ms > latest_timestamp => return Ok(None),
+        Some(start_ms) => find_first_checkpoint_at(start_ms, 0, latest, provider).await?,
+        None => 0,
+    };
+
+    let end = match end_ms {
+        Some(end_ms) if end_ms >= latest_timestamp => latest,
+        Some(end_ms) => {
+            // The range ends right before the first checkpoint created after the end bound.
+            let after_end = find_first_checkpoint_at(end_ms + 1, start, latest, provider).await?;
+            match after_end.checked_sub(1) {
+                Some(end) => end,
+                None => return Ok(None),
+            }
+        }
+        None => latest,
+    };
+
+    if start > end {
+        return Ok(None);
     }
 
-    let checkpoint_results = try_join_all(checkpoin_futures).await?;
-    Ok(checkpoint_results)
+    Ok(Some(CheckpointRange::new(start.into(), Some(end.into()))))
+}
+
+/// Binary-searches `[low, high]` for the first checkpoint with a timestamp greater than or equal
+/// to `timestamp_ms`. The checkpoint at `high` is expected to satisfy the condition.
+async fn find_first_checkpoint_at(
+    timestamp_ms: u64,
+    mut low: u64,
+    mut high: u64,
+    provider: &SuiClient,
+) -> Result<u64> {
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let checkpoint = get_checkpoint(CheckpointNumberOrTag::Number(mid), provider).await?;
+
+        if checkpoint.timestamp_ms < timestamp_ms {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Fetches checkpoints in order, with at most [`MAX_CONCURRENT_REQUESTS`] requests in flight.
+pub async fn batch_get_checkpoints(
+    checkpoint_numbers: impl IntoIterator<Item = u64>,
+    provider: &SuiClient,
+) -> Result<Vec<RpcCheckpoint>> {
+    stream::iter(checkpoint_numbers)
+        .map(|checkpoint_number| {
+            get_checkpoint(CheckpointNumberOrTag::Number(checkpoint_number), provider)
+        })
+        .buffered(MAX_CONCURRENT_REQUESTS)
+        .try_collect()
+        .await
+}
+
+/// Splits a range of checkpoints into pages of at most `page_size` checkpoints, so a large
+/// range is fetched and filtered a page at a time.
+pub fn checkpoint_pages(
+    range: RangeInclusive<u64>,
+    page_size: u64,
+) -> impl Iterator<Item = RangeInclusive<u64>> {
+    let end = *range.end();
+    range
+        .step_by(page_size as usize)
+        .map(move |start| start..=start.saturating_add(page_size - 1).min(end))
 }
 
 pub async fn get_checkpoint(
     checkpoint_id: CheckpointNumberOrTag,
     provider: &SuiClient,
 ) -> Result<RpcCheckpoint> {
-    let sui_checkpoint_id = checkpoint_id.to_sui_checkpoint_id().ok_or(
-        CheckpointResolverErrors::UnableToFetchCheckpointNumber(checkpoint_id.clone()),
-    )?;
+    let checkpoint_number = get_checkpoint_number_from_tag(provider, &checkpoint_id).await?;
+    let sui_checkpoint_id = SuiCheckpointId::SequenceNumber(checkpoint_number);
     let checkpoint = provider
         .read_api()
         .get_checkpoint(sui_checkpoint_id)
@@ -146,11 +364,14 @@ pub async fn get_checkpoint(
     Ok(checkpoint)
 }
 
+/// Picks the selected fields of a checkpoint. Selecting `transaction_digest` expands the
+/// checkpoint into one row per transaction.
 fn filter_fields(
     checkpoint: RpcCheckpoint,
+    gas_cost: &GasCostSummary,
     fields: &[CheckpointField],
     chain: &Chain,
-) -> CheckpointQueryRes {
+) -> Vec<CheckpointQueryRes> {
     let mut result = CheckpointQueryRes::default();
 
     for field in fields {
@@ -171,22 +392,16 @@ fn filter_fields(
                 result.digest = Some(checkpoint.digest.base58_encode());
             }
             CheckpointField::ComputationCost => {
-                result.computation_cost =
-                    Some(checkpoint.epoch_rolling_gas_cost_summary.storage_cost);
+                result.computation_cost = Some(gas_cost.computation_cost);
             }
             CheckpointField::StorageCost => {
-                result.storage_cost = Some(checkpoint.epoch_rolling_gas_cost_summary.storage_cost);
+                result.storage_cost = Some(gas_cost.storage_cost);
             }
             CheckpointField::StorageRebate => {
-                result.storage_rebate =
-                    Some(checkpoint.epoch_rolling_gas_cost_summary.storage_rebate);
+                result.storage_rebate = Some(gas_cost.storage_rebate);
             }
             CheckpointField::NonRefundableStorageFee => {
-                result.non_refundable_storage_fee = Some(
-                    checkpoint
-                        .epoch_rolling_gas_cost_summary
-                        .non_refundable_storage_fee,
-                );
+                result.non_refundable_storage_fee = Some(gas_cost.non_refundable_storage_fee);
             }
             CheckpointField::PreviousDigest => {
                 let previous_digest = checkpoint
@@ -204,10 +419,61 @@ fn filter_fields(
             CheckpointField::NetworkTotalTransactions => {
                 result.network_total_transactions = Some(checkpoint.network_total_transactions);
             }
+            CheckpointField::TransactionDigests => {
+                result.transaction_digests = Some(
+                    checkpoint
+                        .transactions
+                        .iter()
+                        .map(|digest| digest.base58_encode())
+                        .collect(),
+                );
+            }
+            // Filled in below, once per transaction.
+            CheckpointField::TransactionDigest => {}
+            CheckpointField::EndOfEpoch => {
+                result.end_of_epoch = Some(checkpoint.end_of_epoch_data.is_some());
+            }
+            CheckpointField::NextEpochProtocolVersion => {
+                result.next_epoch_protocol_version = checkpoint
+                    .end_of_epoch_data
+                    .as_ref()
+                    .map(|data| data.next_epoch_protocol_version.as_u64());
+            }
+            CheckpointField::CheckpointCommitments => {
+                result.checkpoint_commitments = Some(
+                    checkpoint
+                        .checkpoint_commitments
+                        .iter()
+                        .map(|commitment| match commitment {
+                            CheckpointCommitment::ECMHLiveObjectSetDigest(digest) => {
+                                digest.digest.to_string()
+                            }
+                        })
+                        .collect(),
+                );
+            }
         }
     }
 
-    result
+    if !fields.contains(&CheckpointField::TransactionDigest) {
+        return vec![result];
+    }
+
+    transaction_rows(result, &checkpoint.transactions)
+}
+
+/// Repeats the row of a checkpoint for each of its transactions, with `transaction_digest` set.
+fn transaction_rows(
+    result: CheckpointQueryRes,
+    digests: &[TransactionDigest],
+) -> Vec<CheckpointQueryRes> {
+    digests
+        .iter()
+        .map(|digest| CheckpointQueryRes {
+            transaction_digest: Some(digest.base58_encode()),
+            ..result.clone()
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -238,4 +504,69 @@ mod tests {
             "Start checkpoint must be less than end checkpoint"
         );
     }
+
+    #[test]
+    fn test_checkpoint_gas_cost() {
+        let previous = GasCostSummary::new(1_000, 5_000, 2_000, 20);
+        let current = GasCostSummary::new(1_500, 5_800, 2_100, 25);
+
+        assert_eq!(
+            checkpoint_gas_cost(&current, Some(&previous)),
+            GasCostSummary::new(500, 800, 100, 5)
+        );
+        // The first checkpoint of an epoch starts the rolling totals over.
+        assert_eq!(checkpoint_gas_cost(&current, None), current);
+        // Totals never go down within an epoch, a lower total doesn't underflow.
+        assert_eq!(
+            checkpoint_gas_cost(&previous, Some(&current)),
+            GasCostSummary::new(0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_transaction_rows() {
+        let digests = [
+            "5PqUjTz5YAcWuVoF9a6iGUnyKmrHKzuS7DTxDEB9V8Qn",
+            "11111111111111111111111111111111",
+        ]
+        .map(|digest| digest.parse::<TransactionDigest>().unwrap());
+        let result = CheckpointQueryRes {
+            number: Some(10),
+            transaction_digests: Some(digests.iter().map(|d| d.base58_encode()).collect()),
+            ..Default::default()
+        };
+
+        let rows = transaction_rows(result.clone(), &digests);
+        assert_eq!(rows.len(), 2);
+        for (row, digest) in rows.iter().zip(&digests) {
+            assert_eq!(row.number, Some(10));
+            assert_eq!(row.transaction_digest, Some(digest.base58_encode()));
+            assert_eq!(row.transaction_digests, result.transaction_digests);
+        }
+        assert!(transaction_rows(result, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_pages() {
+        let pages = checkpoint_pages(10..=35, 10).collect::<Vec<_>>();
+        assert_eq!(pages, vec![10..=19, 20..=29, 30..=35]);
+        assert_eq!(checkpoint_pages(5..=5, 10).collect::<Vec<_>>(), vec![5..=5]);
+        assert_eq!(
+            checkpoint_pages(u64::MAX - 1..=u64::MAX, 10).collect::<Vec<_>>(),
+            vec![u64::MAX - 1..=u64::MAX]
+        );
+        assert_eq!(checkpoint_pages(10..=5, 10).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_error_when_no_ids_or_range_filter() {
+        let chain = ChainOrRpc::Chain(Chain::Mainnet);
+        let block = Checkpoint::new(Some(vec![]), None, vec![]);
+
+        let result = resolve_checkpoint_query(&block, &[chain]).await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            CheckpointResolverErrors::MissingCheckpointIdsOrRange.to_string()
+        );
+    }
 }
@@ -1,70 +1,338 @@
 use crate::common::{
-    chain::ChainOrRpc,
+    chain::{Chain, ChainOrRpc},
     coin::{Coin, CoinField},
+    filters::EqualityFilter,
     query_result::CoinQueryRes,
 };
 use anyhow::{anyhow, Result};
 use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use sui_json_rpc_types::{ObjectChange, SuiObjectDataOptions, SuiTransactionBlockResponseOptions};
 use sui_sdk::{SuiClient, SuiClientBuilder};
+use sui_types::{base_types::ObjectID, coin::TreasuryCap, parse_sui_struct_tag};
+
+#[derive(Debug, Serialize, Deserialize, thiserror::Error)]
+pub enum CoinResolverErrors {
+    #[error("Query should either provide coin types or an owner filter")]
+    MissingCoinTypeOrOwner,
+}
 
 pub async fn resolve_coin_query(coin: &Coin, chains: &[ChainOrRpc]) -> Result<Vec<CoinQueryRes>> {
+    let has_coin_type = coin.ids().is_some()
+        || coin
+            .coin_type_filters()
+            .iter()
+            .any(|filter| matches!(filter, EqualityFilter::Eq(_)));
+    if !has_coin_type && coin.owners().is_empty() {
+        return Err(CoinResolverErrors::MissingCoinTypeOrOwner.into());
+    }
+
     let mut all_coins_futures = Vec::new();
 
     for chain in chains {
         let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
+        let chain = chain.to_chain().await?;
 
-        // TODO: Handle filter
-        // TODO: Remove unwrap
-        for coin_id in coin.ids().unwrap() {
-            let fields = coin.fields().clone();
+        for coin_type in resolve_coin_types(coin, &provider).await? {
             let provider = provider.clone();
+            let chain = chain.clone();
 
-            let coin_future = async move { get_coin(coin_id, fields, &provider, chain).await };
+            let coin_future = async move { get_coin(&coin_type, coin, &provider, &chain).await };
 
             all_coins_futures.push(coin_future);
         }
     }
 
     let coin_res = try_join_all(all_coins_futures).await?;
-    Ok(coin_res)
+    Ok(coin_res.into_iter().flatten().collect())
+}
+
+/// Fetches the coin types held by the `owner` filters and selects the coin types of the query.
+async fn resolve_coin_types(coin: &Coin, provider: &SuiClient) -> Result<Vec<String>> {
+    let mut owned_coin_types = Vec::new();
+    for owner in coin.owners() {
+        let balances = provider.coin_read_api().get_all_balances(owner).await?;
+        owned_coin_types.push(
+            balances
+                .into_iter()
+                .map(|balance| balance.coin_type)
+                .collect(),
+        );
+    }
+
+    Ok(select_coin_types(coin, owned_coin_types))
+}
+
+/// Lists the coin types a query is about: the explicit ids and `type = ...` filters, or the
+/// coin types held by the `owner` filters, `owned_coin_types` holding those of each owner.
+/// All the `type` and `owner` filters have to match.
+fn select_coin_types(coin: &Coin, owned_coin_types: Vec<Vec<String>>) -> Vec<String> {
+    let mut coin_types: Vec<String> = coin.ids().cloned().unwrap_or_default();
+    coin_types.extend(
+        coin.coin_type_filters()
+            .into_iter()
+            .filter_map(|filter| match filter {
+                EqualityFilter::Eq(coin_type) => Some(coin_type.clone()),
+                EqualityFilter::Neq(_) => None,
+            }),
+    );
+
+    for owned in owned_coin_types {
+        coin_types = if coin_types.is_empty() {
+            owned
+        } else {
+            coin_types
+                .into_iter()
+                .filter(|coin_type| owned.iter().any(|owned| same_coin_type(coin_type, owned)))
+                .collect()
+        };
+    }
+
+    let mut unique_coin_types: Vec<String> = Vec::new();
+    for coin_type in coin_types {
+        let matches_filters = coin.coin_type_filters().iter().all(|filter| match filter {
+            EqualityFilter::Eq(other) => same_coin_type(other, &coin_type),
+            EqualityFilter::Neq(other) => !same_coin_type(other, &coin_type),
+        });
+        let is_duplicate = unique_coin_types
+            .iter()
+            .any(|unique| same_coin_type(unique, &coin_type));
+
+        if matches_filters && !is_duplicate {
+            unique_coin_types.push(coin_type);
+        }
+    }
+
+    unique_coin_types
+}
+
+/// Compares coin types by their struct tags, so that `0x2::sui::SUI` and its long form match.
+fn same_coin_type(a: &str, b: &str) -> bool {
+    match (parse_sui_struct_tag(a), parse_sui_struct_tag(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
 }
 
 async fn get_coin(
     coin_id: &String,
-    fields: Vec<CoinField>,
+    coin: &Coin,
     provider: &SuiClient,
-    chain: &ChainOrRpc,
-) -> Result<CoinQueryRes> {
-    let mut coin = CoinQueryRes::default();
-    let chain = chain.to_chain().await?;
+    chain: &Chain,
+) -> Result<Option<CoinQueryRes>> {
+    let mut coin_res = CoinQueryRes::default();
     let coin_result = provider
         .coin_read_api()
         .get_coin_metadata(coin_id.clone())
         .await?
         .ok_or_else(|| anyhow!("Coin metadata not found for ID: {}", coin_id))?;
 
-    for field in &fields {
+    if !coin.matches_decimals(coin_result.decimals) {
+        return Ok(None);
+    }
+
+    for field in &coin.fields() {
         match field {
+            CoinField::Id => {
+                coin_res.id = Some(coin_id.clone());
+            }
             CoinField::Decimals => {
-                coin.decimals = Some(coin_result.decimals);
+                coin_res.decimals = Some(coin_result.decimals);
             }
             CoinField::Name => {
-                coin.name = Some(coin_result.name.clone());
+                coin_res.name = Some(coin_result.name.clone());
             }
             CoinField::Symbol => {
-                coin.symbol = Some(coin_result.symbol.clone());
+                coin_res.symbol = Some(coin_result.symbol.clone());
             }
             CoinField::Description => {
-                coin.description = Some(coin_result.description.clone());
+                coin_res.description = Some(coin_result.description.clone());
             }
             CoinField::IconUrl => {
-                coin.icon_url = coin_result.icon_url.clone();
+                coin_res.icon_url = coin_result.icon_url.clone();
             }
             CoinField::Chain => {
-                coin.chain = Some(chain.clone());
+                coin_res.chain = Some(chain.clone());
             }
+            CoinField::TotalSupply => {
+                let supply = provider
+                    .coin_read_api()
+                    .get_total_supply(coin_id.clone())
+                    .await?;
+                coin_res.total_supply = Some(supply.value);
+            }
+            CoinField::TreasuryCap => {
+                coin_res.treasury_cap = get_treasury_cap(coin_id, provider)
+                    .await?
+                    .map(|id| id.to_string());
+            }
+        }
+    }
+
+    Ok(Some(coin_res))
+}
+
+/// Finds the `TreasuryCap<T>` of a coin. Coins are usually created in the `init` function of
+/// their package, so the treasury cap is among the objects created by the publish transaction.
+/// Returns `None` for coins whose treasury cap was created later, or that don't have one (SUI).
+async fn get_treasury_cap(coin_type: &str, provider: &SuiClient) -> Result<Option<ObjectID>> {
+    let struct_tag = parse_sui_struct_tag(coin_type)?;
+
+    let package = provider
+        .read_api()
+        .get_object_with_options(
+            ObjectID::from(struct_tag.address),
+            SuiObjectDataOptions::new().with_previous_transaction(),
+        )
+        .await?;
+    let publish_transaction = match package.data.and_then(|data| data.previous_transaction) {
+        Some(digest) => digest,
+        None => return Ok(None),
+    };
+
+    let transaction = provider
+        .read_api()
+        .get_transaction_with_options(
+            publish_transaction,
+            SuiTransactionBlockResponseOptions::new().with_object_changes(),
+        )
+        .await?;
+
+    find_treasury_cap(transaction.object_changes.unwrap_or_default(), coin_type)
+}
+
+/// Finds the `TreasuryCap<T>` of a coin among the objects created by a transaction.
+fn find_treasury_cap(
+    object_changes: Vec<ObjectChange>,
+    coin_type: &str,
+) -> Result<Option<ObjectID>> {
+    let treasury_cap_type = TreasuryCap::type_(parse_sui_struct_tag(coin_type)?);
+
+    Ok(object_changes.into_iter().find_map(|change| match change {
+        ObjectChange::Created {
+            object_type,
+            object_id,
+            ..
+        } if object_type == treasury_cap_type => Some(object_id),
+        _ => None,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        common::{
+            entity::Entity,
+            types::{Expression, GetExpression},
+        },
+        interpreter::frontend::parser::Parser,
+    };
+    use sui_types::{
+        base_types::{SequenceNumber, SuiAddress},
+        digests::ObjectDigest,
+        object::Owner,
+    };
+
+    const SUI: &str = "0x2::sui::SUI";
+    const LONG_SUI: &str =
+        "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+    const USDC: &str =
+        "0xdba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+    const OWNER: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+    fn coin(source: &str) -> Coin {
+        let expressions = Parser::new(source).parse_expressions().unwrap();
+        match expressions.into_iter().next() {
+            Some(Expression::Get(GetExpression {
+                entity: Entity::Coin(coin),
+                ..
+            })) => coin,
+            _ => panic!("expected a coin query"),
         }
     }
 
-    Ok(coin)
+    fn owned(coin_types: &[&str]) -> Vec<String> {
+        coin_types
+            .iter()
+            .map(|coin_type| coin_type.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_coin_types_of_owner() {
+        let query = coin(&format!(
+            "SELECT * FROM coin WHERE owner = {OWNER} ON sui_mainnet"
+        ));
+        assert_eq!(
+            select_coin_types(&query, vec![owned(&[LONG_SUI, USDC])]),
+            owned(&[LONG_SUI, USDC])
+        );
+
+        let query = coin(&format!(
+            "SELECT * FROM coin WHERE owner = {OWNER}, type != '{SUI}' ON sui_mainnet"
+        ));
+        assert_eq!(
+            select_coin_types(&query, vec![owned(&[LONG_SUI, USDC])]),
+            owned(&[USDC])
+        );
+
+        // Every owner has to hold the coin type.
+        let query = coin(&format!(
+            "SELECT * FROM coin WHERE owner = {OWNER}, owner = {OWNER} ON sui_mainnet"
+        ));
+        assert_eq!(
+            select_coin_types(&query, vec![owned(&[SUI, USDC]), owned(&[LONG_SUI])]),
+            owned(&[SUI])
+        );
+    }
+
+    #[test]
+    fn test_coin_types_of_type_filters() {
+        let query = coin(&format!(
+            "SELECT * FROM coin '{SUI}', '{LONG_SUI}', '{USDC}' ON sui_mainnet"
+        ));
+        assert_eq!(select_coin_types(&query, vec![]), owned(&[SUI, USDC]));
+
+        let query = coin(&format!(
+            "SELECT * FROM coin WHERE type = '{USDC}', owner = {OWNER} ON sui_mainnet"
+        ));
+        assert_eq!(
+            select_coin_types(&query, vec![owned(&[SUI, USDC])]),
+            owned(&[USDC])
+        );
+        assert_eq!(select_coin_types(&query, vec![owned(&[SUI])]), owned(&[]));
+
+        // The coin type has to match every `type` filter.
+        let query = coin(&format!(
+            "SELECT * FROM coin WHERE type = '{SUI}', type = '{USDC}' ON sui_mainnet"
+        ));
+        assert_eq!(select_coin_types(&query, vec![]), owned(&[]));
+    }
+
+    #[test]
+    fn test_find_treasury_cap() {
+        let created = |object_type: &str, object_id: ObjectID| ObjectChange::Created {
+            sender: SuiAddress::ZERO,
+            owner: Owner::Immutable,
+            object_type: parse_sui_struct_tag(object_type).unwrap(),
+            object_id,
+            version: SequenceNumber::new(),
+            digest: ObjectDigest::MIN,
+        };
+        let treasury_cap = ObjectID::from_single_byte(7);
+        let object_changes = vec![
+            created(
+                &format!("0x2::coin::CoinMetadata<{USDC}>"),
+                ObjectID::from_single_byte(6),
+            ),
+            created(&format!("0x2::coin::TreasuryCap<{USDC}>"), treasury_cap),
+        ];
+
+        assert_eq!(
+            find_treasury_cap(object_changes.clone(), USDC).unwrap(),
+            Some(treasury_cap)
+        );
+        assert_eq!(find_treasury_cap(object_changes, SUI).unwrap(), None);
+    }
 }
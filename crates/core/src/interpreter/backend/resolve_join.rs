@@ -0,0 +1,93 @@
+use super::resolve_subquery::{resolve_rows, Row};
+use crate::common::{
+    chain::ChainOrRpc, entity_id::unique_ids, join::Join, query_result::RowQueryRes,
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Resolve a join query. For each chain, the source rows are fetched first, then the joined
+/// entity is fetched once for every distinct join key, and the rows are matched on that key.
+pub async fn resolve_join_query(join: &Join, chains: &[ChainOrRpc]) -> Result<Vec<RowQueryRes>> {
+    let mut join_res = Vec::new();
+    let target = join.target();
+    let source_key = join.source_entity().serialized_field(join.source_key());
+    let target_key = target.serialized_field(target.id_field());
+
+    for chain in chains {
+        let chain = std::slice::from_ref(chain);
+        let source_rows = resolve_rows(join.source(), chain).await?;
+
+        let keys = unique_ids(source_rows.iter().filter_map(|row| key_of(row, source_key)));
+        if keys.is_empty() {
+            continue;
+        }
+
+        let target_query = target.lookup(&keys, &join.target_fields())?;
+        let mut target_rows: HashMap<String, Vec<Row>> = HashMap::new();
+        for row in resolve_rows(&target_query, chain).await? {
+            if let Some(key) = key_of(&row, target_key) {
+                target_rows.entry(key).or_default().push(row);
+            }
+        }
+
+        for source_row in &source_rows {
+            let matches = key_of(source_row, source_key).and_then(|key| target_rows.get(&key));
+            for target_row in matches.into_iter().flatten() {
+                join_res.push(join_row(join, source_row, target_row));
+            }
+        }
+    }
+
+    Ok(join_res)
+}
+
+/// The join key of a row, if the row has one.
+fn key_of(row: &Row, key: &str) -> Option<String> {
+    match row.get(key)? {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn join_row(join: &Join, source_row: &Row, target_row: &Row) -> RowQueryRes {
+    let columns = join
+        .columns()
+        .iter()
+        .map(|column| {
+            let row = if column.entity == join.source_entity() {
+                source_row
+            } else {
+                target_row
+            };
+            let value = row
+                .get(column.entity.serialized_field(&column.field))
+                .cloned()
+                .unwrap_or(Value::Null);
+            (column.name(), value)
+        })
+        .collect();
+
+    RowQueryRes { columns }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::join::JoinEntity;
+
+    #[test]
+    fn test_key_of() {
+        let row: Row =
+            serde_json::from_str(r#"{"number": 10, "digest": "abc", "owner": null}"#).unwrap();
+        assert_eq!(key_of(&row, "number"), Some("10".to_string()));
+        assert_eq!(key_of(&row, "digest"), Some("abc".to_string()));
+        assert_eq!(key_of(&row, "owner"), None);
+        assert_eq!(key_of(&row, "epoch"), None);
+        assert_eq!(
+            JoinEntity::Account.serialized_field("stake_amount"),
+            "staked_amount"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use crate::common::{
+    chain::{Chain, ChainOrRpc},
+    filters::{EqualityFilter, Filter},
+    name::{Name, NameField},
+    name_services::{get_address_names, get_name_record, NSError, NameOrAddress, SuiNsConfig},
+    query_result::NameQueryRes,
+};
+use anyhow::Result;
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use sui_sdk::{SuiClient, SuiClientBuilder};
+
+#[derive(Debug, Serialize, Deserialize, thiserror::Error)]
+pub enum NameResolverErrors {
+    #[error("Query should either provide names or an address filter")]
+    MissingNameOrAddress,
+}
+
+/// Resolve the query to get SuiNS names. Names are resolved on each of the queried chains.
+pub async fn resolve_name_query(name: &Name, chains: &[ChainOrRpc]) -> Result<Vec<NameQueryRes>> {
+    let has_name = name.ids().is_some()
+        || name
+            .name_filters()
+            .iter()
+            .any(|filter| matches!(filter, EqualityFilter::Eq(_)));
+    let has_address = name
+        .address_filters()
+        .iter()
+        .any(|filter| matches!(filter, EqualityFilter::Eq(_)));
+    if !has_name && !has_address {
+        return Err(NameResolverErrors::MissingNameOrAddress.into());
+    }
+
+    let mut all_names_futures = Vec::new();
+
+    for chain in chains {
+        let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
+        let chain = chain.to_chain().await?;
+        let config = SuiNsConfig::for_chain(&chain);
+
+        for ns_name in resolve_names(name, config.as_ref(), &provider).await? {
+            let provider = provider.clone();
+            let chain = chain.clone();
+
+            let name_future =
+                async move { get_name(ns_name, name, config.as_ref(), &provider, &chain).await };
+
+            all_names_futures.push(name_future);
+        }
+    }
+
+    let name_res = try_join_all(all_names_futures).await?;
+    Ok(name_res.into_iter().flatten().collect())
+}
+
+/// Lists the names a query is about: the explicit ids and `name = ...` filters, or every name
+/// known for the `address = ...` filters. All the `name` and `address` filters have to match.
+async fn resolve_names(
+    name: &Name,
+    config: Option<&SuiNsConfig>,
+    provider: &SuiClient,
+) -> Result<Vec<String>> {
+    let mut names: Vec<String> = name.ids().cloned().unwrap_or_default();
+    names.extend(
+        name.name_filters()
+            .into_iter()
+            .filter_map(|filter| match filter {
+                EqualityFilter::Eq(name) => Some(name.clone()),
+                EqualityFilter::Neq(_) => None,
+            }),
+    );
+
+    for filter in name.address_filters() {
+        let address = match filter {
+            EqualityFilter::Eq(address) => *address,
+            EqualityFilter::Neq(_) => continue,
+        };
+        let address_names = get_address_names(address, config, provider).await?;
+
+        names = if names.is_empty() {
+            address_names
+        } else {
+            names
+                .into_iter()
+                .filter(|name| address_names.contains(name))
+                .collect()
+        };
+    }
+
+    let mut unique_names: Vec<String> = Vec::new();
+    for ns_name in names {
+        let matches_filters = name
+            .name_filters()
+            .iter()
+            .all(|filter| filter.compare(&ns_name));
+
+        if matches_filters && !unique_names.contains(&ns_name) {
+            unique_names.push(ns_name);
+        }
+    }
+
+    Ok(unique_names)
+}
+
+async fn get_name(
+    ns_name: String,
+    name: &Name,
+    config: Option<&SuiNsConfig>,
+    provider: &SuiClient,
+    chain: &Chain,
+) -> Result<Option<NameQueryRes>> {
+    let mut name_res = NameQueryRes::default();
+
+    // Expired names, or names without a target, don't resolve to an address.
+    let address = match NameOrAddress::Name(ns_name.clone()).resolve(provider).await {
+        Ok(address) => Some(address),
+        Err(NSError::ResolverNotFound(_)) => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let excluded = name
+        .address_filters()
+        .iter()
+        .any(|filter| matches!(filter, EqualityFilter::Neq(other) if address == Some(*other)));
+    if excluded {
+        return Ok(None);
+    }
+
+    let record = match config {
+        Some(config) if name.fields().iter().any(NameField::is_record_field) => {
+            get_name_record(&ns_name, config, provider).await?
+        }
+        _ => None,
+    }
+    .unwrap_or_default();
+
+    for field in &name.fields() {
+        match field {
+            NameField::Name => {
+                name_res.name = Some(ns_name.clone());
+            }
+            NameField::Address => {
+                name_res.address = address;
+            }
+            NameField::Expiration => {
+                name_res.expiration = record.expiration_timestamp_ms;
+            }
+            NameField::Target => {
+                name_res.target = record.target_address;
+            }
+            NameField::Avatar => {
+                name_res.avatar = record.avatar.clone();
+            }
+            NameField::Chain => {
+                name_res.chain = Some(chain.clone());
+            }
+        }
+    }
+
+    Ok(Some(name_res))
+}
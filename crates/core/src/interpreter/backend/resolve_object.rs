@@ -1,5 +1,5 @@
 use crate::common::{
-    chain::ChainOrRpc,
+    chain::{Chain, ChainOrRpc},
     object::{Object, ObjectField},
     query_result::ObjectQueryRes,
 };
@@ -24,9 +24,10 @@ pub async fn resolve_object_query(
     let mut all_results = Vec::new();
     for chain in chains {
         let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
-        let objects_ids = object.ids().unwrap();
+        let chain = chain.to_chain().await?;
+        let objects_ids = object.object_ids();
         let fields = object.fields().clone();
-        let results = get_objects(objects_ids.to_vec(), fields, &provider, chain).await?;
+        let results = get_objects(objects_ids, fields, &provider, &chain).await?;
         all_results.extend(results);
     }
 
@@ -37,10 +38,9 @@ async fn get_objects(
     objects_ids: Vec<ObjectID>,
     fields: Vec<ObjectField>,
     provider: &SuiClient,
-    chain: &ChainOrRpc,
+    chain: &Chain,
 ) -> Result<Vec<ObjectQueryRes>> {
     let mut objects = Vec::new();
-    let chain = chain.to_chain().await?;
     let options = SuiObjectDataOptions::default()
         .with_owner()
         .with_previous_transaction()
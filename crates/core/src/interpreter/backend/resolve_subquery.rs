@@ -0,0 +1,160 @@
+use super::{
+    resolve_account::resolve_account_query, resolve_checkpoint::resolve_checkpoint_query,
+    resolve_coin::resolve_coin_query, resolve_join::resolve_join_query,
+    resolve_name::resolve_name_query, resolve_object::resolve_object_query,
+    resolve_transaction::resolve_transaction_query,
+};
+pub use crate::common::expression::Row;
+use crate::common::{chain::ChainOrRpc, entity::Entity};
+use anyhow::Result;
+use futures::future::{BoxFuture, FutureExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Executes the `IN (SELECT ...)` subqueries of an entity and applies their values to it.
+/// Subqueries are executed before the entity, so their values end up in the id lists the
+/// resolvers already fetch in batches. Returns `None` when a subquery returns no values,
+/// since the entity can't match anything then.
+pub fn resolve_subqueries<'a>(
+    entity: &'a Entity,
+    chains: &'a [ChainOrRpc],
+) -> BoxFuture<'a, Result<Option<Entity>>> {
+    async move {
+        let mut entity = entity.clone();
+
+        for subquery in entity.take_subqueries() {
+            let rows = resolve_rows(subquery.entity()?, chains).await?;
+            let values = column_values(&rows);
+            if values.is_empty() {
+                return Ok(None);
+            }
+            entity.apply_in(&subquery.column, &values)?;
+        }
+
+        Ok(Some(entity))
+    }
+    .boxed()
+}
+
+/// Runs the query of an entity, returning its rows keyed by column.
+pub fn resolve_rows<'a>(
+    entity: &'a Entity,
+    chains: &'a [ChainOrRpc],
+) -> BoxFuture<'a, Result<Vec<Row>>> {
+    async move {
+        let Some(entity) = resolve_subqueries(entity, chains).await? else {
+            return Ok(vec![]);
+        };
+
+        match &entity {
+            Entity::Account(account) => to_rows(resolve_account_query(account, chains).await?),
+            Entity::Checkpoint(checkpoint) => {
+                to_rows(resolve_checkpoint_query(checkpoint, chains).await?)
+            }
+            Entity::Transaction(transaction) => {
+                to_rows(resolve_transaction_query(transaction, chains).await?)
+            }
+            Entity::Coin(coin) => to_rows(resolve_coin_query(coin, chains).await?),
+            Entity::Object(object) => to_rows(resolve_object_query(object, chains).await?),
+            Entity::Name(name) => to_rows(resolve_name_query(name, chains).await?),
+            Entity::Join(join) => to_rows(resolve_join_query(join, chains).await?),
+        }
+    }
+    .boxed()
+}
+
+pub(crate) fn to_rows<T: Serialize>(results: Vec<T>) -> Result<Vec<Row>> {
+    results
+        .into_iter()
+        .map(|result| match serde_json::to_value(result)? {
+            Value::Object(row) => Ok(row),
+            _ => Ok(Row::new()),
+        })
+        .collect()
+}
+
+/// The distinct values of the single column selected by a subquery. List columns, such as
+/// `changed_objects`, are serialized as comma separated values and are split back.
+fn column_values(rows: &[Row]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    rows.iter()
+        .flat_map(|row| row.values())
+        .flat_map(|value| match value {
+            Value::Null => vec![],
+            Value::String(value) => split_list(value),
+            value => vec![value.to_string()],
+        })
+        .filter(|value| !value.is_empty() && seen.insert(value.clone()))
+        .collect()
+}
+
+/// Splits a comma separated list, keeping the commas of generic types such as
+/// `0x2::coin::Coin<0x2::sui::SUI, 0x2::sui::SUI>` in place.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in value.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(value[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(value[start..].trim().to_string());
+    items
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::query_result::CheckpointQueryRes;
+
+    #[test]
+    fn test_column_values() {
+        let rows: Vec<Row> = serde_json::from_str(
+            r#"[
+                {"changed_objects": "0x1,0x2"},
+                {"changed_objects": "0x2,0x3"},
+                {"changed_objects": null},
+                {"number": 10}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(column_values(&rows), vec!["0x1", "0x2", "0x3", "10"]);
+        assert_eq!(
+            split_list("0x2::coin::Coin<0x2::sui::SUI, 0x3::a::B>,0x2::sui::SUI"),
+            vec!["0x2::coin::Coin<0x2::sui::SUI, 0x3::a::B>", "0x2::sui::SUI"]
+        );
+    }
+
+    #[test]
+    fn test_list_column_values() {
+        let digests = vec![
+            "5PqUjTz5YAcWuVoF9a6iGUnyKmrHKzuS7DTxDEB9V8Qn".to_string(),
+            "11111111111111111111111111111111".to_string(),
+        ];
+        let results = vec![CheckpointQueryRes {
+            transaction_digests: Some(digests.clone()),
+            ..Default::default()
+        }];
+
+        let rows = to_rows(results).unwrap();
+        assert_eq!(
+            rows[0]["transaction_digests"],
+            Value::from(digests.join(","))
+        );
+        assert_eq!(column_values(&rows), digests);
+
+        let row: CheckpointQueryRes =
+            serde_json::from_value(Value::Object(rows[0].clone())).unwrap();
+        assert_eq!(row.transaction_digests, Some(digests));
+    }
+}
@@ -1,7 +1,12 @@
-use super::resolve_checkpoint::{batch_get_checkpoints, get_checkpoint};
+use super::resolve_checkpoint::{
+    batch_get_checkpoints, checkpoint_pages, get_checkpoint, resolve_checkpoint_bounds,
+    CHECKPOINT_PAGE_SIZE,
+};
 use crate::common::{
-    chain::ChainOrRpc,
+    chain::{Chain, ChainOrRpc},
     checkpoint::CheckpointId,
+    entity_id::unique_ids,
+    filters::inclusive_bounds,
     query_result::TransactionQueryRes,
     transaction::{Transaction, TransactionField},
 };
@@ -19,7 +24,7 @@ use sui_types::digests::TransactionDigest;
 pub enum TransactionResolverErrors {
     #[error("Mismatch between Entity and EntityId, {0} can't be resolved as a transaction id")]
     MismatchEntityAndEntityId(String),
-    #[error("Query should either provide tx hash or block number/range filter")]
+    #[error("Query should either provide tx hash, checkpoint number/range or timestamp filter")]
     MissingTransactionHashOrFilter,
 }
 
@@ -32,37 +37,59 @@ pub enum TransactionResolverErrors {
 /// 4. If ids are not provided, then block number or block range filter must be provided.
 /// 5. Fetch the transactions by block number or block range.
 /// 6. If both ids and block number or block range filter are provided, then fetch the transactions by ids first, and filter the result by block number or block range.
+/// 7. Checkpoint comparisons (`checkpoint >= 1000`) and timestamp bounds are normalized into a single checkpoint range first.
 pub async fn resolve_transaction_query(
     transaction: &Transaction,
     chains: &[ChainOrRpc],
 ) -> Result<Vec<TransactionQueryRes>> {
-    if !transaction.ids().is_some() && !transaction.has_checkpoint_filter() {
+    if !transaction.ids().is_some()
+        && !transaction.has_checkpoint_filter()
+        && !transaction.has_timestamp_filter()
+    {
         return Err(TransactionResolverErrors::MissingTransactionHashOrFilter.into());
     }
 
+    let timestamp_filters = transaction.timestamp_filters()?;
+    let filter_fields = transaction.filter_fields();
     let mut all_results = Vec::new();
 
     for chain in chains {
         let provider = SuiClientBuilder::default().build(chain.rpc_url()?).await?;
+        let chain = chain.to_chain().await?;
 
         // Fetch transactions for this chain
-        let rpc_transactions = match transaction.ids() {
+        let mut rpc_transactions = match transaction.ids() {
             Some(ids) => get_transactions_by_ids(ids, &provider).await?,
             None => {
-                let block_id = transaction.get_checkpoint_id_filter()?;
-                get_transactions_by_checkpoint_id(block_id, &provider).await?
+                let timestamp_bounds = transaction
+                    .has_timestamp_filter()
+                    .then(|| inclusive_bounds(&timestamp_filters));
+                match resolve_checkpoint_bounds(
+                    transaction.checkpoint_filters(),
+                    timestamp_bounds,
+                    &provider,
+                )
+                .await?
+                {
+                    Some(range) => {
+                        get_transactions_by_checkpoint_id(&CheckpointId::Range(range), &provider)
+                            .await?
+                    }
+                    None => vec![],
+                }
             }
         };
+        rpc_transactions.retain(|t| {
+            Transaction::matches_timestamp(&timestamp_filters, t.timestamp_ms)
+                && transaction.matches_sender(t.transaction.as_ref().map(|tx| *tx.data.sender()))
+        });
 
-        let result_futures = rpc_transactions
+        // The filters are checked on the fields they compare, which may not be selected.
+        let filtered_tx_res: Vec<TransactionQueryRes> = rpc_transactions
             .iter()
-            .map(|t| pick_transaction_fields(t, transaction.fields(), chain));
-        let tx_res = try_join_all(result_futures).await?;
-
-        // Filter and collect results for this chain
-        let filtered_tx_res: Vec<TransactionQueryRes> = tx_res
-            .into_iter()
-            .filter(|t| t.has_value() && transaction.filter(t))
+            .filter(|t| transaction.filter(&pick_transaction_fields(t, &filter_fields, &chain)))
+            .map(|t| pick_transaction_fields(t, transaction.fields(), &chain))
+            .filter(|t| t.has_value())
             .collect();
 
         all_results.extend(filtered_tx_res);
@@ -76,7 +103,7 @@ async fn get_transactions_by_ids(
     provider: &SuiClient,
 ) -> Result<Vec<RpcTransaction>> {
     let mut tx_futures = Vec::new();
-    for id in ids {
+    for id in unique_ids(ids) {
         let provider = provider.clone();
         let transation_options = SuiTransactionBlockResponseOptions::new()
             .with_effects()
@@ -114,32 +141,39 @@ async fn get_transactions_by_checkpoint_id(
         }
         CheckpointId::Range(r) => {
             let checkpoint_numbers = r.resolve_checkpoint_numbers(provider).await?;
-            let checkpoints = batch_get_checkpoints(checkpoint_numbers, provider).await?;
-            let option_transaction = SuiTransactionBlockResponseOptions::new()
-                .with_effects()
-                .with_events();
-            let mut all_digests = Vec::new();
-            for checkpoint in checkpoints {
-                all_digests.extend(checkpoint.transactions);
+            let mut txs = Vec::new();
+
+            // A range up to `latest` can be large, its transactions are fetched a page of
+            // checkpoints at a time.
+            for page in checkpoint_pages(checkpoint_numbers, CHECKPOINT_PAGE_SIZE) {
+                let checkpoints = batch_get_checkpoints(page, provider).await?;
+                let option_transaction = SuiTransactionBlockResponseOptions::new()
+                    .with_effects()
+                    .with_events();
+                let mut all_digests = Vec::new();
+                for checkpoint in checkpoints {
+                    all_digests.extend(checkpoint.transactions);
+                }
+
+                txs.extend(
+                    provider
+                        .read_api()
+                        .multi_get_transactions_with_options(all_digests, option_transaction)
+                        .await?,
+                );
             }
 
-            let txs = provider
-                .read_api()
-                .multi_get_transactions_with_options(all_digests, option_transaction)
-                .await?;
-
             Ok(txs)
         }
     }
 }
 
-async fn pick_transaction_fields(
+fn pick_transaction_fields(
     tx: &RpcTransaction,
     fields: &Vec<TransactionField>,
-    chain: &ChainOrRpc,
-) -> Result<TransactionQueryRes> {
+    chain: &Chain,
+) -> TransactionQueryRes {
     let mut result = TransactionQueryRes::default();
-    let chain = chain.to_chain().await?;
     let txn_data = tx.transaction.as_ref().map(|t| &t.data);
     let sender = txn_data.map(|d| d.sender());
     let gas = txn_data.map(|d| d.gas_data());
@@ -185,10 +219,19 @@ async fn pick_transaction_fields(
             TransactionField::TotalEvents => {
                 result.total_events = Some(total_events);
             }
+            TransactionField::ChangedObjects => {
+                result.changed_objects = tx.effects.as_ref().map(|effects| {
+                    effects
+                        .all_changed_objects()
+                        .into_iter()
+                        .map(|(object, _)| object.reference.object_id.to_string())
+                        .collect()
+                });
+            }
             // Implement the rest or use `todo!()`:
             _ => todo!("Field {:?} not yet implemented", field),
         }
     }
 
-    Ok(result)
+    result
 }
@@ -0,0 +1,592 @@
+use super::{
+    diagnostic::{closest, Diagnostic, Diagnostics},
+    parser::{parse_program, Rule},
+};
+use crate::{
+    common::{
+        chain::Chain, join::JoinEntity, literal::unquote, schema::FieldType,
+        transaction::TransactionField,
+    },
+    interpreter::backend::{
+        resolve_checkpoint::CheckpointResolverErrors, resolve_coin::CoinResolverErrors,
+        resolve_name::NameResolverErrors, resolve_transaction::TransactionResolverErrors,
+    },
+};
+use pest::iterators::Pair;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum AnalyzerError {
+    #[error("Unknown field `{1}` for {0}")]
+    UnknownField(JoinEntity, String),
+
+    #[error("Unknown chain `{0}`, other networks are named in the config file")]
+    UnknownChain(String),
+
+    #[error("Field `{1}` of {0} isn't supported yet")]
+    UnsupportedField(JoinEntity, String),
+
+    #[error("Filtering {0} on `{1}` isn't supported yet")]
+    UnsupportedFilter(JoinEntity, String),
+
+    #[error("Column `{1}` can't be used in an IN filter on {0}, expected {2}")]
+    UnsupportedInColumn(JoinEntity, String, String),
+
+    #[error("Expected an integer, found `{0}`")]
+    ExpectedInteger(String),
+
+    #[error("`{0}` is too large for an integer")]
+    IntegerTooLarge(String),
+
+    #[error("`{0}` is a {1} and can't be compared with `{2}`")]
+    MismatchedLiteral(String, FieldType, String),
+
+    #[error("Event queries aren't supported yet")]
+    UnsupportedEvents,
+
+    #[error("Events can't be filtered on `timestamp`, filter the transactions that emitted them")]
+    UnsupportedEventTimestamp,
+
+    #[error("Query should either provide addresses, names or an address filter")]
+    MissingAddresses,
+
+    #[error("Query should either provide object ids or an object filter")]
+    MissingObjectIds,
+}
+
+/// Checks a program between parsing and execution, so invalid queries are rejected before
+/// any request is sent.
+pub struct Analyzer<'a> {
+    source: &'a str,
+    networks: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Analyzer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Analyzer {
+            source,
+            networks: vec![],
+            diagnostics: vec![],
+        }
+    }
+
+    /// Accepts `networks` as chains besides the public ones, like the networks of the config
+    /// file. The analyzer doesn't read the config itself.
+    pub fn with_networks(mut self, networks: Vec<String>) -> Self {
+        self.networks = networks;
+        self
+    }
+
+    /// Checks that the fields, filters and literals of every query exist and work together,
+    /// and that every query says what to fetch. All the problems are reported at once.
+    pub fn analyze(mut self) -> Result<(), Diagnostics> {
+        let pairs = parse_program(self.source).map_err(|error| Diagnostics(vec![error]))?;
+        for pair in pairs {
+            self.check(pair, None);
+        }
+
+        match self.diagnostics.is_empty() {
+            true => Ok(()),
+            false => {
+                self.diagnostics.sort_by_key(|d| (d.line, d.column));
+                Err(Diagnostics(self.diagnostics))
+            }
+        }
+    }
+
+    fn report(&mut self, pair: &Pair<'_, Rule>, error: impl ToString) {
+        let diagnostic = Diagnostic::from_span(&pair.as_span(), error.to_string());
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Checks `pair` and its children. `entity` is the entity of the query they belong to.
+    fn check(&mut self, pair: Pair<'_, Rule>, entity: Option<JoinEntity>) {
+        let queried = JoinEntity::from_rule(pair.as_rule());
+        if let Some(queried) = queried {
+            self.check_query(&pair, queried);
+        }
+        let entity = queried.or(entity);
+
+        match pair.as_rule() {
+            Rule::event_get => {
+                self.report(&pair, AnalyzerError::UnsupportedEvents);
+                // Events aren't timestamped by the resolvers, unlike checkpoints and
+                // transactions, so the filter would be silently ignored.
+                let timestamp_filters = pair.into_inner().filter(|filter| {
+                    filter.as_rule() == Rule::tx_filter
+                        && filter.clone().into_inner().next().is_some_and(|filter| {
+                            filter.as_rule() == Rule::timestamp_ms_filter_type
+                        })
+                });
+                for filter in timestamp_filters {
+                    self.report(&filter, AnalyzerError::UnsupportedEventTimestamp);
+                }
+                return;
+            }
+            // The value of a `type` filter is compared as text.
+            Rule::type_filter_type => return,
+            Rule::column_ref => {
+                if let Some(entity) = entity {
+                    self.check_field(&pair, entity);
+                }
+            }
+            Rule::join_column => {
+                let mut inner = pair.clone().into_inner();
+                let entity = inner
+                    .next()
+                    .and_then(|entity| JoinEntity::try_from(entity.as_str()).ok());
+                if let (Some(entity), Some(field)) = (entity, inner.next()) {
+                    if field.as_rule() == Rule::join_field_name {
+                        self.check_field(&field, entity);
+                    }
+                }
+            }
+            Rule::chain => return self.check_chain(&pair),
+            Rule::in_column => {
+                if let Some(entity) = entity {
+                    self.check_in_column(&pair, entity);
+                }
+            }
+            Rule::expr => {
+                if let Some(entity) = entity {
+                    self.check_comparisons(&pair, entity);
+                }
+            }
+            Rule::float => return self.report(&pair, AnalyzerError::ExpectedInteger(text(&pair))),
+            Rule::integer if pair.as_str().parse::<u64>().is_err() => {
+                self.report(&pair, AnalyzerError::IntegerTooLarge(text(&pair)));
+            }
+            _ => {}
+        }
+
+        for inner in pair.into_inner() {
+            self.check(inner, entity);
+        }
+    }
+
+    fn check_field(&mut self, field: &Pair<'_, Rule>, entity: JoinEntity) {
+        let name = field.as_str();
+        if !entity.has_field(name) {
+            let fields = entity.all_fields();
+            let error = AnalyzerError::UnknownField(entity, name.to_string());
+            let diagnostic = Diagnostic::from_span(&field.as_span(), error.to_string())
+                .with_suggestion(closest(name, fields.iter().map(String::as_str)));
+            self.diagnostics.push(diagnostic);
+        } else if entity == JoinEntity::Transaction
+            && TransactionField::try_from(name).is_ok_and(|field| !field.is_implemented())
+        {
+            self.report(
+                field,
+                AnalyzerError::UnsupportedField(entity, name.to_string()),
+            );
+        }
+    }
+
+    fn check_chain(&mut self, chain: &Pair<'_, Rule>) {
+        let name = chain.as_str();
+        let chains = Chain::public_chains()
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.networks.iter().cloned())
+            .collect::<Vec<_>>();
+        if !chains.iter().any(|chain| chain == name) {
+            let error = AnalyzerError::UnknownChain(name.to_string());
+            let diagnostic = Diagnostic::from_span(&chain.as_span(), error.to_string())
+                .with_suggestion(closest(name, chains.iter().map(String::as_str)));
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn check_in_column(&mut self, column: &Pair<'_, Rule>, entity: JoinEntity) {
+        let columns = entity.in_columns();
+        if !columns.contains(&column.as_str()) {
+            let error = AnalyzerError::UnsupportedInColumn(
+                entity,
+                column.as_str().to_string(),
+                columns.join(", "),
+            );
+            self.report(column, error);
+        }
+    }
+
+    /// Checks what a query or a join source fetches by and its filters: the resolvers need
+    /// ids, or filters they can look the entity up by, and can't filter transactions on the
+    /// fields they don't fetch yet.
+    fn check_query(&mut self, query: &Pair<'_, Rule>, entity: JoinEntity) {
+        let mut has_ids = false;
+        let mut has_lookup = false;
+        let mut typed_filters = vec![];
+
+        for pair in query.clone().into_inner().flat_map(join_filters) {
+            match pair.as_rule() {
+                Rule::account_id
+                | Rule::checkpoint_id
+                | Rule::tx_id
+                | Rule::coin_id
+                | Rule::object_id
+                | Rule::name_id
+                | Rule::variable => has_ids = true,
+                Rule::account_filter
+                | Rule::checkpoint_filter
+                | Rule::tx_filter
+                | Rule::coin_filter
+                | Rule::object_filter
+                | Rule::name_filter => {
+                    let Some(filter) = pair.into_inner().next() else {
+                        continue;
+                    };
+                    match filter.as_rule() {
+                        // Expressions are evaluated on the fetched rows.
+                        Rule::expr_filter => {}
+                        // Transactions can't be looked up by sender.
+                        Rule::in_filter => {
+                            has_ids |= entity != JoinEntity::Transaction
+                                || filter.as_str().trim_start().starts_with("digest")
+                        }
+                        _ if is_lookup(&filter, entity) => has_lookup = true,
+                        _ => typed_filters.push(filter),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let missing: Option<String> = match entity {
+            _ if has_ids || has_lookup => None,
+            JoinEntity::Account => Some(AnalyzerError::MissingAddresses.to_string()),
+            JoinEntity::Object => Some(AnalyzerError::MissingObjectIds.to_string()),
+            JoinEntity::Transaction => {
+                Some(TransactionResolverErrors::MissingTransactionHashOrFilter.to_string())
+            }
+            JoinEntity::Checkpoint => {
+                Some(CheckpointResolverErrors::MissingCheckpointIdsOrRange.to_string())
+            }
+            JoinEntity::Coin => Some(CoinResolverErrors::MissingCoinTypeOrOwner.to_string()),
+            JoinEntity::Name => Some(NameResolverErrors::MissingNameOrAddress.to_string()),
+        };
+        if let Some(missing) = missing {
+            self.report(query, missing);
+        }
+
+        if entity != JoinEntity::Transaction {
+            return;
+        }
+        for filter in typed_filters {
+            let Some(field) = transaction_filter_field(filter.as_rule()) else {
+                continue;
+            };
+            if matches!(field, "recipient" | "data") {
+                self.report(
+                    &filter,
+                    AnalyzerError::UnsupportedFilter(entity, field.into()),
+                );
+            }
+        }
+    }
+
+    /// Checks the `column <op> literal` comparisons joined by `AND` and `OR` in an expression,
+    /// where the literal can never be equal to a value of the column.
+    fn check_comparisons(&mut self, expr: &Pair<'_, Rule>, entity: JoinEntity) {
+        let items = expr.clone().into_inner().collect::<Vec<_>>();
+        let is_boundary = |i: Option<usize>| {
+            i.and_then(|i| items.get(i))
+                .map_or(true, |item| matches!(item.as_rule(), Rule::and | Rule::or))
+        };
+
+        for (i, window) in items.windows(3).enumerate() {
+            let [lhs, operator, rhs] = window else {
+                continue;
+            };
+            let is_comparison = matches!(
+                operator.as_rule(),
+                Rule::eq | Rule::neq | Rule::lt | Rule::lte | Rule::gt | Rule::gte
+            );
+            if !is_comparison || !is_boundary(i.checked_sub(1)) || !is_boundary(Some(i + 3)) {
+                continue;
+            }
+
+            let (column, literal) = match (lhs.as_rule(), rhs.as_rule()) {
+                (Rule::column_ref, _) => (lhs, rhs),
+                (_, Rule::column_ref) => (rhs, lhs),
+                _ => continue,
+            };
+            let field_type = entity.field_type(column.as_str());
+            if !literal_matches(field_type, literal) {
+                let error = AnalyzerError::MismatchedLiteral(
+                    column.as_str().to_string(),
+                    field_type,
+                    literal.as_str().to_string(),
+                );
+                self.report(literal, error);
+            }
+        }
+    }
+}
+
+/// The filters of a join source are nested in a `*_join_filters` pair.
+fn join_filters(pair: Pair<'_, Rule>) -> Vec<Pair<'_, Rule>> {
+    match pair.as_rule() {
+        Rule::tx_join_filters
+        | Rule::checkpoint_join_filters
+        | Rule::coin_join_filters
+        | Rule::name_join_filters => pair.into_inner().collect(),
+        _ => vec![pair],
+    }
+}
+
+/// Whether a filter tells the resolver what to fetch, as opposed to narrowing the fetched rows.
+fn is_lookup(filter: &Pair<'_, Rule>, entity: JoinEntity) -> bool {
+    let is_eq = || {
+        filter
+            .clone()
+            .into_inner()
+            .flatten()
+            .next()
+            .and_then(|operator| operator.into_inner().next())
+            .is_some_and(|operator| operator.as_rule() == Rule::eq_operator)
+    };
+
+    match filter.as_rule() {
+        Rule::checkpointrange_filter
+        | Rule::timestamp_ms_filter_type
+        | Rule::epoch_filter_type
+        | Rule::coin_owner_filter_type
+        | Rule::object_filter_type => true,
+        Rule::address_filter_type => entity == JoinEntity::Account || is_eq(),
+        Rule::coin_type_filter_type | Rule::ns_filter_type => is_eq(),
+        _ => false,
+    }
+}
+
+/// The field a typed transaction filter compares, in the transactions it is applied to.
+fn transaction_filter_field(rule: Rule) -> Option<&'static str> {
+    match rule {
+        Rule::type_filter_type => Some("type"),
+        Rule::sender_filter_type => Some("sender"),
+        Rule::recipient_filter_type => Some("recipient"),
+        Rule::data_filter_type => Some("data"),
+        Rule::gas_budget_filter_type => Some("gas_budget"),
+        Rule::gas_price_filter_type => Some("gas_price"),
+        Rule::gas_used_filter_type => Some("gas_used"),
+        Rule::status_filter_type => Some("status"),
+        Rule::executed_epoch_filter_type => Some("executed_epoch"),
+        _ => None,
+    }
+}
+
+/// Whether a literal can be equal to a value of the type. Strings are compared as numbers or
+/// booleans when they hold one, like when expressions are evaluated.
+fn literal_matches(field_type: FieldType, literal: &Pair<'_, Rule>) -> bool {
+    match (literal.as_rule(), field_type) {
+        (Rule::null_literal, _) | (_, FieldType::Text | FieldType::List) => true,
+        // Columns and computed values are only known once the rows are fetched.
+        (rule, _)
+            if !matches!(
+                rule,
+                Rule::number_literal | Rule::bool_literal | Rule::string_literal
+            ) =>
+        {
+            true
+        }
+        (Rule::number_literal, FieldType::Number) | (Rule::bool_literal, FieldType::Boolean) => {
+            true
+        }
+        (Rule::string_literal, FieldType::Number) => unquote(literal.as_str())
+            .trim()
+            .parse::<f64>()
+            .is_ok_and(f64::is_finite),
+        (Rule::string_literal, FieldType::Boolean) => {
+            let value = unquote(literal.as_str());
+            let value = value.trim();
+            value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
+        }
+        _ => false,
+    }
+}
+
+fn text(pair: &Pair<'_, Rule>) -> String {
+    pair.as_str().trim().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn problems(source: &str) -> Vec<Diagnostic> {
+        match Analyzer::new(source).analyze() {
+            Ok(()) => vec![],
+            Err(Diagnostics(diagnostics)) => diagnostics,
+        }
+    }
+
+    #[test]
+    fn test_valid_queries() {
+        let sources = [
+            "SELECT * FROM tx WHERE checkpoint = 10, gas_used > 1000 ON sui_mainnet",
+            "SELECT digest, status FROM tx WHERE timestamp > now() - 1h, status = true ON sui_mainnet",
+            "SELECT digest FROM tx WHERE checkpoint = 1, gas_used > gas_price ON sui_mainnet",
+            "SELECT name FROM coin WHERE owner = \
+             0x0000000000000000000000000000000000000000000000000000000000000002 ON sui_mainnet",
+            "SELECT * FROM checkpoint WHERE epoch = 10 ON sui_mainnet",
+            "SELECT * FROM account WHERE address IN (SELECT sender FROM tx WHERE checkpoint = 1)",
+            "SELECT tx.digest, account.sui_balance FROM tx JOIN account \
+             ON tx.sender = account.address WHERE checkpoint = 10 ON sui_mainnet",
+            "SELECT digest FROM tx WHERE checkpoint = 1, sender = \
+             0x0000000000000000000000000000000000000000000000000000000000000002",
+            "SELECT tx.digest, account.sui_balance FROM tx JOIN account \
+             ON tx.sender = account.address WHERE checkpoint = 10, gas_used > 1000",
+        ];
+        for source in sources {
+            assert_eq!(problems(source), vec![], "{source}");
+        }
+    }
+
+    #[test]
+    fn test_reports_all_problems() {
+        let source =
+            "SELECT digest, gas_used, balanse FROM tx WHERE gas_used > 10.5 ON sui_mainnet;
+SELECT * FROM coin WHERE decimals = 'nine' ON sui_mainnet;
+SELECT * FROM tx WHERE checkpoint = 1, owner IN ('0x2')";
+        let problems = problems(source);
+        let messages = problems
+            .iter()
+            .map(|p| p.message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Query should either provide tx hash, checkpoint number/range or timestamp filter",
+                "Unknown field `balanse` for tx",
+                "Expected an integer, found `10.5`",
+                "Query should either provide coin types or an owner filter",
+                "`decimals` is a number and can't be compared with `'nine'`",
+                "Column `owner` can't be used in an IN filter on tx, expected digest, sender",
+            ]
+        );
+        assert_eq!((problems[1].line, problems[1].column), (1, 26));
+        assert_eq!(problems[1].suggestion, None);
+        assert_eq!((problems[4].line, problems[4].column), (2, 37));
+    }
+
+    #[test]
+    fn test_unsupported_fields_and_filters() {
+        let source = "SELECT digest, recipient FROM tx WHERE checkpoint = 1, recipient = \
+                      0x0000000000000000000000000000000000000000000000000000000000000002";
+        let messages = problems(source)
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Field `recipient` of tx isn't supported yet",
+                "Filtering tx on `recipient` isn't supported yet",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_timestamp_filters() {
+        let source = "SELECT * FROM event WHERE timestamp > now() - 1h ON sui_mainnet";
+        let problems = problems(source);
+        let messages = problems
+            .iter()
+            .map(|problem| problem.message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Event queries aren't supported yet",
+                "Events can't be filtered on `timestamp`, filter the transactions that emitted them",
+            ]
+        );
+        assert_eq!((problems[1].line, problems[1].column), (1, 27));
+    }
+
+    #[test]
+    fn test_join_source_filters() {
+        let source = "SELECT tx.digest, account.sui_balance FROM tx JOIN account \
+                      ON tx.sender = account.address WHERE checkpoint = 10, recipient = \
+                      0x0000000000000000000000000000000000000000000000000000000000000002";
+        let messages = problems(source)
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec!["Filtering tx on `recipient` isn't supported yet"]
+        );
+    }
+
+    #[test]
+    fn test_unbounded_checkpoint_filters() {
+        let source = "SELECT * FROM checkpoint WHERE transactions > 1000, computation_cost > 0";
+        let messages = problems(source)
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![CheckpointResolverErrors::MissingCheckpointIdsOrRange.to_string()]
+        );
+        assert_eq!(
+            problems("SELECT * FROM checkpoint WHERE epoch = 10, transactions > 1000"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_unknown_field_suggestion() {
+        let source = "SELECT address,\n       balance\nFROM account \
+                      0x0000000000000000000000000000000000000000000000000000000000000002";
+        let problems = problems(source);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].message, "Unknown field `balance` for account");
+        assert_eq!((problems[0].line, problems[0].column), (2, 8));
+        assert_eq!(problems[0].suggestion.as_deref(), Some("sui_balance"));
+    }
+
+    #[test]
+    fn test_unknown_chain_suggestion() {
+        let source = "SELECT * FROM tx WHERE checkpoint = 1\nON sui_mainet";
+        let problems = problems(source);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].message,
+            "Unknown chain `sui_mainet`, other networks are named in the config file"
+        );
+        assert_eq!((problems[0].line, problems[0].column), (2, 4));
+        assert_eq!(problems[0].suggestion.as_deref(), Some("sui_mainnet"));
+    }
+
+    #[test]
+    fn test_configured_networks() {
+        let analyze = |source: &str| {
+            Analyzer::new(source)
+                .with_networks(vec!["localnet".to_string()])
+                .analyze()
+        };
+
+        assert_eq!(
+            analyze("SELECT * FROM tx WHERE checkpoint = 1 ON localnet"),
+            Ok(())
+        );
+        assert_eq!(
+            problems("SELECT * FROM tx WHERE checkpoint = 1 ON localnet").len(),
+            1
+        );
+        let Err(Diagnostics(problems)) =
+            analyze("SELECT * FROM tx WHERE checkpoint = 1 ON localnt")
+        else {
+            panic!("expected an unknown chain");
+        };
+        assert_eq!(problems[0].suggestion.as_deref(), Some("localnet"));
+    }
+}
@@ -0,0 +1,463 @@
+use super::parser::{Parser, Rule};
+use crate::common::{chain::Chain, config::Config, expression::Function, join::JoinEntity};
+use pest::{error::ErrorVariant, Parser as PestParser};
+
+/// What a completion inserts, so editors can tell them apart.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CompletionKind {
+    Keyword,
+    Entity,
+    Field,
+    Function,
+    Chain,
+    Operator,
+}
+
+/// A word that can be written at the cursor.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// A short description, like the type of a field or what an entity holds.
+    pub detail: Option<String>,
+}
+
+/// Marks the cursor in the parsed text. No rule matches it, so the parser stops there and
+/// lists the tokens it expected.
+const CURSOR: char = '\u{1}';
+
+/// The operators completed from the expected tokens, the arithmetic ones aren't worth it.
+const OPERATORS: &[&str] = &["=", "!=", ">", ">=", "<", "<="];
+
+/// The completions for the word ending at byte `offset` of `source`, which starts at
+/// [`word_start`]. What can be written there is what the grammar expects after the text before
+/// the word: keywords, entities, chains and operators, plus the fields of the queried entity
+/// and the functions where an expression can start.
+pub fn complete(source: &str, offset: usize) -> Vec<Completion> {
+    let start = word_start(source, offset);
+    let prefix = source[start..offset].to_lowercase();
+    let words = words(source);
+    let entity = statement_entity(&words, start);
+
+    let candidates = match source[..start].strip_suffix('.') {
+        // The columns of a join, `tx.sender`
+        Some(before) => JoinEntity::try_from(&before[word_start(before, before.len())..])
+            .map(|entity| field_completions(Some(entity)))
+            .unwrap_or_default(),
+        None => expected_completions(&source[statement_start(&words, start)..start], entity),
+    };
+
+    let mut completions: Vec<Completion> = vec![];
+    for candidate in candidates {
+        let is_new = completions.iter().all(|c| c.label != candidate.label);
+        if is_new && candidate.label.to_lowercase().starts_with(&prefix) {
+            completions.push(candidate);
+        }
+    }
+    completions
+}
+
+/// The documentation of the word at byte `offset` of `source`, in markdown: the type and
+/// filters of a field of the queried entity, or what an entity holds.
+pub fn hover(source: &str, offset: usize) -> Option<String> {
+    let start = word_start(source, offset);
+    let end = source[start..]
+        .find(|c: char| !is_ident_char(c))
+        .map_or(source.len(), |i| start + i);
+    let word = &source[start..end];
+    if word.is_empty() {
+        return None;
+    }
+
+    let words = words(source);
+    let previous = words
+        .iter()
+        .rev()
+        .find(|(i, _)| *i < start)
+        .map(|(_, word)| word.to_uppercase());
+    let names_entity = matches!(previous.as_deref(), Some("FROM" | "JOIN" | "DESCRIBE"));
+
+    let entity = match source[..start].strip_suffix('.') {
+        Some(before) => JoinEntity::try_from(&before[word_start(before, before.len())..]).ok(),
+        None => statement_entity(&words, start),
+    };
+
+    // `name` is both an entity and a field of it
+    let field = entity.and_then(|entity| field_doc(entity, word));
+    match JoinEntity::try_from(word) {
+        Ok(named) if names_entity || source[end..].starts_with('.') || field.is_none() => {
+            Some(entity_doc(named))
+        }
+        _ => field,
+    }
+}
+
+/// The byte offset of the start of the word ending at byte `offset`.
+pub fn word_start(source: &str, offset: usize) -> usize {
+    source[..offset]
+        .rfind(|c: char| !is_ident_char(c))
+        .map_or(0, |i| i + 1)
+}
+
+fn expected_completions(text: &str, entity: Option<JoinEntity>) -> Vec<Completion> {
+    let input = format!("{text}{CURSOR}");
+    pest::set_error_detail(true);
+    let error = match Parser::parse(Rule::program, &input) {
+        Ok(_) => return vec![],
+        Err(error) => error,
+    };
+    // The text before the cursor doesn't parse, there is nothing to complete.
+    let attempts = match error.parse_attempts() {
+        Some(attempts) if attempts.max_position >= text.len() => attempts,
+        _ => return vec![],
+    };
+
+    let mut completions = attempts
+        .expected_tokens()
+        .iter()
+        .filter_map(|token| token_completion(text, &token.to_string(), entity))
+        .collect::<Vec<_>>();
+
+    // The networks of the config file are chains too, which the grammar doesn't list.
+    if completions
+        .iter()
+        .any(|completion| completion.kind == CompletionKind::Chain)
+    {
+        let networks = Config::load().get_networks().unwrap_or_default();
+        completions.extend(networks.into_iter().map(|network| Completion {
+            label: network,
+            kind: CompletionKind::Chain,
+            detail: Some("network".to_string()),
+        }));
+    }
+
+    let positives = match &error.variant {
+        ErrorVariant::ParsingError { positives, .. } => positives.as_slice(),
+        ErrorVariant::CustomError { .. } => &[],
+    };
+    if positives.iter().any(|rule| starts_column(*rule)) {
+        completions.extend(field_completions(entity));
+        completions.extend(Function::ALL.iter().map(|function| Completion {
+            label: function.to_string(),
+            kind: CompletionKind::Function,
+            detail: Some("function".to_string()),
+        }));
+    }
+
+    completions
+}
+
+/// The completion of a token the grammar expects after `text`. Keywords are kept only if the
+/// text goes on parsing after them, since the keywords ending filters are also expected.
+fn token_completion(text: &str, token: &str, entity: Option<JoinEntity>) -> Option<Completion> {
+    let completion = |kind, detail: Option<String>| {
+        Some(Completion {
+            label: token.to_string(),
+            kind,
+            detail,
+        })
+    };
+
+    if token.chars().all(|c| c.is_ascii_uppercase()) {
+        return match continues_after(text, token) {
+            true => completion(CompletionKind::Keyword, None),
+            false => None,
+        };
+    }
+    if Chain::public_chains()
+        .iter()
+        .any(|chain| chain.to_string() == token)
+    {
+        return completion(CompletionKind::Chain, Some("chain".to_string()));
+    }
+    if let Ok(entity) = JoinEntity::try_from(token) {
+        return completion(
+            CompletionKind::Entity,
+            Some(entity.description().to_string()),
+        );
+    }
+    if OPERATORS.contains(&token) {
+        return completion(CompletionKind::Operator, None);
+    }
+
+    field_completions(entity)
+        .into_iter()
+        .find(|field| field.label == token)
+}
+
+fn continues_after(text: &str, keyword: &str) -> bool {
+    let input = format!("{text}{keyword} {CURSOR}");
+    match Parser::parse(Rule::program, &input) {
+        Ok(_) => true,
+        Err(error) => error
+            .parse_attempts()
+            .is_some_and(|attempts| attempts.max_position > text.len() + keyword.len()),
+    }
+}
+
+/// Whether the grammar expecting `rule` means a column can be written.
+fn starts_column(rule: Rule) -> bool {
+    matches!(
+        rule,
+        Rule::account_fields
+            | Rule::checkpoint_fields
+            | Rule::tx_fields
+            | Rule::coin_fields
+            | Rule::object_fields
+            | Rule::name_fields
+            | Rule::select_item
+            | Rule::expr
+            | Rule::expr_filter
+            | Rule::account_filter
+            | Rule::checkpoint_filter
+            | Rule::tx_filter
+            | Rule::coin_filter
+            | Rule::object_filter
+            | Rule::name_filter
+            | Rule::in_column
+    )
+}
+
+/// The fields and filters of `entity`, or the fields of every entity when it isn't known yet.
+fn field_completions(entity: Option<JoinEntity>) -> Vec<Completion> {
+    let Some(entity) = entity else {
+        return JoinEntity::ALL
+            .iter()
+            .flat_map(|entity| entity.selectable_fields())
+            .map(|field| Completion {
+                label: field,
+                kind: CompletionKind::Field,
+                detail: Some("field".to_string()),
+            })
+            .collect();
+    };
+
+    let fields = entity.selectable_fields();
+    let filters = entity
+        .filters()
+        .iter()
+        .flat_map(|filter| filter.names.iter())
+        .filter(|name| !fields.iter().any(|field| field == *name))
+        .map(|name| Completion {
+            label: name.to_string(),
+            kind: CompletionKind::Field,
+            detail: Some(format!("filter of {entity}")),
+        })
+        .collect::<Vec<_>>();
+
+    fields
+        .iter()
+        .map(|field| Completion {
+            label: field.clone(),
+            kind: CompletionKind::Field,
+            detail: Some(format!("{} field of {entity}", entity.field_type(field))),
+        })
+        .chain(filters)
+        .collect()
+}
+
+fn entity_doc(entity: JoinEntity) -> String {
+    format!(
+        "**{entity}**\n\n{}\n\nFields: {}",
+        entity.description(),
+        entity
+            .selectable_fields()
+            .iter()
+            .map(|field| format!("`{field}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// The type and filters of a field, or the values a filter on something that can't be
+/// selected takes.
+fn field_doc(entity: JoinEntity, word: &str) -> Option<String> {
+    if entity.selectable_fields().iter().any(|field| field == word) {
+        let filters = entity.field_filters(word);
+        let mut doc = format!(
+            "**{word}**: {} field of `{entity}`",
+            entity.field_type(word)
+        );
+        match filters.names.is_empty() {
+            true => doc.push_str("\n\nFiltered with expressions on the fetched rows"),
+            false => doc.push_str(&format!(
+                "\n\nFilter: `{}` with `{}`",
+                filters.names.join("`, `"),
+                filters.operators.join("`, `")
+            )),
+        }
+        if let Some(value) = filters.value {
+            doc.push_str(&format!(", takes a {value}"));
+        }
+        return Some(doc);
+    }
+
+    let filter = entity
+        .filters()
+        .iter()
+        .find(|filter| filter.names.contains(&word))?;
+    Some(format!(
+        "**{word}**: filter of `{entity}` with `{}`, takes a {}",
+        filter.operators.symbols().join("`, `"),
+        filter.value
+    ))
+}
+
+/// The entity queried by the statement around byte `offset`: the one after the `FROM` of the
+/// last `SELECT` before the offset, or of the first one when the offset comes before it.
+fn statement_entity(words: &[(usize, &str)], offset: usize) -> Option<JoinEntity> {
+    let start = statement_start(words, offset);
+    let statement = words
+        .iter()
+        .skip_while(|(i, _)| *i < start)
+        .take_while(|(i, word)| *i < offset || *word != ";")
+        .collect::<Vec<_>>();
+
+    let is_select = |word: &(usize, &str)| word.1.eq_ignore_ascii_case("SELECT");
+    let select = statement
+        .iter()
+        .rposition(|word| word.0 < offset && is_select(word))
+        .or_else(|| statement.iter().position(|word| is_select(word)))?;
+
+    statement[select..]
+        .windows(2)
+        .find(|pair| pair[0].1.eq_ignore_ascii_case("FROM"))
+        .and_then(|pair| JoinEntity::try_from(pair[1].1).ok())
+}
+
+/// The byte offset of the statement around byte `offset`, right after the last `;` before it.
+fn statement_start(words: &[(usize, &str)], offset: usize) -> usize {
+    words
+        .iter()
+        .rev()
+        .find(|(i, word)| *i < offset && *word == ";")
+        .map_or(0, |(i, _)| i + 1)
+}
+
+/// The identifiers and `;` of `source` with their byte offset, skipping strings and comments.
+fn words(source: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let end = match c {
+            '\'' | '"' => {
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            '-' if source[i..].starts_with("--") => {
+                source[i..].find('\n').map_or(source.len(), |end| i + end)
+            }
+            '/' if source[i..].starts_with("/*") => source[i + 2..]
+                .find("*/")
+                .map_or(source.len(), |end| i + 2 + end + 2),
+            ';' => {
+                words.push((i, ";"));
+                continue;
+            }
+            c if is_ident_char(c) => {
+                let end = source[i..]
+                    .find(|c: char| !is_ident_char(c))
+                    .map_or(source.len(), |end| i + end);
+                words.push((i, &source[i..end]));
+                end
+            }
+            _ => continue,
+        };
+        while chars.next_if(|(j, _)| *j < end).is_some() {}
+    }
+
+    words
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn labels(source: &str) -> Vec<String> {
+        complete(source, source.len())
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_complete_keywords() {
+        assert_eq!(
+            labels(""),
+            vec!["DESCRIBE", "EXPLAIN", "LET", "SELECT", "SET", "SHOW"]
+        );
+        assert_eq!(labels("SHOW "), vec!["CHAINS", "ENTITIES"]);
+        assert_eq!(labels("sel"), vec!["SELECT"]);
+        assert_eq!(labels("SELECT * "), vec!["FROM"]);
+
+        // `FROM` and `WHEN` can end a filter, but can't follow one
+        let after_filter = labels("SELECT * FROM tx WHERE checkpoint = 5 ");
+        assert!(after_filter.contains(&"ON".to_string()));
+        assert!(!after_filter.contains(&"FROM".to_string()));
+        assert!(!after_filter.contains(&"WHEN".to_string()));
+    }
+
+    #[test]
+    fn test_complete_entities_and_chains() {
+        assert_eq!(
+            labels("SELECT * FROM "),
+            vec!["account", "checkpoint", "coin", "name", "object", "tx"]
+        );
+        assert_eq!(labels("DESCRIBE t"), vec!["tx"]);
+        assert_eq!(
+            labels("SELECT * FROM tx WHERE checkpoint = 5 ON sui_"),
+            vec!["sui_devnet", "sui_mainnet", "sui_testnet"]
+        );
+    }
+
+    #[test]
+    fn test_complete_fields_and_operators() {
+        let fields = complete("SELECT gas FROM tx WHERE checkpoint = 5", 10)
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["gas_budget", "gas_price", "gas_used"]);
+
+        let filters = labels("SELECT * FROM tx WHERE checkpoint = 5, ");
+        assert!(filters.contains(&"sender".to_string()));
+        assert!(filters.contains(&"timestamp".to_string()));
+        assert!(filters.contains(&"to_datetime".to_string()));
+        assert!(!filters.contains(&"sui_balance".to_string()));
+
+        let operators = labels("SELECT * FROM tx WHERE sender ");
+        for operator in ["=", "!=", "IN"] {
+            assert!(operators.contains(&operator.to_string()), "{operator}");
+        }
+
+        assert_eq!(
+            labels("SELECT tx.digest, account.sui_"),
+            vec!["sui_balance"]
+        );
+    }
+
+    #[test]
+    fn test_hover() {
+        let source = "SELECT gas_used FROM tx WHERE checkpoint = 5 ON sui_mainnet";
+        let doc = hover(source, 9).unwrap();
+        assert!(
+            doc.starts_with("**gas_used**: number field of `tx`"),
+            "{doc}"
+        );
+        assert!(doc.contains("`>=`"), "{doc}");
+        assert!(hover(source, 22).unwrap().starts_with("**tx**"));
+        assert_eq!(hover(source, 6), None);
+    }
+}
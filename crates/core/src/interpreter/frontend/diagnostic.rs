@@ -0,0 +1,346 @@
+use super::parser::Rule;
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    Span,
+};
+use std::fmt::Display;
+
+/// A problem in a query, pointing at the line and column it was found at.
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    /// 1-based line of the start of the problem.
+    pub line: usize,
+    /// 1-based column of the start of the problem, in characters.
+    pub column: usize,
+    /// The number of characters underlined, at least one.
+    pub length: usize,
+    /// The source line the problem starts on.
+    pub snippet: String,
+    /// What could have been written instead, in plain words.
+    pub expected: Vec<String>,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    /// A problem with the `length` bytes of `source` starting at byte `start`.
+    pub fn new(source: &str, start: usize, length: usize, message: impl Into<String>) -> Self {
+        let start = start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let end = (start + length).min(line_end);
+
+        Diagnostic {
+            message: message.into(),
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            length: source[start..end].chars().count().max(1),
+            snippet: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            expected: vec![],
+            suggestion: None,
+        }
+    }
+
+    pub fn from_span(span: &Span<'_>, message: impl Into<String>) -> Self {
+        Diagnostic::new(
+            span.get_input(),
+            span.start(),
+            span.end() - span.start(),
+            message,
+        )
+    }
+
+    /// Turns a syntax error into a diagnostic, pointing at the furthest position the parser
+    /// got to and listing what it expected there.
+    pub fn from_pest(source: &str, error: &Error<Rule>) -> Self {
+        // Whitespace is skipped before any token is tried, so attempts ending on it come from
+        // lookaheads, like the one keeping keywords out of column names.
+        let attempts = error
+            .parse_attempts()
+            .filter(|attempts| !source[attempts.max_position..].starts_with(char::is_whitespace));
+
+        let (start, tokens, expected) = match attempts {
+            Some(attempts) => {
+                let tokens = attempts
+                    .expected_tokens()
+                    .iter()
+                    .map(ToString::to_string)
+                    .filter(|token| is_keyword_token(token))
+                    .collect::<Vec<_>>();
+                let expected = match tokens.is_empty() {
+                    true => attempts
+                        .call_stacks()
+                        .iter()
+                        .filter_map(|stack| stack.parent)
+                        .map(describe)
+                        .collect(),
+                    false => tokens.iter().map(|token| format!("`{token}`")).collect(),
+                };
+                (attempts.max_position, tokens, expected)
+            }
+            None => {
+                let start = match error.location {
+                    InputLocation::Pos(start) | InputLocation::Span((start, _)) => start,
+                };
+                let expected = match &error.variant {
+                    ErrorVariant::ParsingError { positives, .. } => {
+                        positives.iter().map(|rule| describe(*rule)).collect()
+                    }
+                    ErrorVariant::CustomError { .. } => vec![],
+                };
+                (start, vec![], expected)
+            }
+        };
+
+        let word = word_at(source, start);
+        let message = match word {
+            "" => "Unexpected end of query".to_string(),
+            word => format!("Unexpected `{word}`"),
+        };
+        let suggestion = closest(word, tokens.iter().map(String::as_str)).map(str::to_string);
+
+        let mut diagnostic = Diagnostic::new(source, start, word.len(), message);
+        diagnostic.expected = dedup(expected);
+        diagnostic.suggestion = suggestion;
+        diagnostic
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<impl Into<String>>) -> Self {
+        self.suggestion = suggestion.map(Into::into);
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "{}", self.message)?;
+        writeln!(f, "{gutter}--> line {}, column {}", self.line, self.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(self.column - 1),
+            "^".repeat(self.length)
+        )?;
+
+        if !self.expected.is_empty() {
+            write!(f, "\n{gutter} = expected {}", one_of(&self.expected))?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n{gutter} = did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+/// All the problems found in a program.
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, diagnostic) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+            write!(f, "{diagnostic}")?;
+        }
+        if self.0.len() > 1 {
+            write!(f, "\n\nFound {} problems", self.0.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// The candidate closest to `word`, if it's close enough to be a likely typo, which is at most
+/// one edit for every three characters. Candidates are also compared by their `_` separated
+/// suffixes, so `balance` suggests `sui_balance`.
+pub fn closest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    let threshold = word.chars().count() / 3;
+    if threshold == 0 {
+        return None;
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let lowercase = candidate.to_lowercase();
+            let distance = lowercase
+                .match_indices('_')
+                .map(|(i, _)| &lowercase[i + 1..])
+                .chain([lowercase.as_str()])
+                .map(|suffix| edit_distance(&word, suffix))
+                .min()?;
+            (distance <= threshold).then_some((distance, candidate))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Whether an expected token is worth listing: keywords, entities, chains and symbols, but
+/// not whitespace, comments or character ranges.
+fn is_keyword_token(token: &str) -> bool {
+    let is_range = token.chars().count() == 4 && token.contains("..");
+    !token.trim().is_empty() && token != "--" && token != "/*" && !is_range
+}
+
+/// The word starting at byte `start`, up to the next whitespace or separator.
+fn word_at(source: &str, start: usize) -> &str {
+    let rest = &source[start.min(source.len())..];
+    let end = rest
+        .find(|c: char| c.is_whitespace() || ",;()".contains(c))
+        .unwrap_or(rest.len());
+    match end {
+        0 => &rest[..rest.chars().next().map_or(0, char::len_utf8)],
+        end => &rest[..end],
+    }
+}
+
+fn one_of(expected: &[String]) -> String {
+    let tokens = expected.iter().all(|item| item.starts_with('`'));
+    match expected {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] if tokens => format!("one of {} or {}", rest.join(", "), last),
+        [rest @ .., last] => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+fn dedup(items: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = vec![];
+    for item in items {
+        if !unique.contains(&item) {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
+/// A plain words description of what a rule matches.
+fn describe(rule: Rule) -> String {
+    let description = match rule {
+        Rule::program | Rule::get | Rule::entity | Rule::subquery => "a query",
+        Rule::let_statement
+        | Rule::set_statement
+        | Rule::explain
+        | Rule::show_entities
+        | Rule::show_chains
+        | Rule::describe => "a statement",
+        Rule::distinct => "`DISTINCT`",
+        Rule::wildcard => "`*`",
+        Rule::account_fields
+        | Rule::checkpoint_fields
+        | Rule::tx_fields
+        | Rule::coin_fields
+        | Rule::object_fields
+        | Rule::name_fields
+        | Rule::event_fields
+        | Rule::join_fields
+        | Rule::select_item => "a list of fields",
+        Rule::join_entity | Rule::join_column => "a joined field, like `tx.sender`",
+        Rule::expr
+        | Rule::expr_filter
+        | Rule::neg
+        | Rule::not
+        | Rule::case_expr
+        | Rule::function
+        | Rule::function_ident
+        | Rule::column_ref => "an expression",
+        Rule::hex_literal
+        | Rule::number_literal
+        | Rule::string_literal
+        | Rule::bool_literal
+        | Rule::null_literal => "a value",
+        Rule::variable => "a `$variable`",
+        Rule::account_id | Rule::address => "an address",
+        Rule::quoted_ns | Rule::name_id => "a SuiNS name",
+        Rule::object_id | Rule::object => "an object id",
+        Rule::tx_id | Rule::hash => "a transaction digest",
+        Rule::coin_id | Rule::quoted_struct_tag | Rule::move_struct_tag => "a coin type",
+        Rule::checkpoint_id | Rule::checkpoint_range | Rule::checkpoint_tag_or_number => {
+            "a checkpoint number, range or tag"
+        }
+        Rule::integer | Rule::float => "a number",
+        Rule::boolean => "`true` or `false`",
+        Rule::chain_selector | Rule::chain_list | Rule::chain | Rule::chain_wildcard => "a chain",
+        Rule::rpc_url => "an RPC URL",
+        Rule::dump => "`>>`",
+        Rule::file_name => "a file name",
+        Rule::file_format => "`json`, `csv` or `parquet`",
+        Rule::EOI => "the end of the query",
+        rule => return format!("{rule:?}").replace('_', " "),
+    };
+    description.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interpreter::frontend::parser::Parser;
+    use pest::Parser as PestParser;
+
+    fn diagnostic(source: &str) -> Diagnostic {
+        pest::set_error_detail(true);
+        let error = Parser::parse(Rule::program, source).unwrap_err();
+        Diagnostic::from_pest(source, &error)
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = diagnostic("SELECT * FROM acount 0x2 ON sui_mainnet");
+        assert_eq!((error.line, error.column, error.length), (1, 15, 6));
+        assert_eq!(error.message, "Unexpected `acount`");
+        assert!(error.expected.contains(&"`account`".to_string()));
+        assert_eq!(error.suggestion.as_deref(), Some("account"));
+
+        let error = diagnostic("SELECT * FROM tx WHERE checkpoint = 1\nON 'sui_mainnet'");
+        assert_eq!((error.line, error.column), (2, 4));
+        assert_eq!(error.suggestion.as_deref(), Some("sui_mainnet"));
+        assert_eq!(
+            error.to_string(),
+            "Unexpected `'sui_mainnet'`
+ --> line 2, column 4
+  |
+2 | ON 'sui_mainnet'
+  |    ^^^^^^^^^^^^^
+  = expected one of `*`, `http://`, `https://`, `sui_devnet`, `sui_mainnet` or `sui_testnet`
+  = did you mean `sui_mainnet`?"
+        );
+
+        let error = diagnostic("SELECT * FROM account 0x12 ON sui_mainnet");
+        assert_eq!(error.column, 23);
+        assert_eq!(error.expected, vec!["a `$variable`", "an address"]);
+    }
+
+    #[test]
+    fn test_closest() {
+        let fields = ["address", "sui_balance", "coin_owned", "stake_amount"];
+        assert_eq!(closest("balanse", fields), Some("sui_balance"));
+        assert_eq!(closest("adress", fields), Some("address"));
+        assert_eq!(closest("gas", fields), None);
+    }
+}
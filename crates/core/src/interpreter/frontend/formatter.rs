@@ -0,0 +1,707 @@
+use super::parser::Parser;
+use crate::common::literal::literal_end;
+use anyhow::Result;
+use std::{borrow::Cow, ops::Range, sync::OnceLock};
+
+/// Statements longer than this are broken into one line per clause.
+const MAX_WIDTH: usize = 100;
+/// Lists that don't fit in their clause line go on with a line per item, indented by this.
+const INDENT: &str = "    ";
+
+/// The keywords are read from the grammar, so a keyword added there is formatted as one.
+const GRAMMAR: &str = include_str!("productions.pest");
+const OPERATOR_CHARS: &str = "=!<>|";
+/// Operators written with a space on both sides. Arithmetic operators are kept as they are
+/// written, as `-` is also part of names like `my-name.sui`.
+const SPACED_OPERATORS: &[&str] = &["=", "!=", "<>", "<", "<=", ">", ">=", "||", ">>"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum FormatterError {
+    #[error("Formatting changed the meaning of the program, it was left as it is")]
+    ChangedProgram,
+}
+
+/// Formats a program: keywords in upper case, one statement per line ending with `;`, a line
+/// per clause for statements that don't fit in a line, and single spaces between tokens.
+/// Comments are kept where they were, and computed columns without an alias are kept as they
+/// are written, as they are named by their text.
+///
+/// Programs that parse are checked to parse to the same expressions once formatted. Programs
+/// with `$1` or `:name` placeholders can't be parsed without their values and aren't checked.
+/// `networks` are the networks of the config file the program can run on, like `localnet`.
+///
+/// The formatter works on the tokens of the program rather than printing its expressions: the
+/// expressions don't keep comments, and print literals and computed columns the way they are
+/// evaluated rather than the way they were written, which would rename unaliased columns.
+pub fn format_program(source: &str, networks: &[String]) -> Result<String> {
+    let tokens = tokenize(source);
+    let has_placeholders = tokens.iter().any(Token::is_placeholder);
+    let formatted = format_tokens(source, tokens);
+
+    if !has_placeholders {
+        let expressions = Parser::new(source)
+            .with_networks(networks.to_vec())
+            .parse_expressions()?;
+        let formatted_expressions = Parser::new(&formatted)
+            .with_networks(networks.to_vec())
+            .parse_expressions()
+            .map_err(|_| FormatterError::ChangedProgram)?;
+        if formatted_expressions != expressions {
+            return Err(FormatterError::ChangedProgram.into());
+        }
+    }
+
+    Ok(formatted)
+}
+
+/// What a span of a program is, for syntax highlighting.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Highlight {
+    Keyword,
+    String,
+    Number,
+    Operator,
+    Comment,
+}
+
+/// The spans of `source` worth highlighting, in order. Words are keywords where the formatter
+/// would upper case them, so aliases like `AS end` aren't.
+pub fn highlights(source: &str) -> Vec<(Range<usize>, Highlight)> {
+    tokenize(source)
+        .into_iter()
+        .filter_map(|token| {
+            let highlight = match token.kind {
+                TokenKind::Keyword => Highlight::Keyword,
+                TokenKind::Comment => Highlight::Comment,
+                TokenKind::Operator => Highlight::Operator,
+                TokenKind::Word if token.text.starts_with(['\'', '"']) => Highlight::String,
+                TokenKind::Word if token.text.starts_with(|c: char| c.is_ascii_digit()) => {
+                    Highlight::Number
+                }
+                _ => return None,
+            };
+            Some((token.start..token.end, highlight))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum TokenKind {
+    Word,
+    Keyword,
+    Operator,
+    Open,
+    Close,
+    Comma,
+    Semicolon,
+    Comment,
+}
+
+#[derive(Debug, Clone)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: Cow<'a, str>,
+    start: usize,
+    end: usize,
+    /// Whether the token is preceded by whitespace.
+    spaced: bool,
+    /// The line breaks between the token and the one before it.
+    line_breaks: usize,
+}
+
+impl Token<'_> {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Keyword && self.text == keyword
+    }
+
+    fn is_line_comment(&self) -> bool {
+        self.kind == TokenKind::Comment && self.text.starts_with("--")
+    }
+
+    /// Whether the token is a comment that starts its own line.
+    fn is_own_line_comment(&self) -> bool {
+        self.kind == TokenKind::Comment && (self.line_breaks > 0 || self.start == 0)
+    }
+
+    fn is_placeholder(&self) -> bool {
+        let mut chars = self.text.chars();
+        match (chars.next(), chars.next()) {
+            (Some('$'), Some(c)) => c.is_ascii_digit(),
+            (Some(':'), Some(c)) => c.is_ascii_alphabetic() || c == '_',
+            _ => false,
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut gap_start = 0;
+    let mut i = 0;
+
+    while let Some(c) = source[i..].chars().next() {
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+
+        let (kind, end) = match (literal_end(source, i), c) {
+            (Some(end), _) if "-/".contains(c) => (TokenKind::Comment, end),
+            (Some(end), _) => (TokenKind::Word, end),
+            (None, '(') => (TokenKind::Open, i + 1),
+            (None, ')') => (TokenKind::Close, i + 1),
+            (None, ',') => (TokenKind::Comma, i + 1),
+            (None, ';') => (TokenKind::Semicolon, i + 1),
+            (None, c) if OPERATOR_CHARS.contains(c) => {
+                let len = source[i..]
+                    .find(|c| !OPERATOR_CHARS.contains(c))
+                    .unwrap_or(source.len() - i);
+                (TokenKind::Operator, i + len)
+            }
+            (None, _) => (TokenKind::Word, word_end(source, i)),
+        };
+
+        let gap = &source[gap_start..i];
+        let mut token = Token {
+            kind,
+            text: Cow::Borrowed(&source[i..end]),
+            start: i,
+            end,
+            spaced: !gap.is_empty(),
+            line_breaks: gap.matches('\n').count(),
+        };
+        if let Some(keyword) = keywords()
+            .iter()
+            .find(|k| k.eq_ignore_ascii_case(&token.text))
+        {
+            if kind == TokenKind::Word && !is_literal_position(&tokens, keyword) {
+                token.kind = TokenKind::Keyword;
+                token.text = Cow::Borrowed(*keyword);
+            }
+        }
+
+        tokens.push(token);
+        i = end;
+        gap_start = end;
+    }
+
+    tokens
+}
+
+/// The end of the word starting at byte `start`. Struct tags keep their type arguments, which
+/// can have spaces and commas: `0x2::coin::Coin<0x2::sui::SUI>`.
+fn word_end(source: &str, start: usize) -> usize {
+    let mut angles = 0;
+
+    for (offset, c) in source[start..].char_indices() {
+        let i = start + offset;
+        let ends = match c {
+            '<' if source[start..i].contains("::") => {
+                angles += 1;
+                false
+            }
+            '>' if angles > 0 => {
+                angles -= 1;
+                false
+            }
+            _ if angles > 0 => c == '\n',
+            _ => {
+                c.is_whitespace()
+                    || "(),;".contains(c)
+                    || OPERATOR_CHARS.contains(c)
+                    || literal_end(source, i).is_some()
+            }
+        };
+        if ends && i > start {
+            return i;
+        }
+    }
+
+    source.len()
+}
+
+/// Whether the next word is an alias or a value of an `IN` list, which are never keywords.
+fn is_literal_position(tokens: &[Token], word: &str) -> bool {
+    let mut code = tokens.iter().rev().filter(|t| t.kind != TokenKind::Comment);
+    match code.next() {
+        Some(previous) if previous.is_keyword("AS") => true,
+        Some(previous) if matches!(previous.kind, TokenKind::Open | TokenKind::Comma) => {
+            if word == "SELECT" {
+                return false;
+            }
+            // Walk back to the `(` of the list, which follows `IN` and isn't a subquery.
+            let mut depth = 0;
+            let mut code = std::iter::once(previous).chain(code);
+            while let Some(token) = code.next() {
+                match token.kind {
+                    TokenKind::Close => depth += 1,
+                    TokenKind::Open if depth > 0 => depth -= 1,
+                    TokenKind::Open => return code.next().is_some_and(|t| t.is_keyword("IN")),
+                    TokenKind::Keyword if depth == 0 && token.text == "SELECT" => return false,
+                    _ => {}
+                }
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+enum Item<'a> {
+    Statement(Vec<Token<'a>>),
+    Comment(Token<'a>),
+}
+
+fn format_tokens<'a>(source: &'a str, tokens: Vec<Token<'a>>) -> String {
+    let items = split_statements(tokens);
+    let statements = items
+        .iter()
+        .filter(|item| matches!(item, Item::Statement(_)))
+        .count();
+    let mut lines: Vec<String> = vec![];
+    let mut index = 0;
+
+    for item in items {
+        match item {
+            Item::Comment(comment) if !comment.is_own_line_comment() && !lines.is_empty() => {
+                let line = lines.last_mut().expect("lines is not empty");
+                line.push(' ');
+                line.push_str(&comment.text);
+            }
+            Item::Comment(comment) => {
+                if comment.line_breaks > 1 && !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(comment.text.into_owned());
+            }
+            Item::Statement(tokens) => {
+                if tokens[0].line_breaks > 1 && !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                index += 1;
+                let tokens = keep_computed_columns(source, tokens);
+                lines.extend(format_statement(&tokens, index < statements));
+            }
+        }
+    }
+
+    match lines.is_empty() {
+        true => String::new(),
+        false => lines.join("\n") + "\n",
+    }
+}
+
+/// Splits the tokens into statements and the comments between them. Statements end with `;`,
+/// or where the next one starts.
+fn split_statements(tokens: Vec<Token>) -> Vec<Item> {
+    let mut items = vec![];
+    let mut statement: Vec<Token> = vec![];
+    let mut depth = 0;
+
+    for token in tokens {
+        match token.kind {
+            TokenKind::Semicolon if depth == 0 => {
+                close_statement(&mut items, &mut statement);
+                continue;
+            }
+            TokenKind::Keyword if depth == 0 && starts_statement(&statement, &token) => {
+                close_statement(&mut items, &mut statement);
+            }
+            TokenKind::Comment if statement.is_empty() => {
+                items.push(Item::Comment(token));
+                continue;
+            }
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => depth = usize::max(depth, 1) - 1,
+            _ => {}
+        }
+        statement.push(token);
+    }
+    close_statement(&mut items, &mut statement);
+
+    items
+}
+
+fn starts_statement(statement: &[Token], token: &Token) -> bool {
+    let previous = statement
+        .iter()
+        .rev()
+        .find(|t| t.kind != TokenKind::Comment);
+    statement_keywords().contains(&token.text.as_ref())
+        && previous.is_some_and(|previous| previous.text != "=" && !previous.is_keyword("EXPLAIN"))
+}
+
+/// Adds the statement to the items. Comments on their own line after the statement lead the
+/// next one, and `,` separators are dropped, as every statement is ended with `;`.
+fn close_statement<'a>(items: &mut Vec<Item<'a>>, statement: &mut Vec<Token<'a>>) {
+    let leading = statement
+        .iter()
+        .rposition(|t| t.kind != TokenKind::Comment)
+        .and_then(|last| (last + 1..statement.len()).find(|&i| statement[i].is_own_line_comment()))
+        .map(|start| statement.split_off(start))
+        .unwrap_or_default();
+    while let Some(last) = statement
+        .iter()
+        .rposition(|t| t.kind != TokenKind::Comment)
+        .filter(|&last| statement[last].kind == TokenKind::Comma)
+    {
+        statement.remove(last);
+    }
+
+    if statement.iter().any(|t| t.kind != TokenKind::Comment) {
+        items.push(Item::Statement(std::mem::take(statement)));
+    } else {
+        items.extend(statement.drain(..).map(Item::Comment));
+    }
+    items.extend(leading.into_iter().map(Item::Comment));
+}
+
+/// Replaces the select list columns without an alias by their text as it is written, since
+/// the text is the name of the column.
+fn keep_computed_columns<'a>(source: &'a str, tokens: Vec<Token<'a>>) -> Vec<Token<'a>> {
+    let mut columns = vec![];
+
+    for select in (0..tokens.len()).filter(|&i| tokens[i].is_keyword("SELECT")) {
+        let mut i = select + 1;
+        if tokens.get(i).is_some_and(|t| t.is_keyword("DISTINCT")) {
+            i += 1;
+            if tokens.get(i).is_some_and(|t| t.is_keyword("ON")) {
+                i += tokens[i..]
+                    .iter()
+                    .position(|t| t.kind == TokenKind::Close)
+                    .map_or(0, |close| close + 1);
+            }
+        }
+
+        let mut start = i;
+        let mut depth = 0;
+        let mut has_alias = false;
+        while let Some(token) = tokens.get(i) {
+            if depth == 0 && (token.kind == TokenKind::Comma || token.is_keyword("FROM")) {
+                // Comments after the column stay out of it, they can be line comments.
+                let end = (start..i)
+                    .rfind(|&j| tokens[j].kind != TokenKind::Comment)
+                    .map_or(start, |last| last + 1);
+                if !has_alias && end - start > 1 {
+                    columns.push(start..end);
+                }
+                if token.kind != TokenKind::Comma {
+                    break;
+                }
+                start = i + 1;
+                has_alias = false;
+            }
+            match token.kind {
+                TokenKind::Open => depth += 1,
+                TokenKind::Close if depth == 0 => break,
+                TokenKind::Close => depth -= 1,
+                _ => has_alias |= depth == 0 && token.is_keyword("AS"),
+            }
+            i += 1;
+        }
+    }
+
+    let mut kept = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        match columns.iter().find(|column| column.start == i) {
+            Some(column) => {
+                let (first, last) = (&tokens[column.start], &tokens[column.end - 1]);
+                kept.push(Token {
+                    kind: TokenKind::Word,
+                    text: Cow::Borrowed(&source[first.start..last.end]),
+                    end: last.end,
+                    ..first.clone()
+                });
+                i = column.end;
+            }
+            None => {
+                kept.push(tokens[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    kept
+}
+
+/// Formats a statement on a line, or on a line per clause when it doesn't fit or has line
+/// comments. Comments after the statement are kept on its last line.
+fn format_statement(tokens: &[Token], terminated: bool) -> Vec<String> {
+    let end = tokens
+        .iter()
+        .rposition(|t| t.kind != TokenKind::Comment)
+        .map_or(0, |last| last + 1);
+    let (body, trailing) = tokens.split_at(end);
+    let separator = match terminated {
+        true => ";",
+        false => "",
+    };
+
+    let mut lines = format_tokens_lines(body, false);
+    let fits = lines.len() == 1 && lines[0].len() + separator.len() <= MAX_WIDTH;
+    if !fits {
+        lines = format_tokens_lines(body, true);
+    }
+
+    let last = lines.last_mut().expect("statements have tokens");
+    last.push_str(separator);
+    for comment in trailing {
+        last.push(' ');
+        last.push_str(&comment.text);
+    }
+
+    lines
+}
+
+fn format_tokens_lines(tokens: &[Token], break_clauses: bool) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut previous: Option<&Token> = None;
+    let mut depth = 0;
+    let mut join_condition = false;
+    let mut after_comma = false;
+
+    for token in tokens {
+        let is_clause = match token.kind {
+            TokenKind::Keyword if depth == 0 => match token.text.as_ref() {
+                "FROM" | "WHERE" => true,
+                "JOIN" => {
+                    join_condition = true;
+                    true
+                }
+                // `JOIN ... ON` and `DISTINCT ON` don't start a clause, the `ON` of the chains does.
+                "ON" if join_condition => {
+                    join_condition = false;
+                    false
+                }
+                "ON" => !previous.is_some_and(|p| p.is_keyword("DISTINCT")),
+                _ => false,
+            },
+            TokenKind::Operator => depth == 0 && token.text == ">>",
+            _ => false,
+        };
+
+        let breaks = (break_clauses && is_clause) || token.is_own_line_comment();
+        // Comments after a `,` stay with the item before it.
+        let wraps = break_clauses
+            && after_comma
+            && token.kind != TokenKind::Comment
+            && line.len() + 1 + token.text.len() > MAX_WIDTH;
+        if !line.is_empty() {
+            if breaks {
+                lines.push(std::mem::take(&mut line));
+            } else if wraps {
+                lines.push(std::mem::take(&mut line));
+                line.push_str(INDENT);
+            } else if previous.is_some_and(|previous| is_spaced(previous, token)) {
+                line.push(' ');
+            }
+        }
+        line.push_str(&token.text);
+
+        if token.is_line_comment() || token.is_own_line_comment() {
+            lines.push(std::mem::take(&mut line));
+        }
+        match token.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => depth = usize::max(depth, 1) - 1,
+            _ => {}
+        }
+        if token.kind != TokenKind::Comment {
+            after_comma = depth == 0 && token.kind == TokenKind::Comma;
+        }
+        previous = Some(token);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn is_spaced(previous: &Token, token: &Token) -> bool {
+    use TokenKind::*;
+
+    match (previous.kind, token.kind) {
+        (_, Comma | Semicolon | Close) | (Open, _) => false,
+        (Comma | Comment | Keyword, _) | (_, Comment | Keyword) => true,
+        (Operator, _) if SPACED_OPERATORS.contains(&previous.text.as_ref()) => true,
+        (_, Operator) if SPACED_OPERATORS.contains(&token.text.as_ref()) => true,
+        _ => token.spaced,
+    }
+}
+
+/// The case-insensitive keywords of the grammar, written `^"SELECT"` there and printed in
+/// upper case. Literals such as `true` are written in lower case and aren't keywords.
+fn keywords() -> &'static [&'static str] {
+    static KEYWORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    KEYWORDS.get_or_init(|| {
+        let mut keywords = vec![];
+        for literal in GRAMMAR.split("^\"").skip(1) {
+            let keyword = literal.split('"').next().unwrap_or_default();
+            if !keyword.is_empty()
+                && keyword.chars().all(|c| c.is_ascii_uppercase())
+                && !keywords.contains(&keyword)
+            {
+                keywords.push(keyword);
+            }
+        }
+        keywords
+    })
+}
+
+/// The keywords statements start with, the first keyword of every statement of `program`.
+fn statement_keywords() -> &'static [&'static str] {
+    static STATEMENT_KEYWORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    STATEMENT_KEYWORDS.get_or_init(|| {
+        let statements = rule_body("program")
+            .and_then(|body| body.split_once('('))
+            .and_then(|(_, statements)| statements.split_once(')'))
+            .map_or("", |(statements, _)| statements);
+        let mut keywords = vec![];
+        for statement in statements.split('|') {
+            match first_keyword(statement.trim()) {
+                Some(keyword) if !keywords.contains(&keyword) => keywords.push(keyword),
+                _ => {}
+            }
+        }
+        keywords
+    })
+}
+
+/// The keyword a rule starts with, following the first rule of its first alternative.
+fn first_keyword(rule: &str) -> Option<&'static str> {
+    let first = rule_body(rule)?.trim_start();
+    match first.strip_prefix("^\"") {
+        Some(keyword) => keyword.split('"').next(),
+        None => {
+            let end = first
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(first.len());
+            (end > 0 && &first[..end] != rule)
+                .then(|| first_keyword(&first[..end]))
+                .flatten()
+        }
+    }
+}
+
+/// The text of the grammar after the `{` opening the definition of `rule`.
+fn rule_body(rule: &str) -> Option<&'static str> {
+    let mut offset = 0;
+    for line in GRAMMAR.split_inclusive('\n') {
+        let defines = line
+            .split_once('=')
+            .is_some_and(|(name, _)| name.trim() == rule);
+        if defines {
+            let definition = &GRAMMAR[offset..];
+            return definition.find('{').map(|start| &definition[start + 1..]);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grammar_keywords() {
+        let mut keywords = keywords().to_vec();
+        keywords.sort();
+        assert_eq!(
+            keywords,
+            vec![
+                "AND", "AS", "CASE", "CHAINS", "DESCRIBE", "DISTINCT", "ELSE", "END", "ENTITIES",
+                "EXPLAIN", "FROM", "IN", "JOIN", "LET", "NOT", "ON", "OR", "SELECT", "SET", "SHOW",
+                "THEN", "WHEN", "WHERE",
+            ]
+        );
+        assert_eq!(
+            statement_keywords(),
+            ["LET", "SET", "EXPLAIN", "SHOW", "DESCRIBE", "SELECT"]
+        );
+    }
+
+    #[test]
+    fn test_format_program() {
+        let source = "
+            -- Owners of a name
+            set chain = sui_mainnet
+            let owners = select address from name where name='example.sui' ;
+
+            select * from account $owners ,select digest , gas_budget-gas_used, gas_used as used
+            from tx where checkpoint=10 , gas_used>1000 /* expensive */ on sui_mainnet>>out.csv
+            select * from coin '0x2::coin::Coin<0x2::sui::SUI, 0x2::sui::SUI>' on sui_mainnet -- coins
+        ";
+        let expected = "\
+-- Owners of a name
+SET chain = sui_mainnet;
+LET owners = SELECT address FROM name WHERE name = 'example.sui';
+
+SELECT * FROM account $owners;
+SELECT digest, gas_budget-gas_used, gas_used AS used
+FROM tx
+WHERE checkpoint = 10, gas_used > 1000 /* expensive */
+ON sui_mainnet
+>> out.csv;
+SELECT * FROM coin '0x2::coin::Coin<0x2::sui::SUI, 0x2::sui::SUI>' ON sui_mainnet -- coins
+";
+
+        let formatted = format_program(source, &[]).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_program(&formatted, &[]).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_highlights() {
+        let source = "select 'a;b' as end from tx where gas_used>=10 -- expensive";
+        let spans = highlights(source)
+            .into_iter()
+            .map(|(range, highlight)| (&source[range], highlight))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                ("select", Highlight::Keyword),
+                ("'a;b'", Highlight::String),
+                ("as", Highlight::Keyword),
+                ("from", Highlight::Keyword),
+                ("where", Highlight::Keyword),
+                (">=", Highlight::Operator),
+                ("10", Highlight::Number),
+                ("-- expensive", Highlight::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_placeholders_and_lists() {
+        let source = "select * from tx where sender in (as, :owner) , checkpoint=$1 on sui_mainnet";
+        assert_eq!(
+            format_program(source, &[]).unwrap(),
+            "SELECT * FROM tx WHERE sender IN (as, :owner), checkpoint = $1 ON sui_mainnet\n"
+        );
+    }
+
+    #[test]
+    fn test_format_readme_and_examples() {
+        let readme = include_str!("../../../../../README.md");
+        let blocks = readme
+            .split("```sql")
+            .skip(1)
+            .map(|block| block.split("```").next().unwrap_or_default().to_string());
+        let examples = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples"))
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap());
+
+        for source in blocks.chain(examples) {
+            let formatted = format_program(&source, &[]).unwrap();
+            assert_eq!(
+                format_program(&formatted, &[]).unwrap(),
+                formatted,
+                "{source}"
+            );
+        }
+    }
+}
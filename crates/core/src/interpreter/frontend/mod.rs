@@ -1 +1,5 @@
+pub mod analyzer;
+pub mod completion;
+pub mod diagnostic;
+pub mod formatter;
 pub mod parser;
@@ -1,12 +1,22 @@
-use crate::common::types::Expression;
+use super::diagnostic::Diagnostic;
+use crate::common::{
+    chain::ChainOrRpc,
+    entity::Entity,
+    join::JoinEntity,
+    params::Params,
+    types::{Expression, GetExpression, LetStatement, SetStatement},
+};
 use anyhow::Result;
-use pest::Parser as PestParser;
+use pest::{iterators::Pairs, Parser as PestParser};
 use pest_derive::Parser as DeriveParser;
+use std::collections::HashMap;
 
 #[derive(DeriveParser)]
 #[grammar = "src/interpreter/frontend/productions.pest"]
 pub struct Parser<'a> {
     source: &'a str,
+    default_chains: Option<Vec<ChainOrRpc>>,
+    networks: Vec<String>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -15,23 +25,108 @@ pub enum ParserError {
     UnexpectedToken(String),
     #[error("Missing entity")]
     MissingEntity,
-    #[error("Missing entity_id {0}")]
-    PestCustomError(String),
+    #[error("{0}")]
+    Diagnostic(#[from] Diagnostic),
 }
 
 impl<'a> Parser<'a> {
     pub fn new(source: &'a str) -> Self {
-        Parser { source }
+        Parser {
+            source,
+            default_chains: None,
+            networks: vec![],
+        }
+    }
+
+    /// Runs the queries without `ON` on `chains` until a `SET chain` changes them.
+    pub fn with_default_chains(mut self, chains: Option<Vec<ChainOrRpc>>) -> Self {
+        self.default_chains = chains;
+        self
+    }
+
+    /// Accepts `networks` in `ON` and `SET chain` besides the public chains, like the networks
+    /// of the config file. The parser doesn't read the config itself.
+    pub fn with_networks(mut self, networks: Vec<String>) -> Self {
+        self.networks = networks;
+        self
     }
 
     pub fn parse_expressions(&self) -> Result<Vec<Expression>> {
+        self.parse_expressions_with_params(&Params::default())
+    }
+
+    /// Parses a program after binding its `$1` and `:name` placeholders to `params`.
+    /// `LET` variables and `SET` defaults are applied to the queries that follow them.
+    /// Errors are reported as a [`Diagnostic`] pointing at the statement they were found in.
+    pub fn parse_expressions_with_params(&self, params: &Params) -> Result<Vec<Expression>> {
+        let source = params.bind(self.source)?;
+        self.parse_bound(&source)
+            .map_err(|error| match error.downcast::<ParserError>() {
+                Ok(ParserError::Diagnostic(diagnostic)) => {
+                    ParserError::from(source.locate(diagnostic)).into()
+                }
+                Ok(error) => error.into(),
+                Err(error) => error,
+            })
+    }
+
+    fn parse_bound(&self, source: &str) -> Result<Vec<Expression>> {
         let mut expressions: Vec<Expression> = vec![];
-        let pairs = Parser::parse(Rule::program, self.source)?;
+        let mut variables: HashMap<String, Entity> = HashMap::new();
+        let mut default_chains = self.default_chains.clone();
+        let pairs = parse_program(source).map_err(ParserError::from)?;
 
         for pair in pairs {
+            let span = pair.as_span();
+            let at_statement =
+                |error: String| ParserError::from(Diagnostic::from_span(&span, error));
+
             match pair.as_rule() {
-                Rule::get => {
-                    expressions.push(Expression::Get(pair.into_inner().try_into()?));
+                Rule::get | Rule::explain => {
+                    let is_explain = pair.as_rule() == Rule::explain;
+                    let pairs = match is_explain {
+                        true => pair
+                            .into_inner()
+                            .next()
+                            .map(|get| get.into_inner())
+                            .ok_or_else(|| at_statement("Missing query".to_string()))?,
+                        false => pair.into_inner(),
+                    };
+                    let mut get =
+                        GetExpression::parse(pairs, default_chains.as_ref(), &self.networks)
+                            .map_err(|error| at_statement(error.to_string()))?;
+                    get.entity
+                        .bind_variables(&variables)
+                        .map_err(|error| at_statement(error.to_string()))?;
+                    expressions.push(match is_explain {
+                        true => Expression::Explain(get),
+                        false => Expression::Get(get),
+                    });
+                }
+                Rule::let_statement => {
+                    let mut statement = LetStatement::try_from(pair.into_inner())
+                        .map_err(|error| at_statement(error.to_string()))?;
+                    statement
+                        .entity
+                        .bind_variables(&variables)
+                        .map_err(|error| at_statement(error.to_string()))?;
+                    variables.insert(statement.name, statement.entity);
+                }
+                Rule::set_statement => {
+                    let statement = SetStatement::parse(pair.into_inner(), &self.networks)
+                        .map_err(|error| at_statement(error.to_string()))?;
+                    default_chains = Some(statement.chains);
+                }
+                Rule::show_entities => expressions.push(Expression::ShowEntities),
+                Rule::show_chains => expressions.push(Expression::ShowChains),
+                Rule::describe => {
+                    let entity = pair
+                        .into_inner()
+                        .next()
+                        .ok_or_else(|| at_statement("Missing entity".to_string()))?;
+                    let entity = JoinEntity::try_from(entity.as_str())
+                        .map_err(|error| at_statement(error.to_string()))?;
+                    expressions.push(Expression::Describe(entity));
                 }
                 _ => {
                     return Err(ParserError::UnexpectedToken(pair.as_str().to_string()).into());
@@ -43,6 +138,12 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Parses `source` into the statements of a program.
+pub fn parse_program(source: &str) -> Result<Pairs<'_, Rule>, Diagnostic> {
+    pest::set_error_detail(true);
+    Parser::parse(Rule::program, source).map_err(|error| Diagnostic::from_pest(source, &error))
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
@@ -1,10 +1,12 @@
 pub mod backend;
 pub mod frontend;
 
-use crate::common::{query_result::QueryResult, types::Expression};
+use crate::common::{
+    chain::ChainOrRpc, config::Config, params::Params, query_result::QueryResult, types::Expression,
+};
 use anyhow::Result;
 use backend::execution_engine::ExecutionEngine;
-use frontend::parser::Parser;
+use frontend::{analyzer::Analyzer, diagnostic::Diagnostics, parser::Parser};
 
 pub struct Interpreter;
 
@@ -18,16 +20,51 @@ pub enum InterpreterError {
 
 impl Interpreter {
     pub async fn run_program(source: &str) -> Result<Vec<QueryResult>> {
-        let exressions = Interpreter::run_frontend(source)?;
-        Interpreter::run_backend(exressions).await
+        Interpreter::run_program_with_params(source, &Params::default()).await
     }
 
-    fn run_frontend(source: &str) -> Result<Vec<Expression>> {
-        let expressions = Parser::new(source).parse_expressions()?;
+    /// Runs a program whose `$1` and `:name` placeholders are bound to `params`.
+    pub async fn run_program_with_params(
+        source: &str,
+        params: &Params,
+    ) -> Result<Vec<QueryResult>> {
+        let exressions = Interpreter::parse_program(source, params, None)?;
+        Interpreter::run_expressions(exressions).await
+    }
+
+    /// Checks and parses a program without running it. Queries without `ON` run on
+    /// `default_chains`, or on [`ChainOrRpc::defaults`] without them, unless a `SET chain`
+    /// before them says otherwise.
+    pub fn parse_program(
+        source: &str,
+        params: &Params,
+        default_chains: Option<Vec<ChainOrRpc>>,
+    ) -> Result<Vec<Expression>> {
+        let bound = params.bind(source)?;
+        let networks = Config::load().get_networks().unwrap_or_default();
+        Analyzer::new(&bound)
+            .with_networks(networks.clone())
+            .analyze()
+            .map_err(|Diagnostics(diagnostics)| {
+                Diagnostics(
+                    diagnostics
+                        .into_iter()
+                        .map(|diagnostic| bound.locate(diagnostic))
+                        .collect(),
+                )
+            })?;
+        let default_chains = match default_chains {
+            Some(chains) => chains,
+            None => ChainOrRpc::defaults()?,
+        };
+        let expressions = Parser::new(source)
+            .with_default_chains(Some(default_chains))
+            .with_networks(networks)
+            .parse_expressions_with_params(params)?;
         Ok(expressions)
     }
 
-    async fn run_backend(expressions: Vec<Expression>) -> Result<Vec<QueryResult>> {
+    pub async fn run_expressions(expressions: Vec<Expression>) -> Result<Vec<QueryResult>> {
         let result = ExecutionEngine::new().run(expressions).await?;
         Ok(result)
     }
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use sui_ql_core::{
+    common::config::Config,
+    interpreter::frontend::{
+        analyzer::Analyzer,
+        completion::{complete, hover, CompletionKind},
+        diagnostic::Diagnostic,
+        formatter::format_program,
+    },
+};
+use tokio::sync::RwLock;
+use tower_lsp::{
+    jsonrpc::Result,
+    lsp_types::{
+        CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams,
+        CompletionResponse, Diagnostic as LspDiagnostic, DiagnosticSeverity,
+        DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+        DocumentFormattingParams, Hover, HoverContents, HoverParams, HoverProviderCapability,
+        InitializeParams, InitializeResult, MarkupContent, MarkupKind, OneOf, Position, Range,
+        ServerCapabilities, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit,
+        Url,
+    },
+    Client, LanguageServer, LspService, Server,
+};
+
+/// Serves `.sui_ql` files to editors: diagnostics from the analyzer, completion and hover from
+/// the grammar and the schema of the entities, and formatting.
+struct Backend {
+    client: Client,
+    documents: RwLock<HashMap<Url, String>>,
+}
+
+impl Backend {
+    async fn update(&self, uri: Url, text: String, version: i32) {
+        let networks = Config::load().get_networks().unwrap_or_default();
+        let diagnostics = match Analyzer::new(&text).with_networks(networks).analyze() {
+            Ok(()) => vec![],
+            Err(diagnostics) => diagnostics.0.iter().map(to_lsp_diagnostic).collect(),
+        };
+        self.documents.write().await.insert(uri.clone(), text);
+        self.client
+            .publish_diagnostics(uri, diagnostics, Some(version))
+            .await;
+    }
+
+    async fn document(&self, uri: &Url) -> Option<String> {
+        self.documents.read().await.get(uri).cloned()
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![".".to_string(), " ".to_string()]),
+                    ..Default::default()
+                }),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                ..Default::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "sui-ql-lsp".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.update(document.uri, document.text, document.version)
+            .await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // The documents are synced in full, the last change is the whole text.
+        if let Some(change) = params.content_changes.pop() {
+            let document = params.text_document;
+            self.update(document.uri, change.text, document.version)
+                .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.write().await.remove(&uri);
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let Some(text) = self.document(&position.text_document.uri).await else {
+            return Ok(None);
+        };
+
+        let items = complete(&text, to_offset(&text, position.position))
+            .into_iter()
+            .map(|completion| CompletionItem {
+                label: completion.label,
+                kind: Some(completion_kind(completion.kind)),
+                detail: completion.detail,
+                ..Default::default()
+            })
+            .collect();
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let Some(text) = self.document(&position.text_document.uri).await else {
+            return Ok(None);
+        };
+
+        Ok(
+            hover(&text, to_offset(&text, position.position)).map(|value| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: None,
+            }),
+        )
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let Some(text) = self.document(&params.text_document.uri).await else {
+            return Ok(None);
+        };
+
+        // Programs with syntax errors are left as they are, the diagnostics point at them.
+        let networks = Config::load().get_networks().unwrap_or_default();
+        Ok(format_program(&text, &networks).ok().map(|formatted| {
+            let range = Range::new(Position::new(0, 0), to_position(&text, text.len()));
+            vec![TextEdit::new(range, formatted)]
+        }))
+    }
+}
+
+fn completion_kind(kind: CompletionKind) -> CompletionItemKind {
+    match kind {
+        CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+        CompletionKind::Entity => CompletionItemKind::CLASS,
+        CompletionKind::Field => CompletionItemKind::FIELD,
+        CompletionKind::Function => CompletionItemKind::FUNCTION,
+        CompletionKind::Chain => CompletionItemKind::ENUM_MEMBER,
+        CompletionKind::Operator => CompletionItemKind::OPERATOR,
+    }
+}
+
+fn to_lsp_diagnostic(diagnostic: &Diagnostic) -> LspDiagnostic {
+    // Diagnostics count 1-based characters, LSP positions count 0-based UTF-16 code units.
+    let character = |chars: usize| {
+        diagnostic
+            .snippet
+            .chars()
+            .take(chars)
+            .map(char::len_utf16)
+            .sum::<usize>() as u32
+    };
+    let line = (diagnostic.line as u32).saturating_sub(1);
+    let start = diagnostic.column.saturating_sub(1);
+    let range = Range::new(
+        Position::new(line, character(start)),
+        Position::new(line, character(start + diagnostic.length)),
+    );
+
+    let mut message = diagnostic.message.clone();
+    if !diagnostic.expected.is_empty() {
+        message.push_str(&format!("\nexpected {}", diagnostic.expected.join(", ")));
+    }
+    if let Some(suggestion) = &diagnostic.suggestion {
+        message.push_str(&format!("\ndid you mean `{suggestion}`?"));
+    }
+
+    LspDiagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("sui-ql".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// The byte offset of an LSP position, clamped to the end of its line.
+fn to_offset(text: &str, position: Position) -> usize {
+    let line_start = text
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum::<usize>();
+    let line = text[line_start..].split('\n').next().unwrap_or_default();
+
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// The LSP position of a byte offset.
+fn to_position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+#[tokio::main]
+async fn main() {
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        documents: RwLock::new(HashMap::new()),
+    });
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // `é` is two bytes and one UTF-16 code unit, `😀` is four bytes and two code units.
+    const TEXT: &str = "SELECT 'é😀' FROM tx\nWHERE x = 1";
+
+    #[test]
+    fn test_offsets_of_positions() {
+        let emoji = TEXT.find('😀').unwrap();
+        assert_eq!(to_offset(TEXT, Position::new(0, 9)), emoji);
+        assert_eq!(
+            to_offset(TEXT, Position::new(0, 11)),
+            emoji + '😀'.len_utf8()
+        );
+        // Positions past the end of a line are clamped to it.
+        assert_eq!(
+            to_offset(TEXT, Position::new(0, 100)),
+            TEXT.find('\n').unwrap()
+        );
+        // The last line doesn't end with a newline.
+        assert_eq!(
+            to_offset(TEXT, Position::new(1, 6)),
+            TEXT.find("x =").unwrap()
+        );
+        assert_eq!(to_offset(TEXT, Position::new(1, 100)), TEXT.len());
+        assert_eq!(to_offset(TEXT, Position::new(5, 0)), TEXT.len());
+    }
+
+    #[test]
+    fn test_positions_of_offsets() {
+        let emoji = TEXT.find('😀').unwrap();
+        assert_eq!(to_position(TEXT, emoji), Position::new(0, 9));
+        assert_eq!(to_position(TEXT, TEXT.len()), Position::new(1, 11));
+
+        for offset in [0, emoji, TEXT.find('\n').unwrap(), TEXT.len()] {
+            assert_eq!(to_offset(TEXT, to_position(TEXT, offset)), offset);
+        }
+    }
+
+    #[test]
+    fn test_lsp_diagnostics() {
+        let source = "SELECT 'é😀', balanse FROM tx";
+        let start = source.find("balanse").unwrap();
+        let diagnostic = Diagnostic::new(source, start, "balanse".len(), "Unknown field")
+            .with_suggestion(Some("sui_balance"));
+
+        let lsp = to_lsp_diagnostic(&diagnostic);
+        assert_eq!(
+            lsp.range,
+            Range::new(Position::new(0, 14), Position::new(0, 21))
+        );
+        assert_eq!(lsp.message, "Unknown field\ndid you mean `sui_balance`?");
+
+        // A diagnostic without a line or column is put at the start of the document.
+        let diagnostic = Diagnostic {
+            line: 0,
+            column: 0,
+            ..diagnostic
+        };
+        assert_eq!(
+            to_lsp_diagnostic(&diagnostic).range.start,
+            Position::new(0, 0)
+        );
+    }
+}